@@ -1,9 +1,24 @@
+use crate::config::AppConfig;
 use anyhow::{Context, Result};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{SampleFormat, Stream, StreamConfig};
-use std::collections::VecDeque;
+use cpal::{
+    BufferSize, SampleFormat, SampleRate, Stream, StreamConfig, StreamError, SupportedBufferSize,
+    SupportedStreamConfig, SupportedStreamConfigRange,
+};
+use std::collections::{HashMap, VecDeque};
 use std::f32::consts::PI;
-use std::sync::{Arc, Mutex};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const PIPE_SAMPLE_RATE: u32 = 48_000;
+const PIPE_CHUNK: Duration = Duration::from_millis(10);
+/// How often a lost output device is looked for again.
+const OUTPUT_RETRY_INTERVAL: Duration = Duration::from_secs(2);
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum SoundId {
@@ -13,86 +28,728 @@ pub enum SoundId {
     AssocUp,
     DeauthZap,
     EapolMotif,
+    EapolM1,
+    EapolM2,
+    EapolM3,
+    EapolM4,
     RtsKnock,
     CtsKnockback,
     AckClick,
     DataTick,
     RetryGlitch,
+    FloodAlarm,
+    ApproachCue,
+    LeaveCue,
+    GeigerClick,
+    /// One chunk of the sustained background hiss standing in for ACKs.
+    AckHiss,
+    /// Stands in for repeats the rate limiter swallowed.
+    Burst,
+    /// Announces a channel switch with `CHANNEL_CUE=tone`.
+    ChannelCue,
+    /// The first frame from a MAC the tracker did not know.
+    NewDevice,
+}
+
+impl SoundId {
+    pub const ALL: [SoundId; 23] = [
+        SoundId::BeaconTick,
+        SoundId::ProbeChirp,
+        SoundId::ProbeReply,
+        SoundId::AssocUp,
+        SoundId::DeauthZap,
+        SoundId::EapolMotif,
+        SoundId::EapolM1,
+        SoundId::EapolM2,
+        SoundId::EapolM3,
+        SoundId::EapolM4,
+        SoundId::RtsKnock,
+        SoundId::CtsKnockback,
+        SoundId::AckClick,
+        SoundId::DataTick,
+        SoundId::RetryGlitch,
+        SoundId::FloodAlarm,
+        SoundId::ApproachCue,
+        SoundId::LeaveCue,
+        SoundId::GeigerClick,
+        SoundId::AckHiss,
+        SoundId::Burst,
+        SoundId::ChannelCue,
+        SoundId::NewDevice,
+    ];
+
+    /// Kebab-case name used by `AUDIO_WAVEFORMS`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SoundId::BeaconTick => "beacon-tick",
+            SoundId::ProbeChirp => "probe-chirp",
+            SoundId::ProbeReply => "probe-reply",
+            SoundId::AssocUp => "assoc-up",
+            SoundId::DeauthZap => "deauth-zap",
+            SoundId::EapolMotif => "eapol-motif",
+            SoundId::EapolM1 => "eapol-m1",
+            SoundId::EapolM2 => "eapol-m2",
+            SoundId::EapolM3 => "eapol-m3",
+            SoundId::EapolM4 => "eapol-m4",
+            SoundId::RtsKnock => "rts-knock",
+            SoundId::CtsKnockback => "cts-knockback",
+            SoundId::AckClick => "ack-click",
+            SoundId::DataTick => "data-tick",
+            SoundId::RetryGlitch => "retry-glitch",
+            SoundId::FloodAlarm => "flood-alarm",
+            SoundId::ApproachCue => "approach-cue",
+            SoundId::LeaveCue => "leave-cue",
+            SoundId::GeigerClick => "geiger-click",
+            SoundId::AckHiss => "ack-hiss",
+            SoundId::Burst => "burst",
+            SoundId::ChannelCue => "channel-cue",
+            SoundId::NewDevice => "new-device",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|id| id.name() == name)
+    }
+}
+
+/// Oscillator shape for tonal sounds. Noise-based sounds ignore it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Waveform {
+    #[default]
+    Sine,
+    Square,
+    Triangle,
+    Saw,
+}
+
+impl Waveform {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sine" => Some(Waveform::Sine),
+            "square" => Some(Waveform::Square),
+            "triangle" => Some(Waveform::Triangle),
+            "saw" | "sawtooth" => Some(Waveform::Saw),
+            _ => None,
+        }
+    }
+
+    /// Value at `phase` cycles (only the fractional part matters), in -1..=1.
+    fn sample(self, phase: f32) -> f32 {
+        let phase = phase.fract();
+        match self {
+            Waveform::Sine => (2.0 * PI * phase).sin(),
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Triangle => 1.0 - 4.0 * ((phase + 0.25).fract() - 0.5).abs(),
+            Waveform::Saw => 2.0 * phase - 1.0,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct AudioHandle {
-    queue: Arc<Mutex<VecDeque<f32>>>,
-    palette: Arc<SoundPalette>,
+    mixer: Arc<Mutex<Mixer>>,
+    /// What `play` draws from: `base_palette`, or a copy with spaced tones.
+    palette: Arc<RwLock<Arc<SoundPalette>>>,
+    base_palette: Arc<SoundPalette>,
+    waveforms: Arc<HashMap<SoundId, Waveform>>,
+    sample_rate: u32,
+    /// Output has a left and right channel, so `pan` is honoured.
+    stereo: bool,
+    meter: Arc<OutputMeter>,
+    /// Sounds allowed to overlap before `play` drops new ones; 0 = no cap.
+    max_voices: usize,
+    health: Arc<StreamHealth>,
+}
+
+/// Level of the last buffer handed to the output, as `f32` bits so the
+/// realtime callback never waits on a reader.
+/// A sequence queued by `AudioHandle::self_test`.
+pub struct SelfTest {
+    pub queued: usize,
+    /// Until the last sound ends, plus one gap.
+    pub length: Duration,
+    /// Mixer `played` count once the last sound is out.
+    end: u64,
+}
+
+#[derive(Default)]
+pub struct OutputMeter {
+    rms: AtomicU32,
+    peak: AtomicU32,
+}
+
+impl OutputMeter {
+    fn store(&self, rms: f32, peak: f32) {
+        self.rms.store(rms.to_bits(), Ordering::Relaxed);
+        self.peak.store(peak.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn rms(&self) -> f32 {
+        f32::from_bits(self.rms.load(Ordering::Relaxed))
+    }
+
+    pub fn peak(&self) -> f32 {
+        f32::from_bits(self.peak.load(Ordering::Relaxed))
+    }
+}
+
+/// State of the cpal output stream. The pipe sink never reports a loss.
+#[derive(Default)]
+pub struct StreamHealth {
+    lost: AtomicBool,
+    rebuilds: AtomicU32,
+    last_error: Mutex<Option<String>>,
+}
+
+impl StreamHealth {
+    pub fn is_running(&self) -> bool {
+        !self.lost.load(Ordering::Relaxed)
+    }
+
+    /// Times the stream was rebuilt after losing its device.
+    pub fn rebuilds(&self) -> u32 {
+        self.rebuilds.load(Ordering::Relaxed)
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().ok().and_then(|err| err.clone())
+    }
+
+    fn record_error(&self, err: &StreamError) {
+        if let Ok(mut last) = self.last_error.lock() {
+            *last = Some(err.to_string());
+        }
+    }
 }
 
 pub struct AudioEngine {
     handle: AudioHandle,
-    _stream: Stream,
+    _output: Output,
+}
+
+/// Whatever keeps samples flowing out of the mixer; dropping it stops audio.
+enum Output {
+    Cpal { _supervisor: OutputSupervisor },
+    Pipe { _worker: thread::JoinHandle<()> },
+}
+
+/// What the output thread needs to open the default device again.
+struct CpalSettings {
+    host: Option<String>,
+    sample_rate: u32,
+    channels: Option<u16>,
+    buffer_frames: Option<u32>,
+    dither: bool,
+}
+
+enum OutputSignal {
+    Error(StreamError),
+    Stop,
+}
+
+/// Stops the output thread, and with it the stream, when dropped.
+struct OutputSupervisor {
+    signals: mpsc::Sender<OutputSignal>,
+    _worker: thread::JoinHandle<()>,
+}
+
+impl Drop for OutputSupervisor {
+    fn drop(&mut self) {
+        let _ = self.signals.send(OutputSignal::Stop);
+    }
+}
+
+/// Errors after which the stream will never produce audio again, as when a
+/// USB DAC is unplugged. Anything else is logged and the stream kept.
+fn is_fatal_stream_error(err: &StreamError) -> bool {
+    matches!(err, StreamError::DeviceNotAvailable)
+}
+
+/// Holds `stream` until it fails fatally, then calls `open` every `retry`
+/// until a replacement starts. Returns on `Stop`.
+fn supervise_output<S>(
+    stream: S,
+    mut open: impl FnMut() -> Result<S>,
+    signals: &mpsc::Receiver<OutputSignal>,
+    health: &StreamHealth,
+    retry: Duration,
+) {
+    let mut stream = Some(stream);
+    loop {
+        match signals.recv_timeout(retry) {
+            Ok(OutputSignal::Stop) | Err(RecvTimeoutError::Disconnected) => return,
+            Ok(OutputSignal::Error(err)) => {
+                tracing::error!("Audio stream error: {err}");
+                health.record_error(&err);
+                if is_fatal_stream_error(&err) && stream.take().is_some() {
+                    health.lost.store(true, Ordering::Relaxed);
+                    tracing::warn!(
+                        "Audio output lost; retrying the default device every {}s",
+                        retry.as_secs()
+                    );
+                }
+            }
+            Err(RecvTimeoutError::Timeout) if stream.is_none() => match open() {
+                Ok(rebuilt) => {
+                    stream = Some(rebuilt);
+                    health.lost.store(false, Ordering::Relaxed);
+                    health.rebuilds.fetch_add(1, Ordering::Relaxed);
+                    tracing::info!("Audio output restored");
+                }
+                Err(err) => tracing::debug!("Audio output still unavailable: {err:#}"),
+            },
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+    }
+}
+
+/// Builds and starts a stream on the host's current default output device.
+/// Stream errors are forwarded to `errors` for the supervisor to judge.
+fn open_default_stream(
+    settings: &CpalSettings,
+    mixer: &Arc<Mutex<Mixer>>,
+    errors: mpsc::Sender<OutputSignal>,
+) -> Result<Stream> {
+    let host = select_host(settings.host.as_deref());
+    let device = host
+        .default_output_device()
+        .context("No default output device available")?;
+    let config = select_output_config(&device, Some(settings.sample_rate), settings.channels)?;
+    let sample_rate = config.sample_rate().0;
+    if sample_rate != settings.sample_rate {
+        tracing::warn!(
+            "Output device runs at {sample_rate} Hz, not {} Hz; sounds will be off-pitch",
+            settings.sample_rate
+        );
+    }
+    let channels = config.channels() as usize;
+    tracing::info!(
+        "Audio output: {} Hz, {} channel(s), {:?}",
+        sample_rate,
+        channels,
+        config.sample_format()
+    );
+
+    let mut stream_config: StreamConfig = config.clone().into();
+    stream_config.buffer_size = select_buffer_size(settings.buffer_frames, config.buffer_size());
+    match stream_config.buffer_size {
+        BufferSize::Fixed(frames) => tracing::info!(
+            "Audio buffer: {frames} frames ({:.1} ms)",
+            frames as f32 * 1000.0 / sample_rate as f32
+        ),
+        BufferSize::Default => tracing::info!("Audio buffer: host default"),
+    }
+    let err_fn = move |err| {
+        let _ = errors.send(OutputSignal::Error(err));
+    };
+
+    let stream = match config.sample_format() {
+        SampleFormat::F32 => {
+            let mixer = Arc::clone(mixer);
+            device.build_output_stream(
+                &stream_config,
+                move |data: &mut [f32], _| write_samples_f32(data, channels, &mixer),
+                err_fn,
+                None,
+            )?
+        }
+        SampleFormat::I16 => {
+            let mixer = Arc::clone(mixer);
+            let mut dither = settings.dither.then(Dither::new);
+            device.build_output_stream(
+                &stream_config,
+                move |data: &mut [i16], _| {
+                    write_samples_i16(data, channels, &mixer, dither.as_mut())
+                },
+                err_fn,
+                None,
+            )?
+        }
+        SampleFormat::U16 => {
+            let mixer = Arc::clone(mixer);
+            let mut dither = settings.dither.then(Dither::new);
+            device.build_output_stream(
+                &stream_config,
+                move |data: &mut [u16], _| {
+                    write_samples_u16(data, channels, &mixer, dither.as_mut())
+                },
+                err_fn,
+                None,
+            )?
+        }
+        other => return Err(anyhow::anyhow!("Unsupported sample format: {:?}", other)),
+    };
+
+    stream.play()?;
+    Ok(stream)
+}
+
+#[derive(Clone, Copy, Debug)]
+enum PcmFormat {
+    F32,
+    S16,
+}
+
+impl PcmFormat {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "f32" | "f32le" => Some(PcmFormat::F32),
+            "s16" | "s16le" => Some(PcmFormat::S16),
+            _ => None,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            PcmFormat::F32 => "f32le",
+            PcmFormat::S16 => "s16le",
+        }
+    }
 }
 
 #[derive(Clone)]
 struct SoundPalette {
-    sounds: std::collections::HashMap<SoundId, Vec<f32>>,
+    sounds: HashMap<SoundId, Vec<f32>>,
+}
+
+/// Pending stereo output frames (`[left, right]`), indexed from the read
+/// head. Sounds are summed into the buffer instead of appended, and the sum
+/// is soft-limited on the way out so bursts compress rather than hard-clip.
+#[derive(Default)]
+struct Mixer {
+    buffer: VecDeque<[f32; 2]>,
+    meter: Arc<OutputMeter>,
+    /// Frames handed to the output since start.
+    played: u64,
+    /// `played` value at which each sound started by `play` finishes.
+    voice_ends: Vec<u64>,
+    agc: Option<Agc>,
+    drone: Drone,
+    /// Output is silent until then while sounds keep draining underneath,
+    /// so nothing queued during the pause bursts out afterwards.
+    paused_until: Option<Instant>,
+}
+
+/// Where the activity drone is heading; `AudioHandle::set_drone` moves the
+/// target and the oscillator glides to it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DroneParams {
+    pub freq_hz: f32,
+    pub gain: f32,
+    /// Level of the 2nd and 3rd harmonics relative to the fundamental, 0–1.
+    pub brightness: f32,
+}
+
+/// Events per second at which the drone reaches its top pitch and level.
+const DRONE_MAX_RATE: f32 = 1000.0;
+const DRONE_BASE_HZ: f32 = 110.0;
+
+/// Maps a smoothed total event rate to drone parameters: two octaves of
+/// pitch above 110 Hz, a quiet-to-moderate level and an opening timbre,
+/// all on a log scale so a few events are audible and a storm saturates.
+pub fn drone_params(events_per_sec: f32) -> DroneParams {
+    let level = ((1.0 + events_per_sec.max(0.0)).ln() / (1.0 + DRONE_MAX_RATE).ln()).min(1.0);
+    DroneParams {
+        freq_hz: DRONE_BASE_HZ * 2f32.powf(2.0 * level),
+        gain: 0.03 + 0.09 * level,
+        brightness: level,
+    }
+}
+
+/// Continuous oscillator for the activity drone. Frequency, level and
+/// brightness glide towards the target over about 200 ms so updates never
+/// click; with no target the level fades to silence.
+#[derive(Default)]
+struct Drone {
+    target: Option<DroneParams>,
+    freq_hz: f32,
+    gain: f32,
+    brightness: f32,
+    phase: f32,
+    /// Per-frame glide coefficient.
+    smoothing: f32,
+    sample_rate: f32,
+}
+
+impl Drone {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            freq_hz: DRONE_BASE_HZ,
+            smoothing: (-1.0 / (0.2 * sample_rate as f32)).exp(),
+            sample_rate: sample_rate as f32,
+            ..Self::default()
+        }
+    }
+
+    fn next(&mut self) -> f32 {
+        let target = self.target.unwrap_or(DroneParams {
+            freq_hz: self.freq_hz,
+            gain: 0.0,
+            brightness: self.brightness,
+        });
+        if self.target.is_none() && self.gain < 1e-5 {
+            self.gain = 0.0;
+            return 0.0;
+        }
+        let glide = |current: f32, target: f32| target + (current - target) * self.smoothing;
+        self.freq_hz = glide(self.freq_hz, target.freq_hz);
+        self.gain = glide(self.gain, target.gain);
+        self.brightness = glide(self.brightness, target.brightness);
+        self.phase = (self.phase + self.freq_hz / self.sample_rate).fract();
+        let angle = 2.0 * PI * self.phase;
+        let harmonics = 0.5 * (2.0 * angle).sin() + 0.25 * (3.0 * angle).sin();
+        let wave = (angle.sin() + self.brightness * harmonics) / (1.0 + 0.75 * self.brightness);
+        wave * self.gain
+    }
+}
+
+/// Automatic gain control ahead of the limiter: follows the mean power of
+/// the output and steers a master gain towards a target RMS, so a quiet
+/// environment is brought up (to at most `max_gain`) and a busy one down.
+struct Agc {
+    target: f32,
+    max_gain: f32,
+    /// Per-frame smoothing coefficients for rising and falling levels.
+    attack: f32,
+    release: f32,
+    power: f32,
+    gain: f32,
+}
+
+impl Agc {
+    fn new(config: &AppConfig, sample_rate: u32) -> Self {
+        let coef = |ms: u64| (-1.0 / (ms.max(1) as f32 / 1000.0 * sample_rate as f32)).exp();
+        Self {
+            target: config.audio_agc_target,
+            max_gain: config.audio_agc_max_gain,
+            attack: coef(config.audio_agc_attack_ms),
+            release: coef(config.audio_agc_release_ms),
+            power: 0.0,
+            gain: 1.0,
+        }
+    }
+
+    fn process(&mut self, [left, right]: [f32; 2]) -> [f32; 2] {
+        let mid = (left + right) * 0.5;
+        let square = mid * mid;
+        let coef = if square > self.power {
+            self.attack
+        } else {
+            self.release
+        };
+        self.power = coef * self.power + (1.0 - coef) * square;
+        let rms = self.power.sqrt();
+        let desired = if rms > f32::EPSILON {
+            (self.target / rms).min(self.max_gain)
+        } else {
+            self.max_gain
+        };
+        // Cut gain at the attack rate and restore it at the release rate.
+        let coef = if desired < self.gain {
+            self.attack
+        } else {
+            self.release
+        };
+        self.gain = coef * self.gain + (1.0 - coef) * desired;
+        [left * self.gain, right * self.gain]
+    }
+}
+
+impl Mixer {
+    fn mix(&mut self, offset: usize, samples: &[f32], gain: f32, pan: f32) {
+        let end = offset + samples.len();
+        if self.buffer.len() < end {
+            self.buffer.resize(end, [0.0; 2]);
+        }
+        let [left, right] = pan_gains(pan);
+        for (i, sample) in samples.iter().enumerate() {
+            let frame = &mut self.buffer[offset + i];
+            frame[0] += *sample * gain * left;
+            frame[1] += *sample * gain * right;
+        }
+    }
+
+    /// Like `mix`, but resamples by `pitch` (2.0 = an octave up, half as long).
+    fn mix_pitched(&mut self, offset: usize, samples: &[f32], gain: f32, pitch: f32, pan: f32) {
+        if pitch <= 0.0 || (pitch - 1.0).abs() < f32::EPSILON {
+            return self.mix(offset, samples, gain, pan);
+        }
+        let len = (samples.len() as f32 / pitch) as usize;
+        if self.buffer.len() < offset + len {
+            self.buffer.resize(offset + len, [0.0; 2]);
+        }
+        let [left, right] = pan_gains(pan);
+        for i in 0..len {
+            let pos = i as f32 * pitch;
+            let idx = pos as usize;
+            let frac = pos - idx as f32;
+            let a = samples[idx];
+            let b = samples.get(idx + 1).copied().unwrap_or(0.0);
+            let v = (a + (b - a) * frac) * gain;
+            let frame = &mut self.buffer[offset + i];
+            frame[0] += v * left;
+            frame[1] += v * right;
+        }
+    }
+
+    /// Sounds from `play` still being drained.
+    fn active_voices(&mut self) -> usize {
+        let played = self.played;
+        self.voice_ends.retain(|end| *end > played);
+        self.voice_ends.len()
+    }
+
+    /// Whether a pause from `AudioHandle::pause_for` is still running,
+    /// clearing it once it has run out.
+    fn paused(&mut self) -> bool {
+        match self.paused_until {
+            Some(until) if Instant::now() < until => true,
+            Some(_) => {
+                self.paused_until = None;
+                false
+            }
+            None => false,
+        }
+    }
+
+    fn next_frame(&mut self) -> [f32; 2] {
+        self.played += 1;
+        let mut frame = self.buffer.pop_front().unwrap_or([0.0; 2]);
+        let drone = self.drone.next();
+        frame = [frame[0] + drone, frame[1] + drone];
+        if let Some(agc) = self.agc.as_mut() {
+            frame = agc.process(frame);
+        }
+        let [left, right] = frame;
+        [soft_limit(left), soft_limit(right)]
+    }
+}
+
+/// Balance-style pan: -1.0 is hard left, 1.0 hard right. The near side stays
+/// at unity so centred sounds are exactly as loud as before panning existed.
+fn pan_gains(pan: f32) -> [f32; 2] {
+    let pan = pan.clamp(-1.0, 1.0);
+    [(1.0 - pan).min(1.0), (1.0 + pan).min(1.0)]
 }
 
 impl AudioEngine {
-    pub fn new() -> Result<Self> {
-        let host = cpal::default_host();
+    pub fn new(config: &AppConfig) -> Result<Self> {
+        match config.audio_sink.as_str() {
+            "cpal" => Self::new_cpal(config),
+            "pipe" => Self::new_pipe(config),
+            other => {
+                tracing::warn!("Unknown AUDIO_SINK {other:?}, falling back to cpal");
+                Self::new_cpal(config)
+            }
+        }
+    }
+
+    fn new_cpal(app_config: &AppConfig) -> Result<Self> {
+        let host = select_host(app_config.audio_host.as_deref());
+        tracing::info!("Audio host: {}", host.id().name());
         let device = host
             .default_output_device()
             .context("No default output device available")?;
-        let config = device
-            .default_output_config()
-            .context("No default output config available")?;
-
+        let config = select_output_config(
+            &device,
+            app_config.audio_sample_rate,
+            app_config.audio_channels,
+        )?;
         let sample_rate = config.sample_rate().0;
-        let palette = Arc::new(build_palette(sample_rate));
-        let queue = Arc::new(Mutex::new(VecDeque::with_capacity(4096)));
-        let handle = AudioHandle {
-            queue: Arc::clone(&queue),
-            palette: palette.clone(),
-        };
-
-        let stream_config: StreamConfig = config.clone().into();
-        let err_fn = |err| tracing::error!("Audio stream error: {err}");
-
-        let stream = match config.sample_format() {
-            SampleFormat::F32 => {
-                let queue = Arc::clone(&queue);
-                device.build_output_stream(
-                    &stream_config,
-                    move |data: &mut [f32], _| write_samples_f32(data, &queue),
-                    err_fn,
-                    None,
-                )?
-            }
-            SampleFormat::I16 => {
-                let queue = Arc::clone(&queue);
-                device.build_output_stream(
-                    &stream_config,
-                    move |data: &mut [i16], _| write_samples_i16(data, &queue),
-                    err_fn,
-                    None,
-                )?
-            }
-            SampleFormat::U16 => {
-                let queue = Arc::clone(&queue);
-                device.build_output_stream(
-                    &stream_config,
-                    move |data: &mut [u16], _| write_samples_u16(data, &queue),
-                    err_fn,
-                    None,
-                )?
-            }
-            other => return Err(anyhow::anyhow!("Unsupported sample format: {:?}", other)),
-        };
-
-        stream.play()?;
+        let handle = AudioHandle::new(sample_rate, config.channels() >= 2, app_config);
+
+        // cpal streams can't move between threads, so one thread owns the
+        // stream for its whole life and rebuilds it when the device vanishes.
+        let settings = CpalSettings {
+            host: app_config.audio_host.clone(),
+            sample_rate,
+            channels: app_config.audio_channels,
+            buffer_frames: app_config.audio_buffer_frames,
+            dither: app_config.audio_dither,
+        };
+        let mixer = Arc::clone(&handle.mixer);
+        let health = Arc::clone(&handle.health);
+        let (signal_tx, signal_rx) = mpsc::channel();
+        let (ready_tx, ready_rx) = mpsc::sync_channel(1);
+        let errors = signal_tx.clone();
+        let worker = thread::Builder::new()
+            .name("audio-output".into())
+            .spawn(move || {
+                let open = || open_default_stream(&settings, &mixer, errors.clone());
+                let stream = match open() {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        let _ = ready_tx.send(Err(err));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+                supervise_output(stream, open, &signal_rx, &health, OUTPUT_RETRY_INTERVAL);
+            })?;
+        ready_rx
+            .recv()
+            .context("Audio output thread exited during startup")??;
 
         Ok(Self {
             handle,
-            _stream: stream,
+            _output: Output::Cpal {
+                _supervisor: OutputSupervisor {
+                    signals: signal_tx,
+                    _worker: worker,
+                },
+            },
+        })
+    }
+
+    /// Writes raw mono PCM to stdout (`-`) or a file/FIFO at a real-time
+    /// pace, for piping into tools like `sox` or `ffmpeg`.
+    fn new_pipe(config: &AppConfig) -> Result<Self> {
+        let format = PcmFormat::parse(&config.audio_pipe_format).with_context(|| {
+            format!(
+                "Unsupported AUDIO_PIPE_FORMAT {:?} (expected f32 or s16)",
+                config.audio_pipe_format
+            )
+        })?;
+        let path = config.audio_pipe_path.clone();
+        let dither = config.audio_dither.then(Dither::new);
+        let sample_rate = config.audio_sample_rate.unwrap_or(PIPE_SAMPLE_RATE);
+        let handle = AudioHandle::new(sample_rate, false, config);
+        let mixer = Arc::clone(&handle.mixer);
+
+        tracing::info!(
+            "Audio pipe sink: mono {} PCM at {} Hz to {}",
+            format.label(),
+            sample_rate,
+            if path == "-" { "stdout" } else { &path }
+        );
+
+        let worker = thread::spawn(move || {
+            // Opening a FIFO blocks until a reader attaches, so do it here.
+            let writer: Box<dyn Write + Send> = if path == "-" {
+                Box::new(io::stdout())
+            } else {
+                match OpenOptions::new().write(true).open(&path) {
+                    Ok(file) => Box::new(file),
+                    Err(err) => {
+                        tracing::error!("Unable to open audio pipe {path}: {err}");
+                        return;
+                    }
+                }
+            };
+            if let Err(err) = run_pipe(writer, &mixer, format, sample_rate, dither) {
+                tracing::error!("Audio pipe sink stopped: {err}");
+            }
+        });
+
+        Ok(Self {
+            handle,
+            _output: Output::Pipe { _worker: worker },
         })
     }
 
@@ -102,54 +759,394 @@ impl AudioEngine {
 }
 
 impl AudioHandle {
-    pub fn play(&self, id: SoundId, overlay_retry: bool, gain: f32) {
-        let mut guard = self.queue.lock().ok();
-        if let Some(queue) = guard.as_mut() {
-            if let Some(sound) = self.palette.sounds.get(&id) {
-                for sample in sound.iter() {
-                    queue.push_back(*sample * gain.clamp(0.0, 1.2));
-                }
+    pub fn new(sample_rate: u32, stereo: bool, config: &AppConfig) -> Self {
+        let waveforms = parse_waveforms(&config.audio_waveforms);
+        let base_palette = Arc::new(build_palette(sample_rate, &waveforms));
+        let meter = Arc::new(OutputMeter::default());
+        Self {
+            mixer: Arc::new(Mutex::new(Mixer {
+                buffer: VecDeque::with_capacity(4096),
+                meter: Arc::clone(&meter),
+                agc: config.audio_agc.then(|| Agc::new(config, sample_rate)),
+                drone: Drone::new(sample_rate),
+                ..Mixer::default()
+            })),
+            palette: Arc::new(RwLock::new(Arc::clone(&base_palette))),
+            base_palette,
+            waveforms: Arc::new(waveforms),
+            sample_rate,
+            stereo,
+            meter,
+            max_voices: config.audio_max_voices,
+            health: Arc::new(StreamHealth::default()),
+        }
+    }
+
+    /// Sets the activity drone's target; `None` fades it out.
+    pub fn set_drone(&self, params: Option<DroneParams>) {
+        if let Ok(mut mixer) = self.mixer.lock() {
+            mixer.drone.target = params;
+        }
+    }
+
+    /// Silences the output for `duration` without touching the audio
+    /// toggle; a zero duration ends a pause early.
+    pub fn pause_for(&self, duration: Duration) {
+        if let Ok(mut mixer) = self.mixer.lock() {
+            mixer.paused_until = (!duration.is_zero()).then(|| Instant::now() + duration);
+        }
+    }
+
+    /// Time left on the current pause; zero when not paused.
+    pub fn pause_remaining(&self) -> Duration {
+        self.mixer
+            .lock()
+            .ok()
+            .and_then(|mixer| mixer.paused_until)
+            .map(|until| until.saturating_duration_since(Instant::now()))
+            .unwrap_or_default()
+    }
+
+    pub fn stream_health(&self) -> &StreamHealth {
+        &self.health
+    }
+
+    pub fn meter(&self) -> Arc<OutputMeter> {
+        Arc::clone(&self.meter)
+    }
+
+    /// Queues every palette sound back to back, 150 ms apart, after
+    /// whatever is already pending.
+    pub fn self_test(&self) -> SelfTest {
+        let palette = self.palette();
+        let Ok(mut mixer) = self.mixer.lock() else {
+            return SelfTest {
+                queued: 0,
+                length: Duration::ZERO,
+                end: 0,
+            };
+        };
+        let gap = (self.sample_rate as usize * 150) / 1000;
+        let mut offset = mixer.buffer.len();
+        let mut end = offset;
+        let mut queued = 0;
+        for id in SoundId::ALL {
+            if let Some(sound) = palette.sounds.get(&id) {
+                mixer.mix(offset, sound, 1.0, 0.0);
+                end = offset + sound.len();
+                offset += sound.len() + gap;
+                queued += 1;
             }
-            if overlay_retry {
-                if let Some(glitch) = self.palette.sounds.get(&SoundId::RetryGlitch) {
-                    for sample in glitch.iter() {
-                        queue.push_back(*sample * gain.clamp(0.0, 1.2));
-                    }
-                }
+        }
+        SelfTest {
+            queued,
+            length: Duration::from_secs_f64(offset as f64 / self.sample_rate as f64),
+            end: mixer.played + end as u64,
+        }
+    }
+
+    /// Samples of `test` the output has yet to play. Sounds mixed in since
+    /// don't count, so a busy capture can't hold the check open.
+    pub fn self_test_remaining(&self, test: &SelfTest) -> u64 {
+        self.mixer
+            .lock()
+            .map(|mixer| test.end.saturating_sub(mixer.played))
+            .unwrap_or(test.end)
+    }
+
+    /// Queues `ids` with onsets `spacing` apart, after whatever is already
+    /// pending. Returns how long until the last one has finished.
+    pub fn play_sequence(&self, ids: &[SoundId], spacing: Duration) -> Duration {
+        let palette = self.palette();
+        let Ok(mut mixer) = self.mixer.lock() else {
+            return Duration::ZERO;
+        };
+        let step = (self.sample_rate as f64 * spacing.as_secs_f64()) as usize;
+        let start = mixer.buffer.len();
+        for (i, id) in ids.iter().enumerate() {
+            if let Some(sound) = palette.sounds.get(id) {
+                mixer.mix(start + i * step, sound, 1.0, 0.0);
+            }
+        }
+        Duration::from_secs_f64(mixer.buffer.len() as f64 / self.sample_rate as f64)
+    }
+
+    /// Sends `digits` as Morse code at about 20 words per minute, after
+    /// whatever is already pending. Characters other than digits are skipped.
+    pub fn play_morse_digits(&self, digits: &str) {
+        const DOT_MS: u64 = 60;
+        let dot = build_tick(self.sample_rate, Waveform::Sine, 700.0, DOT_MS, 0.14);
+        let dash = build_tick(self.sample_rate, Waveform::Sine, 700.0, DOT_MS * 3, 0.14);
+        let unit = (self.sample_rate as u64 * DOT_MS / 1000) as usize;
+        let Ok(mut mixer) = self.mixer.lock() else {
+            return;
+        };
+        let mut offset = mixer.buffer.len();
+        for code in digits.chars().filter_map(morse_digit) {
+            for symbol in code.chars() {
+                let tone = if symbol == '.' { &dot } else { &dash };
+                mixer.mix(offset, tone, 1.0, 0.0);
+                offset += tone.len() + unit;
+            }
+            // Three units between characters, one of which is already spent.
+            offset += unit * 2;
+        }
+    }
+
+    /// Samples mixed but not yet consumed by the output.
+    #[cfg(test)]
+    pub fn pending_samples(&self) -> usize {
+        self.mixer.lock().map(|m| m.buffer.len()).unwrap_or(0)
+    }
+
+    /// Mixes a palette sound in at `gain`, resampled by `pitch` (1.0 = as
+    /// built) and placed at `pan` (-1.0 left to 1.0 right; ignored on mono
+    /// outputs).
+    pub fn play(&self, id: SoundId, overlay_retry: bool, gain: f32, pitch: f32, pan: f32) {
+        let gain = gain.clamp(0.0, 1.2);
+        let pan = if self.stereo { pan } else { 0.0 };
+        let Ok(mut mixer) = self.mixer.lock() else {
+            return;
+        };
+        // Storms drop new sounds rather than piling them into mush.
+        if self.max_voices > 0 && mixer.active_voices() >= self.max_voices {
+            return;
+        }
+        // Mix at the read head so events that arrive together are heard
+        // together, and pending audio never grows past the longest sound.
+        let palette = self.palette();
+        if let Some(sound) = palette.sounds.get(&id) {
+            mixer.mix_pitched(0, sound, gain, pitch, pan);
+            let len = if pitch > 0.0 {
+                (sound.len() as f32 / pitch) as u64
+            } else {
+                sound.len() as u64
+            };
+            let end = mixer.played + len;
+            mixer.voice_ends.push(end);
+        }
+        if overlay_retry && let Some(glitch) = palette.sounds.get(&SoundId::RetryGlitch) {
+            mixer.mix(0, glitch, gain, pan);
+        }
+    }
+}
+
+/// Length and level of the plain tones `set_spaced_tones` substitutes.
+const SPACED_TONE_MS: u64 = 60;
+const SPACED_TONE_VOLUME: f32 = 0.12;
+
+/// `n` frequencies evenly spaced on a log (musical) scale from `low` to
+/// `high`, so neighbours are the same interval apart. A single one sits at
+/// the geometric middle.
+pub fn spaced_frequencies(n: usize, low: f32, high: f32) -> Vec<f32> {
+    match n {
+        0 => Vec::new(),
+        1 => vec![(low * high).sqrt()],
+        _ => (0..n)
+            .map(|i| low * (high / low).powf(i as f32 / (n - 1) as f32))
+            .collect(),
+    }
+}
+
+impl AudioHandle {
+    /// `id`'s buffer as played, before mixing and volume, as a 16-bit mono
+    /// WAV file.
+    pub fn sound_wav(&self, id: SoundId) -> Result<Vec<u8>> {
+        let palette = self.palette();
+        let samples = palette
+            .sounds
+            .get(&id)
+            .with_context(|| format!("{} is not in the palette", id.name()))?;
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut wav = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut wav, spec)?;
+        for v in samples {
+            writer.write_sample(quantize_i16(*v, None))?;
+        }
+        writer.finalize()?;
+        Ok(wav.into_inner())
+    }
+
+    fn palette(&self) -> Arc<SoundPalette> {
+        match self.palette.read() {
+            Ok(palette) => Arc::clone(&palette),
+            Err(_) => Arc::clone(&self.base_palette),
+        }
+    }
+
+    /// Replaces every sound in each group with one plain tone, the groups'
+    /// tones spread across `range` in order. An empty slice restores the
+    /// normal palette.
+    pub fn set_spaced_tones(&self, groups: &[Vec<SoundId>], (low, high): (f32, f32)) {
+        let mut palette = (*self.base_palette).clone();
+        let freqs = spaced_frequencies(groups.len(), low, high);
+        for (group, freq) in groups.iter().zip(freqs) {
+            for id in group {
+                let waveform = self.waveforms.get(id).copied().unwrap_or_default();
+                let tone = build_tick(
+                    self.sample_rate,
+                    waveform,
+                    freq,
+                    SPACED_TONE_MS,
+                    SPACED_TONE_VOLUME,
+                );
+                palette.sounds.insert(*id, tone);
             }
         }
+        if let Ok(mut current) = self.palette.write() {
+            *current = Arc::new(palette);
+        }
+    }
+}
+
+fn morse_digit(digit: char) -> Option<&'static str> {
+    Some(match digit {
+        '0' => "-----",
+        '1' => ".----",
+        '2' => "..---",
+        '3' => "...--",
+        '4' => "....-",
+        '5' => ".....",
+        '6' => "-....",
+        '7' => "--...",
+        '8' => "---..",
+        '9' => "----.",
+        _ => return None,
+    })
+}
+
+/// How `CHANNEL_CUE` announces a channel switch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChannelCueStyle {
+    #[default]
+    Off,
+    Tone,
+    /// The new channel number in Morse code.
+    Morse,
+}
+
+impl ChannelCueStyle {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "off" | "" => Some(ChannelCueStyle::Off),
+            "tone" => Some(ChannelCueStyle::Tone),
+            "morse" => Some(ChannelCueStyle::Morse),
+            _ => None,
+        }
+    }
+}
+
+impl AudioHandle {
+    pub fn announce_channel(&self, channel: u16, style: ChannelCueStyle) {
+        match style {
+            ChannelCueStyle::Off => {}
+            ChannelCueStyle::Tone => self.play(SoundId::ChannelCue, false, 1.0, 1.0, 0.0),
+            ChannelCueStyle::Morse => self.play_morse_digits(&channel.to_string()),
+        }
     }
 }
 
-fn build_palette(sample_rate: u32) -> SoundPalette {
+/// Parses `AUDIO_WAVEFORMS`: a bare waveform (or `*=shape`) applies to every
+/// tonal sound, `sound-name=shape` entries override single sounds.
+fn parse_waveforms(spec: &str) -> HashMap<SoundId, Waveform> {
+    let mut default = Waveform::Sine;
+    let mut overrides = HashMap::new();
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (target, shape) = match entry.split_once('=') {
+            Some((target, shape)) => (target.trim(), shape.trim()),
+            None => ("*", entry),
+        };
+        let Some(waveform) = Waveform::parse(shape) else {
+            tracing::warn!("Ignoring AUDIO_WAVEFORMS entry {entry:?}: unknown waveform");
+            continue;
+        };
+        if target == "*" {
+            default = waveform;
+        } else if let Some(id) = SoundId::parse(target) {
+            overrides.insert(id, waveform);
+        } else {
+            tracing::warn!("Ignoring AUDIO_WAVEFORMS entry {entry:?}: unknown sound");
+        }
+    }
+    SoundId::ALL
+        .into_iter()
+        .map(|id| (id, overrides.get(&id).copied().unwrap_or(default)))
+        .collect()
+}
+
+fn build_palette(sample_rate: u32, waveforms: &HashMap<SoundId, Waveform>) -> SoundPalette {
     use SoundId::*;
-    let mut sounds = std::collections::HashMap::new();
+    let mut sounds = HashMap::new();
+    let wave = |id| waveforms.get(&id).copied().unwrap_or_default();
+    let tick = |id, freq, duration_ms, volume| {
+        build_tick(sample_rate, wave(id), freq, duration_ms, volume)
+    };
+    let blip = |id, start, end, duration_ms, volume| {
+        build_blip(sample_rate, wave(id), start, end, duration_ms, volume)
+    };
 
-    sounds.insert(BeaconTick, build_tick(sample_rate, 660.0, 30, 0.08));
-    sounds.insert(ProbeChirp, build_tick(sample_rate, 1200.0, 24, 0.14));
-    sounds.insert(ProbeReply, build_tick(sample_rate, 960.0, 34, 0.14));
-    sounds.insert(AssocUp, build_blip(sample_rate, 520.0, 840.0, 50, 0.16));
+    sounds.insert(BeaconTick, tick(BeaconTick, 660.0, 30, 0.08));
+    sounds.insert(ProbeChirp, tick(ProbeChirp, 1200.0, 24, 0.14));
+    sounds.insert(ProbeReply, tick(ProbeReply, 960.0, 34, 0.14));
+    sounds.insert(AssocUp, blip(AssocUp, 520.0, 840.0, 50, 0.16));
     sounds.insert(DeauthZap, build_noise(sample_rate, 32, 0.4));
+    let eapol_notes = [640.0, 760.0, 880.0, 1020.0];
     sounds.insert(
         EapolMotif,
-        build_motif(sample_rate, &[640.0, 760.0, 880.0, 1020.0], 22, 0.12),
+        build_motif(sample_rate, wave(EapolMotif), &eapol_notes, 22, 0.12),
     );
-    sounds.insert(RtsKnock, build_tick(sample_rate, 360.0, 20, 0.12));
-    sounds.insert(CtsKnockback, build_tick(sample_rate, 480.0, 20, 0.12));
-    sounds.insert(AckClick, build_tick(sample_rate, 2200.0, 12, 0.04));
-    sounds.insert(DataTick, build_tick(sample_rate, 820.0, 16, 0.07));
+    // One motif note per handshake message, so M1-M4 play an ascending phrase.
+    for (id, freq) in [EapolM1, EapolM2, EapolM3, EapolM4]
+        .into_iter()
+        .zip(eapol_notes)
+    {
+        sounds.insert(id, tick(id, freq, 44, 0.12));
+    }
+    sounds.insert(RtsKnock, tick(RtsKnock, 360.0, 20, 0.12));
+    sounds.insert(CtsKnockback, tick(CtsKnockback, 480.0, 20, 0.12));
+    sounds.insert(AckClick, tick(AckClick, 2200.0, 12, 0.04));
+    sounds.insert(DataTick, tick(DataTick, 820.0, 16, 0.07));
     sounds.insert(RetryGlitch, build_noise(sample_rate, 10, 0.05));
+    sounds.insert(FloodAlarm, blip(FloodAlarm, 1400.0, 700.0, 180, 0.18));
+    sounds.insert(ApproachCue, blip(ApproachCue, 500.0, 750.0, 120, 0.1));
+    sounds.insert(LeaveCue, blip(LeaveCue, 750.0, 500.0, 120, 0.1));
+    sounds.insert(GeigerClick, build_noise(sample_rate, 3, 0.5));
+    sounds.insert(ChannelCue, blip(ChannelCue, 880.0, 1320.0, 90, 0.14));
+    sounds.insert(NewDevice, blip(NewDevice, 1760.0, 1760.0, 140, 0.12));
+    sounds.insert(
+        Burst,
+        build_motif(
+            sample_rate,
+            wave(Burst),
+            &[1100.0, 1250.0, 1400.0],
+            14,
+            0.09,
+        ),
+    );
+    sounds.insert(AckHiss, build_hiss(sample_rate, 120, 0.12));
 
     SoundPalette { sounds }
 }
 
-fn build_tick(sample_rate: u32, freq_hz: f32, duration_ms: u64, volume: f32) -> Vec<f32> {
+fn build_tick(
+    sample_rate: u32,
+    waveform: Waveform,
+    freq_hz: f32,
+    duration_ms: u64,
+    volume: f32,
+) -> Vec<f32> {
     let samples = ((sample_rate as u64 * duration_ms.max(1)) / 1000) as usize;
     let samples = samples.max(8);
     let envelope_len = (samples as f32 * 0.1).max(1.0) as usize;
+    let step = freq_hz / sample_rate as f32;
     let mut data = Vec::with_capacity(samples);
     for i in 0..samples {
-        let t = i as f32 / sample_rate as f32;
+        let phase = (i as f32 * step).fract();
         let env = if i < envelope_len {
             i as f32 / envelope_len as f32
         } else if i + envelope_len > samples {
@@ -157,7 +1154,7 @@ fn build_tick(sample_rate: u32, freq_hz: f32, duration_ms: u64, volume: f32) ->
         } else {
             1.0
         };
-        let val = (2.0 * PI * freq_hz * t).sin() * volume * env;
+        let val = waveform.sample(phase) * volume * env;
         data.push(val);
     }
     data
@@ -165,6 +1162,7 @@ fn build_tick(sample_rate: u32, freq_hz: f32, duration_ms: u64, volume: f32) ->
 
 fn build_blip(
     sample_rate: u32,
+    waveform: Waveform,
     start_freq: f32,
     end_freq: f32,
     duration_ms: u64,
@@ -173,21 +1171,30 @@ fn build_blip(
     let samples = ((sample_rate as u64 * duration_ms.max(1)) / 1000) as usize;
     let samples = samples.max(8);
     let mut data = Vec::with_capacity(samples);
+    // Accumulate phase so the sweep stays continuous; square and saw edges
+    // would otherwise jitter as the instantaneous frequency changes.
+    let mut phase = 0.0f32;
     for i in 0..samples {
-        let t = i as f32 / sample_rate as f32;
         let progress = i as f32 / samples as f32;
         let freq = start_freq + (end_freq - start_freq) * progress;
         let env = (1.0 - progress * 0.6).max(0.2);
-        let val = (2.0 * PI * freq * t).sin() * volume * env;
+        let val = waveform.sample(phase) * volume * env;
+        phase = (phase + freq / sample_rate as f32).fract();
         data.push(val);
     }
     data
 }
 
-fn build_motif(sample_rate: u32, freqs: &[f32], note_ms: u64, volume: f32) -> Vec<f32> {
+fn build_motif(
+    sample_rate: u32,
+    waveform: Waveform,
+    freqs: &[f32],
+    note_ms: u64,
+    volume: f32,
+) -> Vec<f32> {
     let mut data = Vec::new();
     for &freq in freqs {
-        let mut note = build_tick(sample_rate, freq, note_ms, volume);
+        let mut note = build_tick(sample_rate, waveform, freq, note_ms, volume);
         data.append(&mut note);
     }
     data
@@ -204,31 +1211,760 @@ fn build_noise(sample_rate: u32, duration_ms: u64, volume: f32) -> Vec<f32> {
         .collect()
 }
 
-fn pop_sample(queue: &Arc<Mutex<VecDeque<f32>>>) -> f32 {
-    queue
-        .lock()
-        .ok()
-        .and_then(|mut q| q.pop_front())
-        .unwrap_or(0.0)
-        .clamp(-1.0, 1.0)
+/// Flat noise with short fades at both ends, so back-to-back chunks blend
+/// into a steady bed instead of a string of hits.
+fn build_hiss(sample_rate: u32, duration_ms: u64, volume: f32) -> Vec<f32> {
+    let samples = ((sample_rate as u64 * duration_ms.max(1)) / 1000) as usize;
+    let fade = (sample_rate as usize * 15 / 1000).clamp(1, samples / 2 + 1);
+    let mut rng = rand::thread_rng();
+    (0..samples)
+        .map(|i| {
+            let edge = i.min(samples - 1 - i);
+            let env = (edge as f32 / fade as f32).min(1.0);
+            (rand::Rng::r#gen::<f32>(&mut rng) * 2.0 - 1.0) * volume * env
+        })
+        .collect()
+}
+
+const LIMITER_KNEE: f32 = 0.6;
+
+/// Linear below the knee, then a tanh curve that approaches but never
+/// reaches full scale.
+fn soft_limit(sample: f32) -> f32 {
+    let magnitude = sample.abs();
+    if magnitude <= LIMITER_KNEE {
+        return sample;
+    }
+    let headroom = 1.0 - LIMITER_KNEE;
+    let over = (magnitude - LIMITER_KNEE) / headroom;
+    (LIMITER_KNEE + headroom * over.tanh()).copysign(sample)
+}
+
+/// Writes one mixer frame per interleaved frame: left and right to the
+/// first two channels, their average to mono outputs and any extra channels.
+fn fill_samples<T: Copy>(
+    data: &mut [T],
+    channels: usize,
+    mixer: &Arc<Mutex<Mixer>>,
+    mut convert: impl FnMut(f32) -> T,
+) {
+    let mut guard = mixer.lock().ok();
+    let paused = guard.as_mut().is_some_and(|m| m.paused());
+    let mut sum_sq = 0.0;
+    let mut peak = 0.0f32;
+    let mut frames = 0;
+    for frame in data.chunks_mut(channels.max(1)) {
+        let [left, right] = match guard.as_mut().map(|m| m.next_frame()) {
+            Some(frame) if !paused => frame,
+            _ => [0.0; 2],
+        };
+        let mid = (left + right) * 0.5;
+        sum_sq += mid * mid;
+        peak = peak.max(left.abs()).max(right.abs());
+        frames += 1;
+        match frame {
+            [l, r, rest @ ..] => {
+                *l = convert(left);
+                *r = convert(right);
+                rest.fill(convert(mid));
+            }
+            _ => frame.fill(convert(mid)),
+        }
+    }
+    if let Some(m) = guard {
+        m.meter.store(rms(sum_sq, frames), peak);
+    }
+}
+
+fn rms(sum_sq: f32, count: usize) -> f32 {
+    if count == 0 {
+        return 0.0;
+    }
+    (sum_sq / count as f32).sqrt()
+}
+
+fn write_samples_f32(data: &mut [f32], channels: usize, mixer: &Arc<Mutex<Mixer>>) {
+    fill_samples(data, channels, mixer, |v| v);
+}
+
+fn write_samples_i16(
+    data: &mut [i16],
+    channels: usize,
+    mixer: &Arc<Mutex<Mixer>>,
+    mut dither: Option<&mut Dither>,
+) {
+    fill_samples(data, channels, mixer, |v| {
+        quantize_i16(v, dither.as_deref_mut())
+    });
+}
+
+fn write_samples_u16(
+    data: &mut [u16],
+    channels: usize,
+    mixer: &Arc<Mutex<Mixer>>,
+    mut dither: Option<&mut Dither>,
+) {
+    fill_samples(data, channels, mixer, |v| {
+        let scaled = (v + 1.0) * 0.5 * u16::MAX as f32;
+        match dither.as_deref_mut() {
+            Some(d) => (scaled + d.next()).round().clamp(0.0, u16::MAX as f32) as u16,
+            None => scaled as u16,
+        }
+    });
+}
+
+/// Without dither this truncates, as the output always has.
+fn quantize_i16(v: f32, dither: Option<&mut Dither>) -> i16 {
+    let scaled = v * i16::MAX as f32;
+    match dither {
+        Some(d) => (scaled + d.next())
+            .round()
+            .clamp(i16::MIN as f32, i16::MAX as f32) as i16,
+        None => scaled as i16,
+    }
+}
+
+/// Triangular-PDF dither of +/-1 LSB, the sum of two uniform values, from
+/// an xorshift generator cheap enough for the audio callback.
+struct Dither {
+    state: u32,
+}
+
+impl Dither {
+    fn new() -> Self {
+        Self {
+            state: rand::random::<u32>() | 1,
+        }
+    }
+
+    fn uniform(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        (self.state >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Next offset in LSBs, triangular over (-1, 1).
+    fn next(&mut self) -> f32 {
+        self.uniform() - self.uniform()
+    }
+}
+
+/// Opens the cpal host named by `AUDIO_HOST` (e.g. `alsa`, `jack`), or the
+/// platform default if unset, unknown or unavailable.
+fn select_host(requested: Option<&str>) -> cpal::Host {
+    let Some(name) = requested else {
+        return cpal::default_host();
+    };
+    let available = cpal::available_hosts();
+    let Some(id) = find_host_id(name, &available) else {
+        let names: Vec<&str> = available.iter().map(|id| id.name()).collect();
+        tracing::warn!(
+            "Audio host {name:?} not available (have: {}); using default",
+            names.join(", ")
+        );
+        return cpal::default_host();
+    };
+    match cpal::host_from_id(id) {
+        Ok(host) => host,
+        Err(err) => {
+            tracing::warn!(
+                "Unable to open audio host {}: {err}; using default",
+                id.name()
+            );
+            cpal::default_host()
+        }
+    }
+}
+
+fn find_host_id(name: &str, available: &[cpal::HostId]) -> Option<cpal::HostId> {
+    available
+        .iter()
+        .copied()
+        .find(|id| id.name().eq_ignore_ascii_case(name.trim()))
+}
+
+/// Uses the device default unless a rate or channel count is requested and
+/// the device advertises a matching config; otherwise warns and falls back.
+fn select_output_config(
+    device: &cpal::Device,
+    sample_rate: Option<u32>,
+    channels: Option<u16>,
+) -> Result<SupportedStreamConfig> {
+    let default = device
+        .default_output_config()
+        .context("No default output config available")?;
+    if sample_rate.is_none() && channels.is_none() {
+        return Ok(default);
+    }
+
+    let supported = device
+        .supported_output_configs()
+        .context("Unable to query supported output configs")?;
+    Ok(choose_output_config(
+        default,
+        supported,
+        sample_rate,
+        channels,
+    ))
+}
+
+fn choose_output_config(
+    default: SupportedStreamConfig,
+    supported: impl IntoIterator<Item = SupportedStreamConfigRange>,
+    sample_rate: Option<u32>,
+    channels: Option<u16>,
+) -> SupportedStreamConfig {
+    let rate = sample_rate.unwrap_or(default.sample_rate().0);
+    let channels = channels.unwrap_or(default.channels());
+    let mut candidates: Vec<SupportedStreamConfigRange> = supported
+        .into_iter()
+        .filter(|range| {
+            range.channels() == channels
+                && range.min_sample_rate().0 <= rate
+                && rate <= range.max_sample_rate().0
+        })
+        .collect();
+    // Prefer the sample format the device would have picked by default.
+    candidates.sort_by_key(|range| range.sample_format() != default.sample_format());
+
+    match candidates.into_iter().next() {
+        Some(range) => range.with_sample_rate(SampleRate(rate)),
+        None => {
+            tracing::warn!(
+                "Output device does not support {rate} Hz with {channels} channel(s); using default {} Hz / {} channel(s)",
+                default.sample_rate().0,
+                default.channels()
+            );
+            default
+        }
+    }
 }
 
-fn write_samples_f32(data: &mut [f32], queue: &Arc<Mutex<VecDeque<f32>>>) {
-    for sample in data.iter_mut() {
-        *sample = pop_sample(queue);
+/// Applies `AUDIO_BUFFER_FRAMES` within the range the chosen output config
+/// supports. Hosts that don't report a range get the request unchecked.
+fn select_buffer_size(requested: Option<u32>, supported: &SupportedBufferSize) -> BufferSize {
+    let Some(frames) = requested else {
+        return BufferSize::Default;
+    };
+    match *supported {
+        SupportedBufferSize::Range { min, max } => {
+            let clamped = frames.clamp(min, max);
+            if clamped != frames {
+                tracing::warn!(
+                    "AUDIO_BUFFER_FRAMES={frames} is outside the device's {min}-{max}; using {clamped}"
+                );
+            }
+            BufferSize::Fixed(clamped)
+        }
+        SupportedBufferSize::Unknown => {
+            tracing::warn!(
+                "Output device does not report a buffer size range; trying {frames} frames"
+            );
+            BufferSize::Fixed(frames)
+        }
     }
 }
 
-fn write_samples_i16(data: &mut [i16], queue: &Arc<Mutex<VecDeque<f32>>>) {
-    for sample in data.iter_mut() {
-        let v = pop_sample(queue);
-        *sample = (v * i16::MAX as f32) as i16;
+fn run_pipe(
+    mut writer: Box<dyn Write + Send>,
+    mixer: &Arc<Mutex<Mixer>>,
+    format: PcmFormat,
+    sample_rate: u32,
+    mut dither: Option<Dither>,
+) -> Result<()> {
+    let frames = (sample_rate as u128 * PIPE_CHUNK.as_millis() / 1000) as usize;
+    let mut samples = vec![0.0f32; frames];
+    let mut bytes = Vec::with_capacity(frames * 4);
+    let mut deadline = Instant::now();
+    loop {
+        write_samples_f32(&mut samples, 1, mixer);
+        bytes.clear();
+        match format {
+            PcmFormat::F32 => {
+                for v in &samples {
+                    bytes.extend_from_slice(&v.to_le_bytes());
+                }
+            }
+            PcmFormat::S16 => {
+                for v in &samples {
+                    bytes.extend_from_slice(&quantize_i16(*v, dither.as_mut()).to_le_bytes());
+                }
+            }
+        }
+        writer.write_all(&bytes)?;
+        writer.flush()?;
+
+        deadline += PIPE_CHUNK;
+        let now = Instant::now();
+        if deadline > now {
+            thread::sleep(deadline - now);
+        } else {
+            // Reader stalled; resync rather than bursting to catch up.
+            deadline = now;
+        }
     }
 }
 
-fn write_samples_u16(data: &mut [u16], queue: &Arc<Mutex<VecDeque<f32>>>) {
-    for sample in data.iter_mut() {
-        let v = pop_sample(queue);
-        *sample = ((v + 1.0) * 0.5 * u16::MAX as f32) as u16;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_loud_sounds_are_limited_not_clipped() {
+        let mut mixer = Mixer::default();
+        let loud = vec![0.9; 64];
+        mixer.mix(0, &loud, 1.0, 0.0);
+        mixer.mix(0, &loud, 1.0, 0.0);
+        for _ in 0..loud.len() {
+            let [sample, _] = mixer.next_frame();
+            assert!(
+                sample > 0.9,
+                "overlap should still be louder than one sound"
+            );
+            assert!(sample < 1.0, "limiter let {sample} reach full scale");
+        }
+    }
+
+    fn test_handle() -> AudioHandle {
+        AudioHandle::new(8000, true, &AppConfig::for_tests())
+    }
+
+    #[test]
+    fn simultaneous_plays_overlap() {
+        let handle = test_handle();
+        let len = handle.palette().sounds[&SoundId::BeaconTick].len();
+        handle.play(SoundId::BeaconTick, false, 1.0, 1.0, 0.0);
+        handle.play(SoundId::BeaconTick, false, 1.0, 1.0, 0.0);
+        let mixer = handle.mixer.lock().unwrap();
+        assert_eq!(mixer.buffer.len(), len);
+        let single = handle.palette().sounds[&SoundId::BeaconTick][len / 2];
+        assert_eq!(mixer.buffer[len / 2], [single * 2.0; 2]);
+    }
+
+    #[test]
+    fn exported_wav_matches_the_palette_buffer() {
+        let handle = test_handle();
+        let wav = handle.sound_wav(SoundId::BeaconTick).unwrap();
+        let reader = hound::WavReader::new(std::io::Cursor::new(wav)).unwrap();
+        assert_eq!(reader.spec().sample_rate, 8000);
+        assert_eq!(reader.spec().channels, 1);
+        let palette = handle.palette();
+        let expected = &palette.sounds[&SoundId::BeaconTick];
+        assert_eq!(reader.len() as usize, expected.len());
+        let samples: Vec<i16> = reader.into_samples().map(Result::unwrap).collect();
+        let peak = samples.iter().map(|s| s.unsigned_abs()).max().unwrap();
+        assert!(peak > 0, "exported tick is silent");
+    }
+
+    /// Keeps the first write, then fails so `run_pipe` returns.
+    struct OneChunk(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for OneChunk {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut out = self.0.lock().unwrap();
+            if !out.is_empty() {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            out.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn pipe_sink_streams_the_played_samples() {
+        let handle = AudioHandle::new(PIPE_SAMPLE_RATE, false, &AppConfig::for_tests());
+        handle.play(SoundId::BeaconTick, false, 1.0, 1.0, 0.0);
+        let expected: Vec<f32> = handle.palette().sounds[&SoundId::BeaconTick]
+            .iter()
+            .map(|v| soft_limit(*v))
+            .collect();
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let writer = Box::new(OneChunk(Arc::clone(&written)));
+        assert!(
+            run_pipe(
+                writer,
+                &handle.mixer,
+                PcmFormat::F32,
+                PIPE_SAMPLE_RATE,
+                None
+            )
+            .is_err()
+        );
+        let written = written.lock().unwrap();
+        let samples: Vec<f32> = written
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(samples.len(), 480);
+        assert_eq!(samples[..], expected[..samples.len()]);
+    }
+
+    fn range(
+        channels: u16,
+        min: u32,
+        max: u32,
+        format: SampleFormat,
+    ) -> SupportedStreamConfigRange {
+        SupportedStreamConfigRange::new(
+            channels,
+            SampleRate(min),
+            SampleRate(max),
+            cpal::SupportedBufferSize::Unknown,
+            format,
+        )
+    }
+
+    #[test]
+    fn output_config_honours_supported_overrides() {
+        let default = SupportedStreamConfig::new(
+            2,
+            SampleRate(44_100),
+            cpal::SupportedBufferSize::Unknown,
+            SampleFormat::F32,
+        );
+        let supported = || {
+            vec![
+                range(1, 8_000, 48_000, SampleFormat::I16),
+                range(1, 8_000, 48_000, SampleFormat::F32),
+                range(2, 44_100, 96_000, SampleFormat::F32),
+            ]
+        };
+
+        let mono = choose_output_config(default.clone(), supported(), Some(16_000), Some(1));
+        assert_eq!(mono.channels(), 1);
+        assert_eq!(mono.sample_rate(), SampleRate(16_000));
+        assert_eq!(mono.sample_format(), SampleFormat::F32);
+
+        let rate_only = choose_output_config(default.clone(), supported(), Some(96_000), None);
+        assert_eq!(rate_only.channels(), 2);
+        assert_eq!(rate_only.sample_rate(), SampleRate(96_000));
+
+        let unsupported =
+            choose_output_config(default.clone(), supported(), Some(192_000), Some(1));
+        assert_eq!(unsupported, default);
+    }
+
+    #[test]
+    fn self_test_queues_every_sound() {
+        let handle = test_handle();
+        let test = handle.self_test();
+        assert_eq!(test.queued, SoundId::ALL.len());
+        let gap = 8000 * 150 / 1000;
+        let expected: usize = SoundId::ALL
+            .iter()
+            .map(|id| handle.palette().sounds[id].len() + gap)
+            .sum();
+        assert_eq!(handle.pending_samples(), expected - gap);
+        assert_eq!(
+            test.length,
+            Duration::from_secs_f64(expected as f64 / 8000.0)
+        );
+        assert_eq!(handle.self_test_remaining(&test), (expected - gap) as u64);
+    }
+
+    #[test]
+    fn self_test_passes_while_other_sounds_keep_arriving() {
+        let handle = test_handle();
+        let test = handle.self_test();
+        let sequence = handle.pending_samples();
+        // A running capture keeps mixing sounds in behind the sequence.
+        handle
+            .mixer
+            .lock()
+            .unwrap()
+            .mix(sequence, &[0.1; 800], 1.0, 0.0);
+        let play = |frames: usize| {
+            let mut mixer = handle.mixer.lock().unwrap();
+            for _ in 0..frames {
+                mixer.next_frame();
+            }
+        };
+        play(sequence - 1);
+        assert_eq!(handle.self_test_remaining(&test), 1);
+        play(1);
+        assert_eq!(handle.self_test_remaining(&test), 0);
+        assert_eq!(handle.pending_samples(), 800);
+    }
+
+    #[test]
+    fn panned_sounds_favour_one_ear_on_stereo_only() {
+        let stereo = test_handle();
+        stereo.play(SoundId::BeaconTick, false, 1.0, 1.0, -0.7);
+        let peak = |handle: &AudioHandle| {
+            let mixer = handle.mixer.lock().unwrap();
+            mixer.buffer.iter().fold([0.0f32; 2], |[l, r], [a, b]| {
+                [l.max(a.abs()), r.max(b.abs())]
+            })
+        };
+        let [left, right] = peak(&stereo);
+        assert!(left > right * 3.0, "left {left} vs right {right}");
+
+        let mono = AudioHandle::new(8000, false, &AppConfig::for_tests());
+        mono.play(SoundId::BeaconTick, false, 1.0, 1.0, -0.7);
+        let [left, right] = peak(&mono);
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn host_lookup_matches_names_case_insensitively() {
+        let hosts = cpal::ALL_HOSTS;
+        let first = hosts[0];
+        let shouted = format!(" {} ", first.name().to_ascii_uppercase());
+        assert_eq!(find_host_id(&shouted, hosts), Some(first));
+        assert_eq!(find_host_id(first.name(), &[]), None);
+        assert_eq!(find_host_id("no-such-host", hosts), None);
+    }
+
+    #[test]
+    fn rms_of_a_full_scale_square_is_one() {
+        assert_eq!(rms(0.0, 0), 0.0);
+        assert_eq!(rms(4.0, 4), 1.0);
+        assert!((rms(0.25 * 0.25 * 8.0, 8) - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn filling_a_buffer_updates_the_meter() {
+        let handle = test_handle();
+        let meter = handle.meter();
+        handle.mixer.lock().unwrap().mix(0, &[0.5; 32], 1.0, 0.0);
+        let mut out = [0.0f32; 64];
+        write_samples_f32(&mut out, 2, &handle.mixer);
+        assert!((meter.rms() - soft_limit(0.5)).abs() < 1e-6);
+        assert_eq!(meter.peak(), soft_limit(0.5));
+    }
+
+    #[test]
+    fn paused_output_is_silent_until_the_deadline() {
+        let handle = test_handle();
+        let mut out = [1.0f32; 64];
+        handle.pause_for(Duration::from_secs(60));
+        handle.mixer.lock().unwrap().mix(0, &[0.5; 32], 1.0, 0.0);
+        write_samples_f32(&mut out, 2, &handle.mixer);
+        assert!(out.iter().all(|v| *v == 0.0));
+        assert!(handle.pause_remaining() > Duration::ZERO);
+
+        // Once the deadline passes the pause clears and sound plays again.
+        handle.mixer.lock().unwrap().paused_until = Some(Instant::now());
+        handle.mixer.lock().unwrap().mix(0, &[0.5; 32], 1.0, 0.0);
+        write_samples_f32(&mut out, 2, &handle.mixer);
+        assert!(handle.mixer.lock().unwrap().paused_until.is_none());
+        assert_eq!(out[0], soft_limit(0.5));
+
+        handle.pause_for(Duration::from_secs(60));
+        handle.pause_for(Duration::ZERO);
+        assert_eq!(handle.pause_remaining(), Duration::ZERO);
+        handle.mixer.lock().unwrap().mix(0, &[0.5; 32], 1.0, 0.0);
+        write_samples_f32(&mut out, 2, &handle.mixer);
+        assert_eq!(out[0], soft_limit(0.5));
+    }
+
+    #[test]
+    fn sounds_beyond_the_voice_cap_are_dropped() {
+        let mut config = AppConfig::for_tests();
+        config.audio_max_voices = 2;
+        let handle = AudioHandle::new(8000, false, &config);
+        let len = handle.palette().sounds[&SoundId::BeaconTick].len();
+        let single = handle.palette().sounds[&SoundId::BeaconTick][len / 2];
+        for _ in 0..3 {
+            handle.play(SoundId::BeaconTick, false, 1.0, 1.0, 0.0);
+        }
+        let mut mixer = handle.mixer.lock().unwrap();
+        assert_eq!(mixer.buffer[len / 2], [single * 2.0; 2]);
+        assert_eq!(mixer.active_voices(), 2);
+
+        // Once both have drained, new sounds play again.
+        for _ in 0..len {
+            mixer.next_frame();
+        }
+        assert_eq!(mixer.active_voices(), 0);
+    }
+
+    #[test]
+    fn square_tick_peaks_at_full_volume_with_more_energy_than_sine() {
+        // 800 Hz at 8 kHz: ten samples per cycle, so sine never lands on its crest.
+        let sine = build_tick(8000, Waveform::Sine, 800.0, 100, 0.5);
+        let square = build_tick(8000, Waveform::Square, 800.0, 100, 0.5);
+        let peak = |data: &[f32]| data.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        // The envelope ramps the first and last 10%; compare the flat middle.
+        let body = |data: &[f32]| {
+            let middle = &data[data.len() / 5..data.len() * 4 / 5];
+            rms(middle.iter().map(|s| s * s).sum(), middle.len())
+        };
+        assert_eq!(peak(&square), 0.5);
+        assert!(peak(&sine) < 0.5);
+        assert!((body(&square) - 0.5).abs() < 1e-3);
+        assert!((body(&square) / body(&sine) - 2f32.sqrt()).abs() < 0.01);
+    }
+
+    #[test]
+    fn buffer_size_is_clamped_to_the_supported_range() {
+        let range = SupportedBufferSize::Range { min: 64, max: 4096 };
+        assert_eq!(select_buffer_size(None, &range), BufferSize::Default);
+        assert_eq!(
+            select_buffer_size(Some(512), &range),
+            BufferSize::Fixed(512)
+        );
+        assert_eq!(select_buffer_size(Some(16), &range), BufferSize::Fixed(64));
+        assert_eq!(
+            select_buffer_size(Some(8192), &range),
+            BufferSize::Fixed(4096)
+        );
+        assert_eq!(
+            select_buffer_size(Some(8192), &SupportedBufferSize::Unknown),
+            BufferSize::Fixed(8192)
+        );
+    }
+
+    #[test]
+    fn agc_boosts_quiet_and_attenuates_loud_streams_towards_the_target() {
+        let mut config = AppConfig::for_tests();
+        config.audio_agc_target = 0.1;
+        config.audio_agc_max_gain = 8.0;
+        config.audio_agc_attack_ms = 10;
+        config.audio_agc_release_ms = 100;
+        // One second of a constant-level square wave at 8 kHz.
+        let settle = |level: f32| {
+            let mut agc = Agc::new(&config, 8000);
+            let mut out = [0.0; 2];
+            for i in 0..8000 {
+                let sample = if i % 2 == 0 { level } else { -level };
+                out = agc.process([sample; 2]);
+            }
+            out[0].abs()
+        };
+        let quiet = settle(0.02);
+        let loud = settle(0.8);
+        assert!(
+            quiet > 0.02 && (quiet - 0.1).abs() < 0.01,
+            "quiet -> {quiet}"
+        );
+        assert!(loud < 0.8 && (loud - 0.1).abs() < 0.01, "loud -> {loud}");
+        // Silence-level input is capped at the maximum gain.
+        assert!((settle(0.001) - 0.008).abs() < 0.001);
+    }
+
+    #[test]
+    fn dither_spreads_a_sub_lsb_signal_around_its_level() {
+        // A constant 0.3 LSB: truncation always yields 0.
+        let level = 0.3 / i16::MAX as f32;
+        assert!((0..1000).all(|_| quantize_i16(level, None) == 0));
+
+        let mut dither = Dither::new();
+        let out: Vec<i16> = (0..20_000)
+            .map(|_| quantize_i16(level, Some(&mut dither)))
+            .collect();
+        assert!(out.iter().all(|v| (-1..=1).contains(v)));
+        assert!(out.contains(&1) && out.contains(&0));
+        // On average the dithered output keeps the signal's level.
+        let mean = out.iter().map(|&v| f32::from(v)).sum::<f32>() / out.len() as f32;
+        assert!((mean - 0.3).abs() < 0.05, "mean {mean}");
+    }
+
+    #[test]
+    fn channel_change_enqueues_the_configured_cue() {
+        let handle = AudioHandle::new(8000, false, &AppConfig::for_tests());
+        handle.announce_channel(6, ChannelCueStyle::Off);
+        assert_eq!(handle.pending_samples(), 0);
+
+        handle.announce_channel(6, ChannelCueStyle::Tone);
+        let tone = handle.pending_samples();
+        assert_eq!(tone, handle.palette().sounds[&SoundId::ChannelCue].len());
+
+        // "-...." is one dash, four dots and the gaps between them: far longer
+        // than the single cue tone.
+        let morse = AudioHandle::new(8000, false, &AppConfig::for_tests());
+        morse.announce_channel(6, ChannelCueStyle::Morse);
+        assert!(morse.pending_samples() > tone * 2);
+    }
+
+    #[test]
+    fn output_is_rebuilt_only_after_a_fatal_stream_error() {
+        let health = Arc::new(StreamHealth::default());
+        let attempts = Arc::new(AtomicU32::new(0));
+        let (signals, signal_rx) = mpsc::channel();
+        let supervisor = {
+            let health = Arc::clone(&health);
+            let attempts = Arc::clone(&attempts);
+            thread::spawn(move || {
+                // The first reopen finds no device; the second succeeds.
+                let open = || match attempts.fetch_add(1, Ordering::Relaxed) {
+                    0 => Err(anyhow::anyhow!("No default output device available")),
+                    n => Ok(n),
+                };
+                supervise_output(0, open, &signal_rx, &health, Duration::from_millis(5));
+            })
+        };
+        let wait_for = |done: &dyn Fn() -> bool| {
+            for _ in 0..400 {
+                if done() {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(5));
+            }
+            panic!("supervisor never got there");
+        };
+
+        let xrun = StreamError::BackendSpecific {
+            err: cpal::BackendSpecificError {
+                description: "underrun".into(),
+            },
+        };
+        signals.send(OutputSignal::Error(xrun)).unwrap();
+        wait_for(&|| health.last_error().is_some());
+        thread::sleep(Duration::from_millis(30));
+        assert!(health.is_running());
+        assert_eq!(attempts.load(Ordering::Relaxed), 0);
+
+        signals
+            .send(OutputSignal::Error(StreamError::DeviceNotAvailable))
+            .unwrap();
+        wait_for(&|| health.rebuilds() == 1);
+        assert!(health.is_running());
+        assert_eq!(attempts.load(Ordering::Relaxed), 2);
+
+        signals.send(OutputSignal::Stop).unwrap();
+        supervisor.join().unwrap();
+    }
+
+    #[test]
+    fn drone_rises_in_pitch_level_and_brightness_with_activity() {
+        let idle = drone_params(0.0);
+        assert_eq!(idle.freq_hz, DRONE_BASE_HZ);
+        assert_eq!(idle.brightness, 0.0);
+        assert!(idle.gain > 0.0);
+
+        let rates = [1.0, 10.0, 100.0, DRONE_MAX_RATE];
+        for pair in rates.windows(2) {
+            let (low, high) = (drone_params(pair[0]), drone_params(pair[1]));
+            assert!(high.freq_hz > low.freq_hz);
+            assert!(high.gain > low.gain);
+            assert!(high.brightness > low.brightness);
+        }
+        // Two octaves at the top, and a storm beyond it saturates.
+        let top = drone_params(DRONE_MAX_RATE);
+        assert!((top.freq_hz - DRONE_BASE_HZ * 4.0).abs() < 0.01);
+        assert_eq!(drone_params(DRONE_MAX_RATE * 10.0), top);
+    }
+
+    #[test]
+    fn spaced_frequencies_rise_by_one_constant_interval() {
+        assert!(spaced_frequencies(0, 300.0, 2400.0).is_empty());
+        assert_eq!(spaced_frequencies(1, 300.0, 2700.0), vec![900.0]);
+        for n in 2..=14 {
+            let freqs = spaced_frequencies(n, 300.0, 2400.0);
+            assert_eq!(freqs.len(), n);
+            assert!((freqs[0] - 300.0).abs() < 0.01);
+            assert!((freqs[n - 1] - 2400.0).abs() < 0.1);
+            let ratio = freqs[1] / freqs[0];
+            for pair in freqs.windows(2) {
+                assert!(pair[1] > pair[0], "{n} tones: {freqs:?}");
+                assert!((pair[1] / pair[0] - ratio).abs() < 1e-4);
+            }
+        }
     }
 }
@@ -1,4 +1,10 @@
+use crate::audio::ChannelCueStyle;
+use crate::devices;
+use crate::events::DiscoveryMode;
+use crate::simulate;
 use std::env;
+use std::net::IpAddr;
+use std::str::FromStr;
 
 #[allow(dead_code)]
 pub struct AppConfig {
@@ -7,20 +13,324 @@ pub struct AppConfig {
     pub tick_frequency_hz: f32,
     pub tick_duration_ms: u64,
     pub tick_volume: f32,
+    pub audio_sink: String,
+    pub audio_pipe_path: String,
+    pub audio_pipe_format: String,
+    pub audio_sample_rate: Option<u32>,
+    pub audio_channels: Option<u16>,
+    /// Fixed cpal buffer size in frames; unset leaves the host default.
+    pub audio_buffer_frames: Option<u32>,
+    pub audio_host: Option<String>,
+    pub audio_max_voices: usize,
+    pub audio_waveforms: String,
+    pub audio_spaced_tones: Option<(f32, f32)>,
+    pub channel_cue: ChannelCueStyle,
+    /// Favourite channels, selectable by index via `/api/channel/preset`.
+    pub channel_presets: Vec<u16>,
+    /// TPDF dither before 16-bit quantization.
+    pub audio_dither: bool,
+    /// Automatic gain towards `audio_agc_target` RMS; off by default.
+    pub audio_agc: bool,
+    pub audio_agc_target: f32,
+    pub audio_agc_attack_ms: u64,
+    pub audio_agc_release_ms: u64,
+    pub audio_agc_max_gain: f32,
+    /// How far AP frames pan left and client frames pan right (0 = off).
+    pub audio_role_pan: f32,
+    pub max_devices: usize,
+    pub discovery_mode: DiscoveryMode,
+    pub cache_max_entries: usize,
+    /// Devices with fewer frames stay tracked but are left out of listings.
+    pub min_frames: u64,
+    pub ws_packets_reconnect_ms: u64,
+    pub ws_devices_reconnect_ms: u64,
+    pub ws_reconnect_max_ms: u64,
+    pub record_db: Option<String>,
+    /// Directory for complete 4-way handshakes as pcap; unset disables.
+    pub handshake_dir: Option<String>,
+    pub power_audit_log: Option<String>,
+    /// JSON file holding MAC aliases; they stay in memory only when unset.
+    pub device_aliases: Option<String>,
+    pub beacon_flood_threshold: usize,
+    pub probe_resp_flood_threshold: u32,
+    pub capture_watchdog_secs: u64,
+    /// pcap read timeout; bounds how long stopping an idle capture takes.
+    pub capture_timeout_ms: u64,
+    pub debug_recent_frames: usize,
+    pub http_compression: bool,
+    pub http_body_limit: usize,
+    pub http_timeout_secs: u64,
+    /// Extra origins allowed to call the API and open WebSockets; `*` allows
+    /// any. Empty means same-origin only.
+    pub allowed_origins: Vec<String>,
+    /// Networks besides loopback allowed to call `/api/shutdown`.
+    pub power_allowed_cidrs: Vec<IpCidr>,
+    /// The host's own MAC, whose frames are dropped from tracking and audio.
+    pub ignore_mac: Option<[u8; 6]>,
+    /// BCM pin pulsed by the GPIO sink (`gpio` feature builds only).
+    pub gpio_pin: Option<u8>,
+    pub gpio_kinds: String,
+    pub gpio_pulse_ms: u64,
+    /// `host[:port]` of the MQTT broker (`mqtt` feature builds only).
+    pub mqtt_broker: Option<String>,
+    pub mqtt_topic: String,
+    pub mqtt_client_id: String,
+    pub self_test: bool,
+    pub simulate: bool,
+    pub simulate_mix: String,
+    pub simulate_rate: u32,
+    /// CSS colours overriding the UI accent and page background.
+    pub theme_accent: Option<String>,
+    pub theme_background: Option<String>,
 }
 
 impl AppConfig {
     pub fn from_env() -> Self {
+        Self::from_vars(&|key| env::var(key).ok())
+    }
+
+    /// Defaults only, whatever the shell exports, so tests see the same
+    /// configuration everywhere.
+    #[cfg(test)]
+    pub fn for_tests() -> Self {
+        Self::from_vars(&|_| None)
+    }
+
+    fn from_vars(vars: Vars) -> Self {
         Self {
-            monitor_interface: env_var("MONITOR_INTERFACE", "wlan1mon"),
-            http_bind: env_var("HTTP_BIND", "0.0.0.0:8080"),
-            tick_frequency_hz: env_var("TICK_FREQUENCY_HZ", "880").parse().unwrap_or(880.0),
-            tick_duration_ms: env_var("TICK_DURATION_MS", "20").parse().unwrap_or(20),
-            tick_volume: env_var("TICK_VOLUME", "0.35").parse().unwrap_or(0.35),
+            monitor_interface: env_var(vars, "MONITOR_INTERFACE", "wlan1mon"),
+            http_bind: env_var(vars, "HTTP_BIND", "0.0.0.0:8080"),
+            tick_frequency_hz: env_var(vars, "TICK_FREQUENCY_HZ", "880")
+                .parse()
+                .unwrap_or(880.0),
+            tick_duration_ms: env_var(vars, "TICK_DURATION_MS", "20")
+                .parse()
+                .unwrap_or(20),
+            tick_volume: env_var(vars, "TICK_VOLUME", "0.35").parse().unwrap_or(0.35),
+            audio_sink: env_var(vars, "AUDIO_SINK", "cpal").to_ascii_lowercase(),
+            audio_pipe_path: env_var(vars, "AUDIO_PIPE_PATH", "-"),
+            audio_pipe_format: env_var(vars, "AUDIO_PIPE_FORMAT", "f32"),
+            audio_sample_rate: env_opt(vars, "AUDIO_SAMPLE_RATE"),
+            audio_channels: env_opt(vars, "AUDIO_CHANNELS"),
+            audio_buffer_frames: env_opt::<u32>(vars, "AUDIO_BUFFER_FRAMES")
+                .filter(|frames| *frames > 0),
+            audio_max_voices: env_var(vars, "AUDIO_MAX_VOICES", "8").parse().unwrap_or(8),
+            audio_waveforms: env_var(vars, "AUDIO_WAVEFORMS", "sine"),
+            audio_spaced_tones: freq_range(vars, "AUDIO_SPACED_TONES"),
+            channel_cue: {
+                let value = env_var(vars, "CHANNEL_CUE", "off");
+                ChannelCueStyle::parse(&value).unwrap_or_else(|| {
+                    tracing::warn!("Unknown CHANNEL_CUE {value:?}, using off");
+                    ChannelCueStyle::Off
+                })
+            },
+            channel_presets: env_var(vars, "CHANNEL_PRESETS", "")
+                .split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .filter_map(|c| {
+                    let channel = c.parse::<u16>().ok().filter(|ch| *ch > 0);
+                    if channel.is_none() {
+                        tracing::warn!("Ignoring invalid CHANNEL_PRESETS entry {c:?}");
+                    }
+                    channel
+                })
+                .collect(),
+            audio_dither: env_flag(vars, "AUDIO_DITHER", false),
+            audio_agc: env_flag(vars, "AUDIO_AGC", false),
+            audio_agc_target: env_opt::<f32>(vars, "AUDIO_AGC_TARGET")
+                .unwrap_or(0.05)
+                .clamp(0.001, 1.0),
+            audio_agc_attack_ms: env_var(vars, "AUDIO_AGC_ATTACK_MS", "50")
+                .parse()
+                .unwrap_or(50),
+            audio_agc_release_ms: env_var(vars, "AUDIO_AGC_RELEASE_MS", "3000")
+                .parse()
+                .unwrap_or(3000),
+            audio_agc_max_gain: env_opt::<f32>(vars, "AUDIO_AGC_MAX_GAIN")
+                .unwrap_or(4.0)
+                .max(1.0),
+            audio_host: vars("AUDIO_HOST").filter(|v| !v.trim().is_empty()),
+            audio_role_pan: env_opt::<f32>(vars, "AUDIO_ROLE_PAN")
+                .unwrap_or(0.7)
+                .clamp(0.0, 1.0),
+            max_devices: env_var(vars, "MAX_DEVICES", "0").parse().unwrap_or(0),
+            discovery_mode: {
+                let value = env_var(vars, "DISCOVERY_MODE", "full");
+                DiscoveryMode::parse(&value).unwrap_or_else(|| {
+                    tracing::warn!("Unknown DISCOVERY_MODE {value:?}, using full");
+                    DiscoveryMode::Full
+                })
+            },
+            cache_max_entries: env_var(vars, "CACHE_MAX_ENTRIES", "4096")
+                .parse()
+                .unwrap_or(4096),
+            min_frames: env_var(vars, "MIN_FRAMES", "1").parse().unwrap_or(1),
+            ws_packets_reconnect_ms: env_var(vars, "WS_PACKETS_RECONNECT_MS", "1500")
+                .parse()
+                .unwrap_or(1500),
+            ws_devices_reconnect_ms: env_var(vars, "WS_DEVICES_RECONNECT_MS", "2000")
+                .parse()
+                .unwrap_or(2000),
+            ws_reconnect_max_ms: env_var(vars, "WS_RECONNECT_MAX_MS", "30000")
+                .parse()
+                .unwrap_or(30000),
+            beacon_flood_threshold: env_var(vars, "BEACON_FLOOD_THRESHOLD", "50")
+                .parse()
+                .unwrap_or(50),
+            probe_resp_flood_threshold: env_var(vars, "PROBE_RESP_FLOOD_THRESHOLD", "100")
+                .parse()
+                .unwrap_or(100),
+            debug_recent_frames: env_var(vars, "DEBUG_RECENT_FRAMES", "0")
+                .parse()
+                .unwrap_or(0),
+            capture_watchdog_secs: env_var(vars, "CAPTURE_WATCHDOG_SECS", "60")
+                .parse()
+                .unwrap_or(60),
+            capture_timeout_ms: env_var(vars, "CAPTURE_TIMEOUT_MS", "1000")
+                .parse()
+                .unwrap_or(1000),
+            http_body_limit: env_var(vars, "HTTP_BODY_LIMIT", "65536")
+                .parse()
+                .unwrap_or(65536),
+            http_compression: env_flag(vars, "HTTP_COMPRESSION", true),
+            http_timeout_secs: env_var(vars, "HTTP_TIMEOUT_SECS", "30")
+                .parse()
+                .unwrap_or(30),
+            allowed_origins: env_var(vars, "ALLOWED_ORIGINS", "")
+                .split(',')
+                .map(|o| o.trim().trim_end_matches('/').to_string())
+                .filter(|o| !o.is_empty())
+                .collect(),
+            power_allowed_cidrs: env_var(vars, "POWER_ALLOWED_CIDRS", "")
+                .split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .filter_map(|c| {
+                    let cidr = IpCidr::parse(c);
+                    if cidr.is_none() {
+                        tracing::warn!("Ignoring invalid POWER_ALLOWED_CIDRS entry {c:?}");
+                    }
+                    cidr
+                })
+                .collect(),
+            ignore_mac: vars("IGNORE_MAC")
+                .filter(|v| !v.trim().is_empty())
+                .and_then(|v| {
+                    let mac = devices::parse_mac(v.trim());
+                    if mac.is_none() {
+                        tracing::warn!("Ignoring invalid IGNORE_MAC {v:?}");
+                    }
+                    mac
+                }),
+            gpio_pin: env_opt(vars, "GPIO_PIN"),
+            gpio_kinds: env_var(vars, "GPIO_KINDS", "deauth"),
+            gpio_pulse_ms: env_var(vars, "GPIO_PULSE_MS", "60").parse().unwrap_or(60),
+            mqtt_broker: vars("MQTT_BROKER").filter(|v| !v.trim().is_empty()),
+            mqtt_topic: env_var(vars, "MQTT_TOPIC", "radioscope"),
+            mqtt_client_id: env_var(vars, "MQTT_CLIENT_ID", "radioscope"),
+            self_test: env_flag(vars, "SELF_TEST", false),
+            simulate: env_flag(vars, "SIMULATE", false),
+            simulate_mix: env_var(vars, "SIMULATE_MIX", simulate::DEFAULT_MIX),
+            simulate_rate: env_var(vars, "SIMULATE_RATE", "40").parse().unwrap_or(40),
+            theme_accent: css_color(vars, "THEME_ACCENT"),
+            theme_background: css_color(vars, "THEME_BACKGROUND"),
+            record_db: vars("RECORD_DB").filter(|v| !v.trim().is_empty()),
+            handshake_dir: vars("HANDSHAKE_DIR").filter(|v| !v.trim().is_empty()),
+            power_audit_log: vars("POWER_AUDIT_LOG").filter(|v| !v.trim().is_empty()),
+            device_aliases: vars("DEVICE_ALIASES").filter(|v| !v.trim().is_empty()),
         }
     }
 }
 
-fn env_var(key: &str, default: &str) -> String {
-    env::var(key).unwrap_or_else(|_| default.to_string())
+/// An address block such as `192.168.1.0/24`; a bare address is a /32 (or /128).
+#[derive(Clone, Copy, Debug)]
+pub struct IpCidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpCidr {
+    pub fn parse(input: &str) -> Option<Self> {
+        let (addr, prefix) = match input.split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, Some(prefix.parse().ok()?)),
+            None => (input.parse::<IpAddr>().ok()?, None),
+        };
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        (prefix <= max).then_some(Self { addr, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Looks up one configuration variable; the process environment outside
+/// tests.
+type Vars<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+fn env_var(vars: Vars, key: &str, default: &str) -> String {
+    vars(key).unwrap_or_else(|| default.to_string())
+}
+
+/// Unset or unparsable values mean "no override".
+fn env_opt<T: FromStr>(vars: Vars, key: &str) -> Option<T> {
+    vars(key).and_then(|v| v.trim().parse().ok())
+}
+
+/// `1`, `true`, `yes` or `on` (any case) turn a flag on; any other value
+/// turns it off, and `default` applies when it's unset.
+fn env_flag(vars: Vars, key: &str, default: bool) -> bool {
+    vars(key).map_or(default, |v| {
+        matches!(
+            v.trim().to_ascii_lowercase().as_str(),
+            "1" | "true" | "yes" | "on"
+        )
+    })
+}
+
+/// Reads a CSS colour (`#hex`, `rgb(...)`, a named colour). Anything that
+/// could break out of the injected style block is rejected.
+fn css_color(vars: Vars, key: &str) -> Option<String> {
+    let value = vars(key)?;
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    let safe = value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "#(),.% -".contains(c));
+    if !safe {
+        tracing::warn!("Ignoring {key}={value:?}: not a CSS colour");
+        return None;
+    }
+    Some(value.to_string())
+}
+
+/// Reads a `low-high` range in Hz, e.g. `300-2400`.
+fn freq_range(vars: Vars, key: &str) -> Option<(f32, f32)> {
+    let value = vars(key)?;
+    if value.trim().is_empty() {
+        return None;
+    }
+    let range = value.split_once('-').and_then(|(low, high)| {
+        let low: f32 = low.trim().parse().ok()?;
+        let high: f32 = high.trim().parse().ok()?;
+        (low > 0.0 && high > low).then_some((low, high))
+    });
+    if range.is_none() {
+        tracing::warn!("Ignoring {key}={value:?}: expected a range like 300-2400");
+    }
+    range
 }
@@ -1,20 +1,150 @@
-use serde::Serialize;
+use crate::events::{EventKind, PacketEvent};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, RwLock};
-use std::time::{Duration, Instant};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ToSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum DeviceRole {
     Ap,
     Client,
+    #[default]
     Unknown,
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Strongest protection an AP advertises in its beacons.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Security {
+    Open,
+    Wep,
+    Wpa,
+    Wpa2,
+    Wpa3,
+}
+
+impl Security {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Security::Open => "open",
+            Security::Wep => "wep",
+            Security::Wpa => "wpa",
+            Security::Wpa2 => "wpa2",
+            Security::Wpa3 => "wpa3",
+        }
+    }
+}
+
+/// Per-minute probe-request buckets kept per device, enough to cover the
+/// longest device window.
+const PROBE_BUCKETS: usize = 120;
+const PROBE_BUCKET_SPAN: Duration = Duration::from_secs(60);
+
+/// Samples kept per device for trend estimation.
+const RSSI_HISTORY: usize = 32;
+/// Least-squares RSSI slope (dB/s) beyond which a device counts as moving.
+const TREND_DB_PER_SEC: f32 = 0.75;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RssiTrend {
+    Approaching,
+    Leaving,
+    Steady,
+}
+
+/// Which devices a listing includes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeviceFilter {
+    #[default]
+    All,
+    /// Clients whose BSSID is an AP in the same listing.
+    Associated,
+}
+
+impl DeviceFilter {
+    pub fn apply(self, list: Vec<DeviceView>) -> Vec<DeviceView> {
+        match self {
+            DeviceFilter::All => list,
+            DeviceFilter::Associated => associated_clients(list),
+        }
+    }
+}
+
+/// How a listing is ordered. `Role` is the snapshot's own order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeviceGrouping {
+    #[default]
+    Role,
+    /// Clusters devices by network, setting `group` on each.
+    Ssid,
+}
+
+/// `group` for devices with no known SSID.
+pub const UNASSOCIATED_GROUP: &str = "unassociated";
+
+impl DeviceGrouping {
+    pub fn apply(self, list: Vec<DeviceView>) -> Vec<DeviceView> {
+        match self {
+            DeviceGrouping::Role => list,
+            DeviceGrouping::Ssid => group_by_ssid(list),
+        }
+    }
+}
+
+/// Annotates each device with its SSID (or `unassociated`) as `group` and
+/// orders by group name, unassociated last. The stable sort keeps the
+/// snapshot's role and frame-count order within each group.
+fn group_by_ssid(mut list: Vec<DeviceView>) -> Vec<DeviceView> {
+    for dev in &mut list {
+        dev.group = Some(
+            dev.ssid
+                .clone()
+                .unwrap_or_else(|| UNASSOCIATED_GROUP.to_string()),
+        );
+    }
+    list.sort_by(|a, b| {
+        let key = |dev: &DeviceView| (dev.ssid.is_none(), dev.group.clone());
+        key(a).cmp(&key(b))
+    });
+    list
+}
+
+/// Keeps clients whose BSSID also appears as an AP in `list`, dropping APs,
+/// unassociated probers and devices of unknown role.
+fn associated_clients(list: Vec<DeviceView>) -> Vec<DeviceView> {
+    let aps: HashSet<String> = list
+        .iter()
+        .filter(|dev| dev.role == DeviceRole::Ap)
+        .map(|dev| dev.mac.clone())
+        .collect();
+    list.into_iter()
+        .filter(|dev| {
+            dev.role == DeviceRole::Client
+                && dev.bssid.as_ref().is_some_and(|bssid| aps.contains(bssid))
+        })
+        .collect()
+}
+
+/// Longest alias `/api/device-alias` accepts, in characters.
+pub const MAX_ALIAS_LEN: usize = 32;
+
+/// Most MACs that can carry an alias at once.
+pub const MAX_ALIASES: usize = 1024;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct DeviceView {
     pub mac: String,
+    /// User-assigned name, if any.
+    pub alias: Option<String>,
     pub bssid: Option<String>,
     pub role: DeviceRole,
     pub age_ms: u64,
@@ -23,6 +153,89 @@ pub struct DeviceView {
     pub allowed: bool,
     pub ssid: Option<String>,
     pub channel: Option<u16>,
+    pub additional_ssids: Vec<String>,
+    /// Operating width in MHz, from the AP's HT/VHT Operation elements.
+    pub channel_width: Option<u16>,
+    pub secondary_channel: Option<u16>,
+    /// Probe requests per minute sent within the listing window (or since
+    /// first seen, if that is shorter).
+    pub probe_rate_per_min: f32,
+    /// Regulatory domain from the AP's Country element, e.g. `US`.
+    pub country: Option<String>,
+    /// Frames the capture likely missed from this device, estimated from
+    /// gaps in its sequence numbers.
+    pub missed_frames: u64,
+    /// Mean 802.11 frame length over `frames`, capture headers excluded;
+    /// small for control chatter, large for bulk data.
+    pub avg_frame_bytes: Option<u32>,
+    /// Set on APs whose latest beacon advertised a channel the radio was not
+    /// tuned to, a common sign of an evil-twin or karma AP.
+    pub channel_mismatch: bool,
+    /// Grouping key when the listing was asked for `group_by=ssid`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+/// `/api/device/{mac}`: the listing row plus per-kind activity.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeviceDetail {
+    #[serde(flatten)]
+    pub device: DeviceView,
+    /// Milliseconds since the device last sent each kind of frame, keyed
+    /// by event kind, with reassociation apart from association; kinds it
+    /// never sent are absent.
+    pub last_kind_seen_ms: BTreeMap<&'static str, u64>,
+}
+
+/// One row of the AP inventory at `/api/networks`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct NetworkView {
+    pub bssid: String,
+    pub ssid: Option<String>,
+    pub channel: Option<u16>,
+    pub security: Option<Security>,
+    pub channel_width: Option<u16>,
+    /// First three octets of the BSSID; vendor names are not bundled.
+    pub oui: String,
+    /// Locally administered BSSIDs carry no meaningful OUI.
+    pub locally_administered: bool,
+    pub rssi_min_dbm: Option<i8>,
+    pub rssi_max_dbm: Option<i8>,
+    /// Unix milliseconds.
+    pub first_seen_ms: u64,
+    pub last_seen_ms: u64,
+    pub frames: u64,
+}
+
+/// What one captured frame tells the tracker about its transmitter.
+#[derive(Debug, Clone, Default)]
+pub struct Observation {
+    pub mac: [u8; 6],
+    pub bssid: Option<[u8; 6]>,
+    pub role: DeviceRole,
+    pub rssi_dbm: Option<i8>,
+    pub ssid: Option<String>,
+    pub channel: Option<u16>,
+    /// The channel the frame was received on, when the capture reports it.
+    pub rx_channel: Option<u16>,
+    /// SSIDs co-hosted via Multiple BSSID elements.
+    pub additional_ssids: Vec<String>,
+    pub channel_width: Option<u16>,
+    pub secondary_channel: Option<u16>,
+    /// From the privacy bit and RSN/WPA elements of beacons and probe responses.
+    pub security: Option<Security>,
+    pub probe_request: bool,
+    /// A reassociation request or response, which classifies as
+    /// [`EventKind::Assoc`] but is recorded apart from it.
+    pub reassociation: bool,
+    /// How the frame was classified; `None` for retransmissions and
+    /// fragments still being reassembled.
+    pub kind: Option<EventKind>,
+    pub country: Option<String>,
+    /// Sequence numbers skipped since the transmitter's previous frame.
+    pub missed_frames: u32,
+    /// Length of the 802.11 frame on air, without the capture header.
+    pub frame_bytes: u32,
 }
 
 #[derive(Debug)]
@@ -30,11 +243,27 @@ struct TrackedDevice {
     mac: [u8; 6],
     bssid: Option<[u8; 6]>,
     role: DeviceRole,
+    first_seen: Instant,
     last_seen: Instant,
     last_rssi: Option<i8>,
+    rssi_range: Option<(i8, i8)>,
+    security: Option<Security>,
+    /// `(bucket start, probe requests)`, oldest first.
+    probe_buckets: VecDeque<(Instant, u32)>,
+    /// Keyed by the label shown in the detail view.
+    last_kind_seen: HashMap<&'static str, Instant>,
+    rssi_history: VecDeque<(Instant, i8)>,
     frames: u64,
+    /// Summed frame lengths; reset with `frames`.
+    bytes: u64,
     ssid: Option<String>,
     channel: Option<u16>,
+    additional_ssids: Vec<String>,
+    channel_width: Option<u16>,
+    secondary_channel: Option<u16>,
+    country: Option<String>,
+    missed_frames: u64,
+    channel_mismatch: bool,
 }
 
 #[derive(Clone, Default)]
@@ -43,109 +272,242 @@ pub struct DeviceTracker {
     blocked: Arc<RwLock<HashSet<[u8; 6]>>>,
     ssid_cache: Arc<RwLock<HashMap<[u8; 6], String>>>,
     channel_cache: Arc<RwLock<HashMap<[u8; 6], u16>>>,
+    /// While set, only BSSIDs advertising this SSID, and stations associated
+    /// to them, are allowed. Checked per frame against `ssid_cache`, so new
+    /// matching BSSIDs join as soon as they beacon.
+    focus_ssid: Arc<RwLock<Option<String>>>,
+    /// Upper bound on tracked devices; 0 means unlimited.
+    max_devices: usize,
+    /// SSID/channel cache size that triggers pruning of BSSIDs no tracked
+    /// device refers to; 0 prunes only on eviction.
+    cache_limit: usize,
+    /// Frames from or to this MAC are never tracked or played.
+    ignored: Option<[u8; 6]>,
+    eviction_logged: Arc<AtomicBool>,
+    /// Sum of every device's `missed_frames`, kept across evictions.
+    missed_frames: Arc<AtomicU64>,
+    /// User-assigned names; unlike devices they survive `reset_all`.
+    aliases: Arc<RwLock<HashMap<[u8; 6], String>>>,
+    /// Serializes alias file writes so the newest map always lands last.
+    alias_save: Arc<Mutex<()>>,
 }
 
 impl DeviceTracker {
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    pub fn observe(
-        &self,
-        mac: [u8; 6],
-        bssid: Option<[u8; 6]>,
-        role: DeviceRole,
-        rssi_dbm: Option<i8>,
-        ssid: Option<String>,
-        channel: Option<u16>,
-    ) {
+    pub fn new(max_devices: usize, ignored: Option<[u8; 6]>, cache_limit: usize) -> Self {
+        Self {
+            max_devices,
+            cache_limit,
+            ignored,
+            ..Self::default()
+        }
+    }
+
+    /// Returns whether `obs.mac` was new to the tracker: never seen, or
+    /// forgotten through eviction or a reset since.
+    pub fn observe(&self, obs: Observation) -> bool {
+        let Observation {
+            mac,
+            bssid,
+            role,
+            rssi_dbm,
+            ssid,
+            channel,
+            rx_channel,
+            additional_ssids,
+            channel_width,
+            secondary_channel,
+            security,
+            probe_request,
+            reassociation,
+            kind,
+            country,
+            missed_frames,
+            frame_bytes,
+        } = obs;
+        if self.is_ignored(Some(mac)) || self.is_ignored(bssid) {
+            return false;
+        }
         let now = Instant::now();
         let mut guard = self.devices.write().expect("device tracker poisoned");
+        let is_new = !guard.contains_key(&mac);
+        if self.max_devices > 0 && guard.len() >= self.max_devices && is_new {
+            self.evict_oldest(&mut guard);
+        }
         let entry = guard.entry(mac).or_insert(TrackedDevice {
             mac,
             bssid,
             role: DeviceRole::Unknown,
+            first_seen: now,
             last_seen: now,
             last_rssi: None,
+            rssi_range: None,
+            security: None,
+            probe_buckets: VecDeque::new(),
+            last_kind_seen: HashMap::new(),
+            country: None,
+            rssi_history: VecDeque::with_capacity(RSSI_HISTORY),
             frames: 0,
+            bytes: 0,
             ssid: None,
             channel: None,
+            additional_ssids: Vec::new(),
+            channel_width: None,
+            secondary_channel: None,
+            missed_frames: 0,
+            channel_mismatch: false,
         });
         entry.last_seen = now;
+        if missed_frames > 0 {
+            entry.missed_frames = entry.missed_frames.saturating_add(u64::from(missed_frames));
+            self.missed_frames
+                .fetch_add(u64::from(missed_frames), AtomicOrdering::Relaxed);
+        }
         entry.frames = entry.frames.saturating_add(1);
+        entry.bytes = entry.bytes.saturating_add(u64::from(frame_bytes));
         if let Some(dbm) = rssi_dbm {
             entry.last_rssi = Some(dbm);
+            entry.rssi_range = Some(match entry.rssi_range {
+                Some((lo, hi)) => (lo.min(dbm), hi.max(dbm)),
+                None => (dbm, dbm),
+            });
+            if entry.rssi_history.len() == RSSI_HISTORY {
+                entry.rssi_history.pop_front();
+            }
+            entry.rssi_history.push_back((now, dbm));
         }
         if let Some(b) = bssid {
             entry.bssid = Some(b);
         }
         entry.role = merge_role(entry.role, role);
+        if !additional_ssids.is_empty() {
+            entry.additional_ssids = additional_ssids;
+        }
+        if security.is_some() {
+            entry.security = security;
+        }
+        if probe_request {
+            entry.record_probe(now);
+        }
+        if kind == Some(EventKind::Beacon)
+            && let (Some(advertised), Some(tuned)) = (channel, rx_channel)
+        {
+            let mismatch = channel_mismatch(advertised, tuned);
+            if mismatch && !entry.channel_mismatch {
+                tracing::warn!(
+                    "{} beacons channel {advertised} but was heard on channel {tuned}",
+                    format_mac(&mac)
+                );
+            }
+            entry.channel_mismatch = mismatch;
+        }
+        if let Some(kind) = kind {
+            let label = if reassociation && kind == EventKind::Assoc {
+                "reassoc"
+            } else {
+                kind.label()
+            };
+            entry.last_kind_seen.insert(label, now);
+        }
+        if country.is_some() {
+            entry.country = country;
+        }
+        if let Some(width) = channel_width {
+            entry.channel_width = Some(width);
+            entry.secondary_channel = secondary_channel;
+        }
+        // Only growth can push a cache over its limit, so only check then.
+        let mut new_cache_entry = false;
         if let Some(name) = ssid {
             entry.ssid = Some(name.clone());
-            if let Some(b) = bssid {
-                if let Ok(mut cache) = self.ssid_cache.write() {
-                    cache.insert(b, name);
-                }
-            }
-        } else if let Some(b) = bssid {
-            if entry.ssid.is_none() {
-                if let Ok(cache) = self.ssid_cache.read() {
-                    if let Some(name) = cache.get(&b) {
-                        entry.ssid = Some(name.clone());
-                    }
-                }
+            if let Some(b) = bssid
+                && let Ok(mut cache) = self.ssid_cache.write()
+            {
+                new_cache_entry |= cache.insert(b, name).is_none();
             }
+        } else if let Some(b) = bssid
+            && entry.ssid.is_none()
+            && let Ok(cache) = self.ssid_cache.read()
+            && let Some(name) = cache.get(&b)
+        {
+            entry.ssid = Some(name.clone());
         }
         if let Some(ch) = channel {
             entry.channel = Some(ch);
-            if let Some(b) = bssid {
-                if let Ok(mut cache) = self.channel_cache.write() {
-                    cache.insert(b, ch);
-                }
+            if let Some(b) = bssid
+                && let Ok(mut cache) = self.channel_cache.write()
+            {
+                new_cache_entry |= cache.insert(b, ch).is_none();
             }
-        } else if entry.channel.is_none() {
-            if let Some(b) = bssid {
-                if let Ok(cache) = self.channel_cache.read() {
-                    if let Some(ch) = cache.get(&b) {
-                        entry.channel = Some(*ch);
-                    }
-                }
+        } else if entry.channel.is_none()
+            && let Some(b) = bssid
+            && let Ok(cache) = self.channel_cache.read()
+            && let Some(ch) = cache.get(&b)
+        {
+            entry.channel = Some(*ch);
+        }
+        if new_cache_entry && self.cache_limit > 0 && self.cache_len() > self.cache_limit {
+            self.prune_caches(&guard);
+        }
+        is_new
+    }
+
+    fn cache_len(&self) -> usize {
+        let ssids = self.ssid_cache.read().map(|c| c.len()).unwrap_or(0);
+        let channels = self.channel_cache.read().map(|c| c.len()).unwrap_or(0);
+        ssids.max(channels)
+    }
+
+    /// Drops cached SSIDs and channels for BSSIDs that neither a tracked
+    /// device's address nor its BSSID still points at.
+    fn prune_caches(&self, devices: &HashMap<[u8; 6], TrackedDevice>) {
+        let referenced: HashSet<[u8; 6]> = devices
+            .values()
+            .flat_map(|dev| [Some(dev.mac), dev.bssid])
+            .flatten()
+            .collect();
+        if let Ok(mut cache) = self.ssid_cache.write() {
+            cache.retain(|bssid, _| referenced.contains(bssid));
+        }
+        if let Ok(mut cache) = self.channel_cache.write() {
+            cache.retain(|bssid, _| referenced.contains(bssid));
+        }
+    }
+
+    /// Drops the least-recently-seen device, preferring unblocked ones so
+    /// filter decisions stay visible in the list.
+    fn evict_oldest(&self, devices: &mut HashMap<[u8; 6], TrackedDevice>) {
+        let blocked = self.blocked.read().expect("device tracker poisoned");
+        let oldest = devices
+            .values()
+            .min_by_key(|dev| (blocked.contains(&dev.mac), dev.last_seen))
+            .map(|dev| dev.mac);
+        if let Some(mac) = oldest {
+            devices.remove(&mac);
+            drop(blocked);
+            self.prune_caches(devices);
+            if !self.eviction_logged.swap(true, AtomicOrdering::Relaxed) {
+                tracing::info!(
+                    "Device cap of {} reached; evicting least-recently-seen devices",
+                    self.max_devices
+                );
             }
+            tracing::debug!("Evicted device {}", format_mac(&mac));
         }
     }
 
-    pub fn snapshot(&self, window: Duration) -> Vec<DeviceView> {
+    /// Devices seen within `window`, omitting any with fewer than
+    /// `min_frames` frames; those stay tracked and appear once they qualify.
+    pub fn snapshot(&self, window: Duration, min_frames: u64) -> Vec<DeviceView> {
         let now = Instant::now();
         let guard = self.devices.read().expect("device tracker poisoned");
-        let blocked = self.blocked.read().expect("device tracker poisoned");
-        let cache = self.ssid_cache.read().expect("device tracker poisoned");
-        let channel_cache = self.channel_cache.read().expect("device tracker poisoned");
+        let tables = ViewTables::read(self);
         let mut list: Vec<DeviceView> = guard
             .values()
-            .filter_map(|dev| {
-                let age = now.duration_since(dev.last_seen);
-                if age > window {
-                    return None;
-                }
-                let ssid = dev
-                    .ssid
-                    .clone()
-                    .or_else(|| dev.bssid.and_then(|b| cache.get(&b).cloned()));
-                let channel = dev
-                    .channel
-                    .or_else(|| dev.bssid.and_then(|b| channel_cache.get(&b).copied()));
-                Some(DeviceView {
-                    mac: format_mac(&dev.mac),
-                    bssid: dev.bssid.map(|b| format_mac(&b)),
-                    role: dev.role,
-                    age_ms: age.as_millis().min(u128::from(u64::MAX)) as u64,
-                    rssi_dbm: dev.last_rssi,
-                    frames: dev.frames,
-                    allowed: !blocked.contains(&dev.mac),
-                    ssid,
-                    channel,
-                })
+            .filter(|dev| {
+                now.duration_since(dev.last_seen) <= window
+                    && (min_frames <= 1 || dev.frames >= min_frames)
             })
+            .map(|dev| tables.view(dev, now, window))
             .collect();
 
         list.sort_by(|a, b| {
@@ -158,21 +520,147 @@ impl DeviceTracker {
         list
     }
 
+    /// One device regardless of age or frame count, with when it last sent
+    /// each kind of frame.
+    pub fn detail(&self, mac: [u8; 6]) -> Option<DeviceDetail> {
+        let now = Instant::now();
+        let guard = self.devices.read().expect("device tracker poisoned");
+        let dev = guard.get(&mac)?;
+        let last_kind_seen_ms = dev
+            .last_kind_seen
+            .iter()
+            .map(|(label, at)| {
+                let age = now.duration_since(*at).as_millis();
+                (*label, age.min(u128::from(u64::MAX)) as u64)
+            })
+            .collect();
+        Some(DeviceDetail {
+            device: ViewTables::read(self).view(dev, now, Duration::MAX),
+            last_kind_seen_ms,
+        })
+    }
+
+    /// Every tracked AP as an inventory row, strongest first.
+    pub fn networks(&self) -> Vec<NetworkView> {
+        let now = Instant::now();
+        let wall = SystemTime::now();
+        let unix_ms = |at: Instant| {
+            (wall - now.duration_since(at))
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0)
+        };
+        let guard = self.devices.read().expect("device tracker poisoned");
+        let cache = self.ssid_cache.read().expect("device tracker poisoned");
+        let mut list: Vec<NetworkView> = guard
+            .values()
+            .filter(|dev| dev.role == DeviceRole::Ap)
+            .map(|dev| NetworkView {
+                bssid: format_mac(&dev.mac),
+                ssid: dev.ssid.clone().or_else(|| cache.get(&dev.mac).cloned()),
+                channel: dev.channel,
+                security: dev.security,
+                channel_width: dev.channel_width,
+                oui: format_mac(&dev.mac)[..8].to_string(),
+                locally_administered: dev.mac[0] & 0x02 != 0,
+                rssi_min_dbm: dev.rssi_range.map(|(lo, _)| lo),
+                rssi_max_dbm: dev.rssi_range.map(|(_, hi)| hi),
+                first_seen_ms: unix_ms(dev.first_seen),
+                last_seen_ms: unix_ms(dev.last_seen),
+                frames: dev.frames,
+            })
+            .collect();
+        list.sort_by_key(|net| std::cmp::Reverse(net.rssi_max_dbm));
+        list
+    }
+
+    /// RSSI trend for `mac` over samples from the last `span`, or `None`
+    /// while there is too little history to judge.
+    pub fn rssi_trend(&self, mac: [u8; 6], span: Duration) -> Option<RssiTrend> {
+        let now = Instant::now();
+        let guard = self.devices.read().expect("device tracker poisoned");
+        let dev = guard.get(&mac)?;
+        let samples: Vec<(f32, f32)> = dev
+            .rssi_history
+            .iter()
+            .filter(|(at, _)| now.duration_since(*at) <= span)
+            .map(|(at, dbm)| (-now.duration_since(*at).as_secs_f32(), *dbm as f32))
+            .collect();
+        trend_from_samples(&samples)
+    }
+
     pub fn allows(&self, src: Option<[u8; 6]>, bssid: Option<[u8; 6]>) -> bool {
+        if self.is_ignored(src) || self.is_ignored(bssid) {
+            return false;
+        }
         let blocked = self.blocked.read().expect("device tracker poisoned");
-        if let Some(mac) = src {
-            if blocked.contains(&mac) {
-                return false;
-            }
+        if let Some(mac) = src
+            && blocked.contains(&mac)
+        {
+            return false;
         }
-        if let Some(id) = bssid {
-            if blocked.contains(&id) {
-                return false;
-            }
+        if let Some(id) = bssid
+            && blocked.contains(&id)
+        {
+            return false;
+        }
+        drop(blocked);
+        if let Some(name) = self.focus_ssid() {
+            let cache = self.ssid_cache.read().expect("device tracker poisoned");
+            return focus_matches(&name, src, bssid, &cache);
         }
         true
     }
 
+    /// SSID a frame belongs to, from its BSSID or, failing that, its
+    /// transmitter, as last announced in a beacon or probe response.
+    pub fn ssid_for(&self, src: Option<[u8; 6]>, bssid: Option<[u8; 6]>) -> Option<String> {
+        let cache = self.ssid_cache.read().ok()?;
+        bssid
+            .and_then(|b| cache.get(&b))
+            .or_else(|| src.and_then(|s| cache.get(&s)))
+            .cloned()
+    }
+
+    /// Whether `evt` belongs to one of `mute_ssids`, judged by its BSSID or
+    /// the station the device filter uses. Alerts are never muted: a flood
+    /// impersonating a muted network is what they exist to report.
+    pub fn ssid_muted(&self, evt: &PacketEvent, mute_ssids: &[String]) -> bool {
+        !mute_ssids.is_empty()
+            && !evt.kind.is_alert()
+            && self
+                .ssid_for(evt.filter_addr(), evt.bssid)
+                .is_some_and(|ssid| mute_ssids.contains(&ssid))
+    }
+
+    /// Restricts playback to one network; `None` lifts the restriction.
+    pub fn set_focus_ssid(&self, ssid: Option<String>) {
+        *self.focus_ssid.write().expect("device tracker poisoned") = ssid;
+    }
+
+    pub fn focus_ssid(&self) -> Option<String> {
+        self.focus_ssid
+            .read()
+            .expect("device tracker poisoned")
+            .clone()
+    }
+
+    /// BSSIDs currently known to advertise `ssid`.
+    pub fn bssids_for_ssid(&self, ssid: &str) -> Vec<[u8; 6]> {
+        let cache = self.ssid_cache.read().expect("device tracker poisoned");
+        let mut bssids: Vec<[u8; 6]> = cache
+            .iter()
+            .filter(|(_, name)| name.as_str() == ssid)
+            .map(|(bssid, _)| *bssid)
+            .collect();
+        bssids.sort();
+        bssids
+    }
+
+    fn is_ignored(&self, mac: Option<[u8; 6]>) -> bool {
+        mac.is_some_and(|mac| self.ignored == Some(mac))
+    }
+
     pub fn set_many(&self, toggles: &[([u8; 6], bool)]) {
         let mut blocked = self.blocked.write().expect("device tracker poisoned");
         for (mac, allowed) in toggles {
@@ -184,15 +672,258 @@ impl DeviceTracker {
         }
     }
 
+    /// Forgets every device and cached SSID/channel. Blocked MACs and the
+    /// SSID focus survive only with `keep_filters`.
+    pub fn reset_all(&self, keep_filters: bool) {
+        if let Ok(mut guard) = self.devices.write() {
+            guard.clear();
+        }
+        if let Ok(mut cache) = self.ssid_cache.write() {
+            cache.clear();
+        }
+        if let Ok(mut cache) = self.channel_cache.write() {
+            cache.clear();
+        }
+        if !keep_filters {
+            if let Ok(mut blocked) = self.blocked.write() {
+                blocked.clear();
+            }
+            self.set_focus_ssid(None);
+        }
+        self.eviction_logged.store(false, AtomicOrdering::Relaxed);
+        self.missed_frames.store(0, AtomicOrdering::Relaxed);
+    }
+
+    /// Sets or, with `None`, clears the alias for `mac`. Returns false,
+    /// leaving the map unchanged, when naming a new MAC would exceed
+    /// `MAX_ALIASES`.
+    pub fn set_alias(&self, mac: [u8; 6], name: Option<String>) -> bool {
+        let mut aliases = self.aliases.write().expect("device tracker poisoned");
+        match name {
+            Some(name) => {
+                if aliases.len() >= MAX_ALIASES && !aliases.contains_key(&mac) {
+                    return false;
+                }
+                aliases.insert(mac, name);
+            }
+            None => {
+                aliases.remove(&mac);
+            }
+        }
+        true
+    }
+
+    /// Reads aliases saved by `save_aliases`; a missing file is an empty map.
+    pub fn load_aliases(&self, path: &Path) -> Result<usize> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err).with_context(|| format!("reading {}", path.display())),
+        };
+        let saved: BTreeMap<String, String> =
+            serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+        let mut aliases = self.aliases.write().expect("device tracker poisoned");
+        for (mac, name) in saved {
+            match parse_mac(&mac) {
+                Some(parsed) => {
+                    aliases.insert(parsed, name);
+                }
+                None => tracing::warn!("Ignoring alias for invalid MAC {mac:?}"),
+            }
+        }
+        Ok(aliases.len())
+    }
+
+    /// Writes every alias to `path` as a `{"mac": "name"}` object, replacing
+    /// the file atomically. Blocking.
+    pub fn save_aliases(&self, path: &Path) -> Result<()> {
+        let _saving = self.alias_save.lock().expect("device tracker poisoned");
+        let saved: BTreeMap<String, String> = self
+            .aliases
+            .read()
+            .expect("device tracker poisoned")
+            .iter()
+            .map(|(mac, name)| (format_mac(mac), name.clone()))
+            .collect();
+        let json = serde_json::to_vec_pretty(&saved)?;
+        // Appended rather than swapped in as the extension, so a path that
+        // already ends in `.tmp` is never its own scratch file.
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        std::fs::write(&tmp, json).with_context(|| format!("writing {}", tmp.display()))?;
+        std::fs::rename(&tmp, path).with_context(|| format!("replacing {}", path.display()))
+    }
+
+    /// Estimated frames missed by the capture since start or `reset_all`.
+    pub fn missed_frames_total(&self) -> u64 {
+        self.missed_frames.load(AtomicOrdering::Relaxed)
+    }
+
     pub fn reset_counts(&self) {
         if let Ok(mut guard) = self.devices.write() {
             for dev in guard.values_mut() {
                 dev.frames = 0;
+                dev.bytes = 0;
             }
         }
     }
 }
 
+impl TrackedDevice {
+    fn record_probe(&mut self, now: Instant) {
+        match self.probe_buckets.back_mut() {
+            Some((start, count)) if now.duration_since(*start) < PROBE_BUCKET_SPAN => {
+                *count = count.saturating_add(1);
+            }
+            _ => {
+                if self.probe_buckets.len() == PROBE_BUCKETS {
+                    self.probe_buckets.pop_front();
+                }
+                self.probe_buckets.push_back((now, 1));
+            }
+        }
+    }
+
+    /// Buckets are counted whole if they started inside `window`, so the
+    /// rate is accurate to about a minute.
+    fn probe_rate_per_min(&self, now: Instant, window: Duration) -> f32 {
+        let probes: u32 = self
+            .probe_buckets
+            .iter()
+            .rev()
+            .take_while(|(start, _)| now.duration_since(*start) <= window)
+            .map(|(_, count)| count)
+            .sum();
+        if probes == 0 {
+            return 0.0;
+        }
+        let span = window
+            .min(now.duration_since(self.first_seen))
+            .max(PROBE_BUCKET_SPAN);
+        probes as f32 * 60.0 / span.as_secs_f32()
+    }
+}
+
+/// A frame belongs to the focused network when its BSSID (or, for frames
+/// without one, its transmitter) is known to advertise `ssid`.
+/// The tracker's side tables, read-locked, for turning tracked devices into
+/// `DeviceView`s.
+struct ViewTables<'a> {
+    blocked: RwLockReadGuard<'a, HashSet<[u8; 6]>>,
+    ssid_cache: RwLockReadGuard<'a, HashMap<[u8; 6], String>>,
+    channel_cache: RwLockReadGuard<'a, HashMap<[u8; 6], u16>>,
+    aliases: RwLockReadGuard<'a, HashMap<[u8; 6], String>>,
+    focus: Option<String>,
+}
+
+impl<'a> ViewTables<'a> {
+    fn read(tracker: &'a DeviceTracker) -> Self {
+        Self {
+            blocked: tracker.blocked.read().expect("device tracker poisoned"),
+            ssid_cache: tracker.ssid_cache.read().expect("device tracker poisoned"),
+            channel_cache: tracker
+                .channel_cache
+                .read()
+                .expect("device tracker poisoned"),
+            aliases: tracker.aliases.read().expect("device tracker poisoned"),
+            focus: tracker.focus_ssid(),
+        }
+    }
+
+    fn view(&self, dev: &TrackedDevice, now: Instant, window: Duration) -> DeviceView {
+        let age = now.duration_since(dev.last_seen);
+        let ssid = dev
+            .ssid
+            .clone()
+            .or_else(|| dev.bssid.and_then(|b| self.ssid_cache.get(&b).cloned()));
+        let channel = dev
+            .channel
+            .or_else(|| dev.bssid.and_then(|b| self.channel_cache.get(&b).copied()));
+        let in_focus = self
+            .focus
+            .as_deref()
+            .is_none_or(|name| focus_matches(name, Some(dev.mac), dev.bssid, &self.ssid_cache));
+        DeviceView {
+            mac: format_mac(&dev.mac),
+            alias: self.aliases.get(&dev.mac).cloned(),
+            bssid: dev.bssid.map(|b| format_mac(&b)),
+            role: dev.role,
+            age_ms: age.as_millis().min(u128::from(u64::MAX)) as u64,
+            rssi_dbm: dev.last_rssi,
+            frames: dev.frames,
+            allowed: in_focus && !self.blocked.contains(&dev.mac),
+            ssid,
+            channel,
+            additional_ssids: dev.additional_ssids.clone(),
+            channel_width: dev.channel_width,
+            secondary_channel: dev.secondary_channel,
+            probe_rate_per_min: dev.probe_rate_per_min(now, window),
+            country: (dev.role == DeviceRole::Ap)
+                .then(|| dev.country.clone())
+                .flatten(),
+            missed_frames: dev.missed_frames,
+            avg_frame_bytes: (dev.frames > 0)
+                .then(|| u32::try_from(dev.bytes / dev.frames).unwrap_or(u32::MAX)),
+            channel_mismatch: dev.role == DeviceRole::Ap && dev.channel_mismatch,
+            group: None,
+        }
+    }
+}
+
+fn focus_matches(
+    ssid: &str,
+    src: Option<[u8; 6]>,
+    bssid: Option<[u8; 6]>,
+    cache: &HashMap<[u8; 6], String>,
+) -> bool {
+    bssid
+        .or(src)
+        .and_then(|mac| cache.get(&mac))
+        .is_some_and(|name| name == ssid)
+}
+
+/// Classifies `(seconds, dbm)` samples by their least-squares slope. Needs
+/// at least four samples spanning a second.
+fn trend_from_samples(samples: &[(f32, f32)]) -> Option<RssiTrend> {
+    let (min_t, max_t) = samples
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(lo, hi), (t, _)| {
+            (lo.min(*t), hi.max(*t))
+        });
+    if samples.len() < 4 || max_t - min_t < 1.0 {
+        return None;
+    }
+    let n = samples.len() as f32;
+    let mean_t = samples.iter().map(|(t, _)| t).sum::<f32>() / n;
+    let mean_v = samples.iter().map(|(_, v)| v).sum::<f32>() / n;
+    let (mut cov, mut var) = (0.0, 0.0);
+    for (t, v) in samples {
+        cov += (t - mean_t) * (v - mean_v);
+        var += (t - mean_t) * (t - mean_t);
+    }
+    let slope = cov / var;
+    Some(if slope >= TREND_DB_PER_SEC {
+        RssiTrend::Approaching
+    } else if slope <= -TREND_DB_PER_SEC {
+        RssiTrend::Leaving
+    } else {
+        RssiTrend::Steady
+    })
+}
+
+/// 2.4 GHz channels overlap, so a strong beacon is routinely heard a
+/// couple of channels away from where it is sent; only report gaps wider
+/// than that. On 5 GHz any difference counts.
+fn channel_mismatch(advertised: u16, tuned: u16) -> bool {
+    const OVERLAP_24GHZ: u16 = 2;
+    if advertised <= 14 && tuned <= 14 {
+        advertised.abs_diff(tuned) > OVERLAP_24GHZ
+    } else {
+        advertised != tuned
+    }
+}
+
 fn merge_role(current: DeviceRole, new_role: DeviceRole) -> DeviceRole {
     match (current, new_role) {
         (DeviceRole::Ap, _) | (_, DeviceRole::Ap) => DeviceRole::Ap,
@@ -222,10 +953,435 @@ pub fn parse_mac(input: &str) -> Option<[u8; 6]> {
         return None;
     }
     let mut bytes = [0u8; 6];
-    for i in 0..6 {
+    for (i, byte) in bytes.iter_mut().enumerate() {
         let idx = i * 2;
-        let part = u8::from_str_radix(&cleaned[idx..idx + 2], 16).ok()?;
-        bytes[i] = part;
+        *byte = u8::from_str_radix(&cleaned[idx..idx + 2], 16).ok()?;
     }
     Some(bytes)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn mac(last: u8) -> [u8; 6] {
+        [0x02, 0, 0, 0, 0, last]
+    }
+
+    fn observe(tracker: &DeviceTracker, mac: [u8; 6]) {
+        tracker.observe(Observation {
+            mac,
+            role: DeviceRole::Client,
+            ..Observation::default()
+        });
+        // Keep last_seen strictly ordered on coarse clocks.
+        thread::sleep(Duration::from_millis(2));
+    }
+
+    fn tracked(tracker: &DeviceTracker) -> Vec<String> {
+        let mut macs: Vec<String> = tracker
+            .snapshot(Duration::from_secs(60), 0)
+            .into_iter()
+            .map(|dev| dev.mac)
+            .collect();
+        macs.sort();
+        macs
+    }
+
+    #[test]
+    fn inserting_beyond_the_cap_evicts_the_oldest() {
+        let tracker = DeviceTracker::new(2, None, 0);
+        observe(&tracker, mac(1));
+        observe(&tracker, mac(2));
+        observe(&tracker, mac(1));
+        observe(&tracker, mac(3));
+        assert_eq!(
+            tracked(&tracker),
+            vec![format_mac(&mac(1)), format_mac(&mac(3))]
+        );
+    }
+
+    #[test]
+    fn eviction_prefers_unblocked_devices() {
+        let tracker = DeviceTracker::new(2, None, 0);
+        observe(&tracker, mac(1));
+        observe(&tracker, mac(2));
+        tracker.set_many(&[(mac(1), false)]);
+        observe(&tracker, mac(3));
+        assert_eq!(
+            tracked(&tracker),
+            vec![format_mac(&mac(1)), format_mac(&mac(3))]
+        );
+    }
+
+    #[test]
+    fn rising_rssi_reads_as_approaching() {
+        let rising: Vec<(f32, f32)> = (0..8)
+            .map(|i| (i as f32 * 0.5, -80.0 + i as f32 * 2.0))
+            .collect();
+        assert_eq!(trend_from_samples(&rising), Some(RssiTrend::Approaching));
+
+        let falling: Vec<(f32, f32)> = rising.iter().map(|(t, v)| (*t, -160.0 - v)).collect();
+        assert_eq!(trend_from_samples(&falling), Some(RssiTrend::Leaving));
+
+        let steady: Vec<(f32, f32)> = (0..8).map(|i| (i as f32 * 0.5, -60.0)).collect();
+        assert_eq!(trend_from_samples(&steady), Some(RssiTrend::Steady));
+
+        assert_eq!(trend_from_samples(&rising[..3]), None);
+    }
+
+    #[test]
+    fn ignored_mac_is_neither_tracked_nor_allowed() {
+        let host = mac(0x42);
+        let tracker = DeviceTracker::new(0, Some(host), 0);
+        observe(&tracker, host);
+        observe(&tracker, mac(1));
+        assert_eq!(tracked(&tracker), vec![format_mac(&mac(1))]);
+
+        assert!(!tracker.allows(Some(host), None));
+        assert!(!tracker.allows(Some(mac(1)), Some(host)));
+        assert!(tracker.allows(Some(mac(1)), Some(mac(2))));
+    }
+
+    #[test]
+    fn devices_below_min_frames_are_hidden_but_tracked() {
+        let tracker = DeviceTracker::new(0, None, 0);
+        observe(&tracker, mac(1));
+        observe(&tracker, mac(2));
+        observe(&tracker, mac(2));
+        let listed = |min_frames| {
+            tracker
+                .snapshot(Duration::from_secs(60), min_frames)
+                .into_iter()
+                .map(|dev| dev.mac)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(listed(2), vec![format_mac(&mac(2))]);
+        observe(&tracker, mac(1));
+        assert_eq!(listed(2).len(), 2);
+    }
+
+    fn beacon_from(tracker: &DeviceTracker, bssid: [u8; 6], ssid: &str) {
+        tracker.observe(Observation {
+            mac: bssid,
+            bssid: Some(bssid),
+            role: DeviceRole::Ap,
+            ssid: Some(ssid.to_string()),
+            ..Observation::default()
+        });
+    }
+
+    #[test]
+    fn focusing_an_ssid_allows_all_its_bssids_and_clients() {
+        let tracker = DeviceTracker::new(0, None, 0);
+        beacon_from(&tracker, mac(1), "home");
+        beacon_from(&tracker, mac(2), "home");
+        beacon_from(&tracker, mac(3), "cafe");
+        tracker.set_focus_ssid(Some("home".to_string()));
+
+        assert_eq!(tracker.bssids_for_ssid("home"), vec![mac(1), mac(2)]);
+        assert!(tracker.allows(Some(mac(1)), Some(mac(1))));
+        assert!(tracker.allows(Some(mac(10)), Some(mac(2))));
+        assert!(!tracker.allows(Some(mac(11)), Some(mac(3))));
+        assert!(!tracker.allows(None, None));
+
+        // A BSSID that starts advertising the SSID later joins the focus.
+        beacon_from(&tracker, mac(4), "home");
+        assert!(tracker.allows(Some(mac(12)), Some(mac(4))));
+
+        tracker.set_focus_ssid(None);
+        assert!(tracker.allows(Some(mac(11)), Some(mac(3))));
+    }
+
+    #[test]
+    fn evicted_bssids_leave_the_ssid_cache() {
+        let tracker = DeviceTracker::new(1, None, 0);
+        beacon_from(&tracker, mac(1), "home");
+        beacon_from(&tracker, mac(2), "cafe");
+        assert!(tracker.bssids_for_ssid("home").is_empty());
+        assert_eq!(tracker.bssids_for_ssid("cafe"), vec![mac(2)]);
+    }
+
+    fn client_of(tracker: &DeviceTracker, mac: [u8; 6], bssid: [u8; 6]) {
+        tracker.observe(Observation {
+            mac,
+            bssid: Some(bssid),
+            role: DeviceRole::Client,
+            ..Observation::default()
+        });
+    }
+
+    #[test]
+    fn associated_filter_keeps_clients_of_listed_aps() {
+        let tracker = DeviceTracker::new(0, None, 0);
+        beacon_from(&tracker, mac(1), "home");
+        client_of(&tracker, mac(2), mac(1));
+        client_of(&tracker, mac(3), mac(9));
+        let list = tracker.snapshot(Duration::from_secs(60), 0);
+        assert_eq!(DeviceFilter::All.apply(list.clone()).len(), 3);
+        let associated: Vec<String> = DeviceFilter::Associated
+            .apply(list)
+            .into_iter()
+            .map(|dev| dev.mac)
+            .collect();
+        assert_eq!(associated, vec![format_mac(&mac(2))]);
+    }
+
+    #[test]
+    fn networks_project_aps_with_security_and_rssi_range() {
+        let tracker = DeviceTracker::new(0, None, 0);
+        let ap = |mac: [u8; 6], ssid: &str, dbm: i8, security| Observation {
+            mac,
+            bssid: Some(mac),
+            role: DeviceRole::Ap,
+            rssi_dbm: Some(dbm),
+            ssid: Some(ssid.to_string()),
+            channel: Some(6),
+            channel_width: Some(40),
+            security,
+            ..Observation::default()
+        };
+        let home = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+        let guest = [0x02, 0, 0, 0, 0, 0x66];
+        tracker.observe(ap(home, "home", -60, Some(Security::Wpa3)));
+        tracker.observe(ap(home, "home", -48, None));
+        tracker.observe(ap(guest, "guest", -70, Some(Security::Open)));
+        client_of(&tracker, [0x02, 0, 0, 0, 0, 0x10], home);
+
+        let networks = tracker.networks();
+        assert_eq!(networks.len(), 2);
+        let (first, second) = (&networks[0], &networks[1]);
+        assert_eq!(first.bssid, "00:11:22:33:44:55");
+        assert_eq!(first.ssid.as_deref(), Some("home"));
+        assert_eq!(first.security, Some(Security::Wpa3));
+        assert_eq!(
+            (first.rssi_min_dbm, first.rssi_max_dbm),
+            (Some(-60), Some(-48))
+        );
+        assert_eq!(
+            (first.oui.as_str(), first.locally_administered),
+            ("00:11:22", false)
+        );
+        assert_eq!((first.frames, first.channel_width), (2, Some(40)));
+        assert!(first.first_seen_ms <= first.last_seen_ms);
+        assert_eq!(second.ssid.as_deref(), Some("guest"));
+        assert_eq!(second.security, Some(Security::Open));
+        assert!(second.locally_administered);
+    }
+
+    #[test]
+    fn ssid_lookup_covers_every_bssid_and_client_of_a_network() {
+        let tracker = DeviceTracker::new(0, None, 0);
+        let office = [0x02, 0, 0, 0, 0, 0x01];
+        let office_5g = [0x02, 0, 0, 0, 0, 0x02];
+        let cafe = [0x02, 0, 0, 0, 0, 0x03];
+        let laptop = [0x02, 0, 0, 0, 0, 0x10];
+        beacon_from(&tracker, office, "office");
+        beacon_from(&tracker, cafe, "cafe");
+
+        let mute = ["office".to_string()];
+        let muted = |kind, src, receiver, bssid| {
+            let evt = PacketEvent {
+                src,
+                receiver,
+                bssid,
+                ..PacketEvent::synthetic(kind)
+            };
+            tracker.ssid_muted(&evt, &mute)
+        };
+        assert!(muted(EventKind::DataTick, Some(laptop), None, Some(office)));
+        assert!(muted(EventKind::Beacon, Some(office), None, None));
+        assert!(!muted(EventKind::DataTick, Some(laptop), None, Some(cafe)));
+        assert!(!muted(EventKind::DataTick, Some(laptop), None, None));
+        // ACKs carry only a receiver, the AP being answered.
+        assert!(muted(EventKind::Ack, None, Some(office), None));
+        // An evil twin of the muted network is still reported.
+        assert!(!muted(
+            EventKind::ProbeRespFlood,
+            Some(office),
+            None,
+            Some(office)
+        ));
+        assert!(!tracker.ssid_muted(
+            &PacketEvent {
+                bssid: Some(office),
+                ..PacketEvent::synthetic(EventKind::Beacon)
+            },
+            &[]
+        ));
+        // A BSSID that starts beaconing the muted SSID later is muted too.
+        assert!(!muted(
+            EventKind::Beacon,
+            Some(office_5g),
+            None,
+            Some(office_5g)
+        ));
+        beacon_from(&tracker, office_5g, "office");
+        assert!(muted(
+            EventKind::Beacon,
+            Some(office_5g),
+            None,
+            Some(office_5g)
+        ));
+    }
+
+    #[test]
+    fn probe_rate_counts_a_burst_of_probe_requests() {
+        let tracker = DeviceTracker::new(0, None, 0);
+        let scanner = [0x02, 0, 0, 0, 0, 0x20];
+        let quiet = [0x02, 0, 0, 0, 0, 0x21];
+        let probe = |mac, probe_request| Observation {
+            mac,
+            role: DeviceRole::Client,
+            probe_request,
+            ..Observation::default()
+        };
+        for _ in 0..200 {
+            tracker.observe(probe(scanner, true));
+        }
+        tracker.observe(probe(quiet, false));
+
+        // Seen for under a minute, so the burst is spread over one minute.
+        let list = tracker.snapshot(Duration::from_secs(600), 0);
+        let rate = |mac| {
+            list.iter()
+                .find(|dev| dev.mac == format_mac(&mac))
+                .map(|dev| dev.probe_rate_per_min)
+        };
+        assert_eq!(rate(scanner), Some(200.0));
+        assert_eq!(rate(quiet), Some(0.0));
+    }
+
+    #[test]
+    fn per_kind_last_seen_updates_independently() {
+        let tracker = DeviceTracker::new(0, None, 0);
+        let ap = [0x02, 0, 0, 0, 0, 0x01];
+        let frame = |kind| Observation {
+            mac: ap,
+            bssid: Some(ap),
+            role: DeviceRole::Ap,
+            kind,
+            ..Observation::default()
+        };
+        tracker.observe(frame(Some(EventKind::Beacon)));
+        thread::sleep(Duration::from_millis(30));
+        tracker.observe(frame(Some(EventKind::DataTick)));
+        tracker.observe(frame(None));
+
+        let detail = tracker.detail(ap).expect("device is tracked");
+        let seen = &detail.last_kind_seen_ms;
+        assert_eq!(seen.len(), 2);
+        assert!(seen["beacon"] >= 30);
+        assert!(seen["data-tick"] < seen["beacon"]);
+        assert!(!seen.contains_key("deauth"));
+
+        // A fresh beacon moves only the beacon timestamp.
+        tracker.observe(frame(Some(EventKind::Beacon)));
+        let seen = tracker.detail(ap).unwrap().last_kind_seen_ms;
+        assert!(seen["beacon"] <= seen["data-tick"]);
+        assert!(tracker.detail([0x02, 0, 0, 0, 0, 0x99]).is_none());
+    }
+
+    #[test]
+    fn grouping_by_ssid_clusters_clients_of_one_network() {
+        let tracker = DeviceTracker::new(0, None, 0);
+        let home = mac(0x01);
+        let cafe = mac(0x02);
+        beacon_from(&tracker, home, "HomeNet");
+        beacon_from(&tracker, cafe, "Cafe");
+        client_of(&tracker, mac(0x10), home);
+        client_of(&tracker, mac(0x11), cafe);
+        client_of(&tracker, mac(0x12), home);
+        observe(&tracker, mac(0x13));
+
+        let list = DeviceGrouping::Ssid.apply(tracker.snapshot(Duration::from_secs(60), 0));
+        let groups: Vec<(&str, &str)> = list
+            .iter()
+            .map(|dev| (dev.group.as_deref().unwrap(), dev.mac.as_str()))
+            .collect();
+        let home_clients: Vec<&str> = groups
+            .iter()
+            .filter(|(group, _)| *group == "HomeNet")
+            .map(|(_, mac)| *mac)
+            .collect();
+        assert!(home_clients.contains(&format_mac(&mac(0x10)).as_str()));
+        assert!(home_clients.contains(&format_mac(&mac(0x12)).as_str()));
+        // Each network is one contiguous run, unassociated devices last.
+        let order: Vec<&str> = groups.iter().map(|(group, _)| *group).collect();
+        assert_eq!(
+            order,
+            [
+                "Cafe",
+                "Cafe",
+                "HomeNet",
+                "HomeNet",
+                "HomeNet",
+                UNASSOCIATED_GROUP
+            ]
+        );
+    }
+
+    #[test]
+    fn beacons_heard_off_their_channel_flag_the_ap() {
+        let tracker = DeviceTracker::new(0, None, 0);
+        let beacon = |mac, advertised, heard_on| Observation {
+            mac,
+            bssid: Some(mac),
+            role: DeviceRole::Ap,
+            channel: Some(advertised),
+            rx_channel: Some(heard_on),
+            kind: Some(EventKind::Beacon),
+            ..Observation::default()
+        };
+        tracker.observe(beacon(mac(1), 11, 6));
+        // Two channels apart on 2.4 GHz is ordinary adjacent-channel leakage.
+        tracker.observe(beacon(mac(2), 8, 6));
+        tracker.observe(beacon(mac(3), 4, 6));
+        // On 5 GHz any difference counts.
+        tracker.observe(beacon(mac(4), 36, 40));
+        tracker.observe(beacon(mac(5), 36, 36));
+        let flagged = |tracker: &DeviceTracker| -> Vec<String> {
+            tracker
+                .snapshot(Duration::from_secs(60), 0)
+                .into_iter()
+                .filter(|dev| dev.channel_mismatch)
+                .map(|dev| dev.mac)
+                .collect()
+        };
+        let mut macs = flagged(&tracker);
+        macs.sort();
+        assert_eq!(macs, vec![format_mac(&mac(1)), format_mac(&mac(4))]);
+
+        // A later beacon on the right channel clears the flag.
+        tracker.observe(beacon(mac(1), 6, 6));
+        assert_eq!(flagged(&tracker), vec![format_mac(&mac(4))]);
+    }
+
+    #[test]
+    fn average_frame_size_divides_bytes_by_frames() {
+        let tracker = DeviceTracker::new(0, None, 0);
+        for frame_bytes in [100, 300] {
+            tracker.observe(Observation {
+                mac: mac(1),
+                role: DeviceRole::Client,
+                frame_bytes,
+                ..Observation::default()
+            });
+        }
+        let snapshot = tracker.snapshot(Duration::from_secs(60), 0);
+        assert_eq!(snapshot[0].avg_frame_bytes, Some(200));
+    }
+
+    #[test]
+    fn observe_reports_only_the_first_sighting() {
+        let tracker = DeviceTracker::new(0, None, 0);
+        let client = || Observation {
+            mac: mac(1),
+            role: DeviceRole::Client,
+            ..Observation::default()
+        };
+        assert!(tracker.observe(client()));
+        assert!(!tracker.observe(client()));
+    }
+}
@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
+use utoipa::ToSchema;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+use crate::audio::SoundId;
+use crate::devices::DeviceRole;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum EventKind {
     Beacon,
@@ -15,6 +19,62 @@ pub enum EventKind {
     Cts,
     Ack,
     DataTick,
+    /// Synthetic alert raised by `BeaconFloodDetector`, not a captured frame.
+    BeaconFlood,
+    /// A frame no classifier recognised; only surfaced with
+    /// `capture_unclassified` on.
+    Other,
+    /// Synthetic alert raised by `ProbeRespFloodDetector` when one BSSID
+    /// answers probes far faster than a normal AP (karma-style attacks).
+    ProbeRespFlood,
+    /// Data subtypes that carry no payload (Null, QoS-Null, CF-Ack/Poll
+    /// without data): power-save keepalives rather than traffic.
+    NullData,
+}
+
+impl EventKind {
+    /// Every kind, in the order the UI lists them.
+    pub const ALL: [EventKind; 14] = [
+        EventKind::Beacon,
+        EventKind::ProbeReq,
+        EventKind::ProbeResp,
+        EventKind::Assoc,
+        EventKind::Deauth,
+        EventKind::Eapol,
+        EventKind::Rts,
+        EventKind::Cts,
+        EventKind::Ack,
+        EventKind::DataTick,
+        EventKind::NullData,
+        EventKind::BeaconFlood,
+        EventKind::ProbeRespFlood,
+        EventKind::Other,
+    ];
+
+    /// The same kebab-case name serde uses on the wire.
+    pub fn label(&self) -> &'static str {
+        match self {
+            EventKind::Beacon => "beacon",
+            EventKind::ProbeReq => "probe-req",
+            EventKind::ProbeResp => "probe-resp",
+            EventKind::Assoc => "assoc",
+            EventKind::Deauth => "deauth",
+            EventKind::Eapol => "eapol",
+            EventKind::Rts => "rts",
+            EventKind::Cts => "cts",
+            EventKind::Ack => "ack",
+            EventKind::DataTick => "data-tick",
+            EventKind::BeaconFlood => "beacon-flood",
+            EventKind::Other => "other",
+            EventKind::ProbeRespFlood => "probe-resp-flood",
+            EventKind::NullData => "null-data",
+        }
+    }
+
+    /// Synthetic detector alerts, as opposed to captured frames.
+    pub fn is_alert(&self) -> bool {
+        matches!(self, EventKind::BeaconFlood | EventKind::ProbeRespFlood)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -36,22 +96,223 @@ pub struct PacketEvent {
     pub kind: EventKind,
     pub rate_key: RateKey,
     pub retry: bool,
+    /// First frame from a MAC the device tracker was not holding.
+    pub new_device: bool,
     pub amplitude: f32,
     pub src: Option<[u8; 6]>,
+    /// Receiver (addr1) of control frames that carry no transmitter, such
+    /// as ACK and CTS; `None` whenever `src` is known.
+    pub receiver: Option<[u8; 6]>,
     pub bssid: Option<[u8; 6]>,
+    pub channel: Option<u16>,
+    pub signal_dbm: Option<i8>,
+    /// 802.11 sequence number, when the frame carries one.
+    pub seq: Option<u16>,
+    /// Receiver (addr1) is a broadcast or multicast group address.
+    pub group_addressed: bool,
+    /// Handshake message number (1-4) for pairwise EAPOL-Key frames.
+    pub eapol_msg: Option<u8>,
+    /// Raw 802.11 frame control field; 0 for synthetic events.
+    pub fc: u16,
+    /// Transmitter role, from the same addr2-vs-BSSID test the tracker uses.
+    pub role: DeviceRole,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+impl PacketEvent {
+    /// An event that stands for no captured frame: alerts and auditions.
+    pub fn synthetic(kind: EventKind) -> Self {
+        Self {
+            kind,
+            rate_key: RateKey::none(),
+            retry: false,
+            new_device: false,
+            amplitude: 1.0,
+            src: None,
+            receiver: None,
+            bssid: None,
+            channel: None,
+            signal_dbm: None,
+            seq: None,
+            group_addressed: false,
+            eapol_msg: None,
+            fc: 0,
+            role: DeviceRole::Unknown,
+        }
+    }
+
+    /// The station the device filter judges this frame by: its transmitter,
+    /// or for ACK and CTS the station being answered.
+    pub fn filter_addr(&self) -> Option<[u8; 6]> {
+        self.src.or(self.receiver)
+    }
+}
+
+/// The palette sound an event plays; EAPOL-Key frames pick the note of
+/// their handshake message.
+pub fn sound_for(evt: &PacketEvent) -> SoundId {
+    use SoundId::*;
+    match evt.kind {
+        EventKind::Beacon => BeaconTick,
+        EventKind::ProbeReq => ProbeChirp,
+        EventKind::ProbeResp => ProbeReply,
+        EventKind::Assoc => AssocUp,
+        EventKind::Deauth => DeauthZap,
+        EventKind::Eapol => match evt.eapol_msg {
+            Some(1) => EapolM1,
+            Some(2) => EapolM2,
+            Some(3) => EapolM3,
+            Some(4) => EapolM4,
+            _ => EapolMotif,
+        },
+        EventKind::Rts => RtsKnock,
+        EventKind::Cts => CtsKnockback,
+        EventKind::Ack => AckClick,
+        EventKind::DataTick => DataTick,
+        EventKind::BeaconFlood | EventKind::ProbeRespFlood => FloodAlarm,
+        EventKind::Other | EventKind::NullData => AckClick,
+    }
+}
+
+/// The sounds of each enabled kind, one group per kind in UI order, for
+/// `AUDIO_SPACED_TONES`. A sound shared with an earlier kind stays with it.
+pub fn spaced_tone_groups(settings: &EventSettings) -> Vec<Vec<SoundId>> {
+    use SoundId::*;
+    let mut assigned = HashSet::new();
+    EventKind::ALL
+        .into_iter()
+        .filter(|kind| *settings.enabled.get(kind).unwrap_or(&true))
+        .map(|kind| {
+            let ids = match kind {
+                EventKind::Eapol => vec![EapolMotif, EapolM1, EapolM2, EapolM3, EapolM4],
+                kind => vec![sound_for(&PacketEvent::synthetic(kind))],
+            };
+            ids.into_iter()
+                .filter(|id| assigned.insert(*id))
+                .collect::<Vec<_>>()
+        })
+        .filter(|group| !group.is_empty())
+        .collect()
+}
+
+/// The only sound an event makes in `retries_only` mode: the retry
+/// indicator for retransmissions, nothing for first transmissions.
+pub fn retry_indicator(evt: &PacketEvent) -> Option<SoundId> {
+    evt.retry.then_some(SoundId::RetryGlitch)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum NoiseMode {
     Crowded,
     Sparse,
 }
 
+/// How much of the capture the sniffer decodes.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiscoveryMode {
+    #[default]
+    Full,
+    /// Low-power AP inventory: only beacons and probe responses are
+    /// captured (via BPF) and decoded.
+    BeaconOnly,
+}
+
+impl DiscoveryMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "full" => Some(DiscoveryMode::Full),
+            "beacon-only" | "beacon_only" => Some(DiscoveryMode::BeaconOnly),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum Band {
+    #[serde(rename = "2.4ghz")]
+    Ghz24,
+    #[serde(rename = "5ghz")]
+    Ghz5,
+    #[serde(rename = "6ghz")]
+    Ghz6,
+}
+
+impl Band {
+    /// Guesses from the number alone, so 6 GHz channels (which reuse 1-233)
+    /// come back as 2.4 or 5 GHz; use `for_frequency` when it is known.
+    pub fn for_channel(channel: u16) -> Option<Band> {
+        match channel {
+            1..=14 => Some(Band::Ghz24),
+            32..=177 => Some(Band::Ghz5),
+            _ => None,
+        }
+    }
+
+    pub fn for_frequency(mhz: u16) -> Option<Band> {
+        match mhz {
+            2400..=2500 => Some(Band::Ghz24),
+            4900..=5899 => Some(Band::Ghz5),
+            5925..=7125 => Some(Band::Ghz6),
+            _ => None,
+        }
+    }
+
+    /// Centre frequency of `channel` in this band (IEEE 802.11 Annex E).
+    pub fn frequency_mhz(self, channel: u16) -> u16 {
+        match (self, channel) {
+            (Band::Ghz24, 14) => 2484,
+            (Band::Ghz24, ch) => 2407 + ch * 5,
+            (Band::Ghz5, ch) => 5000 + ch * 5,
+            (Band::Ghz6, 2) => 5935,
+            (Band::Ghz6, ch) => 5950 + ch * 5,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EventSettings {
     pub mode: NoiseMode,
     pub enabled: HashMap<EventKind, bool>,
+    pub band_filter: Option<Band>,
+    pub min_rssi_dbm: Option<i8>,
+    pub data_tick_pitch: bool,
+    /// Drop data frames sent to broadcast/multicast receivers (ARP, mDNS...)
+    /// so unicast traffic stands out.
+    pub suppress_group_data: bool,
+    /// Overrides the mode's data frames-per-tick when set.
+    pub data_tick_n: Option<u32>,
+    /// Device whose RSSI trend drives the approach/leave cue.
+    pub trend_target: Option<[u8; 6]>,
+    /// While set, only this device is audible, as Geiger-style clicks.
+    pub locate_target: Option<[u8; 6]>,
+    /// Pass frames no classifier recognises through as `EventKind::Other`.
+    pub capture_unclassified: bool,
+    /// Modes in which ACKs feed a background hiss instead of ticking.
+    pub ack_hiss_modes: Vec<NoiseMode>,
+    /// Networks whose frames are silenced, including BSSIDs of that SSID
+    /// that appear later.
+    pub mute_ssids: Vec<String>,
+    /// Play a burst sound for runs of rate-limited repeats instead of
+    /// dropping them silently.
+    pub burst_on_suppress: bool,
+    /// One sound for the whole association family. Off, reassociation is
+    /// pitched apart from association; the tracker records the two apart
+    /// either way.
+    pub merge_assoc_family: bool,
+    /// Drop frames whose parsed channel differs from the one the radio is
+    /// tuned to, i.e. adjacent-channel leakage.
+    pub on_channel_only: bool,
+    /// Kinds that skip the `EventWindow` caps so a storm of other frames
+    /// can't starve them; the rate limiter still applies. Alerts skip the
+    /// caps regardless.
+    pub high_priority: Vec<EventKind>,
+    /// Sound only a retry indicator for retransmitted frames and nothing
+    /// for the rest, to judge link quality by ear.
+    pub retries_only: bool,
+    /// Ping when a MAC the tracker did not hold sends its first frame, on
+    /// top of the frame's own sound.
+    pub new_device_cue: bool,
 }
 
 impl Default for EventSettings {
@@ -67,13 +328,77 @@ impl Default for EventSettings {
         enabled.insert(EventKind::Cts, true);
         enabled.insert(EventKind::Ack, true);
         enabled.insert(EventKind::DataTick, true);
+        enabled.insert(EventKind::BeaconFlood, true);
+        enabled.insert(EventKind::Other, true);
+        enabled.insert(EventKind::ProbeRespFlood, true);
+        // Keepalives would otherwise drown out the data-activity ticks.
+        enabled.insert(EventKind::NullData, false);
         Self {
             mode: NoiseMode::Crowded,
             enabled,
+            band_filter: None,
+            min_rssi_dbm: None,
+            data_tick_pitch: false,
+            suppress_group_data: false,
+            data_tick_n: None,
+            trend_target: None,
+            locate_target: None,
+            capture_unclassified: false,
+            ack_hiss_modes: Vec::new(),
+            mute_ssids: Vec::new(),
+            burst_on_suppress: false,
+            merge_assoc_family: true,
+            on_channel_only: false,
+            high_priority: vec![EventKind::Deauth, EventKind::Eapol],
+            retries_only: false,
+            new_device_cue: false,
         }
     }
 }
 
+impl EventSettings {
+    /// Frames without a known channel can't be placed in a band, so they pass.
+    pub fn band_allows(&self, channel: Option<u16>) -> bool {
+        match (self.band_filter, channel) {
+            (Some(band), Some(channel)) => Band::for_channel(channel) == Some(band),
+            _ => true,
+        }
+    }
+
+    /// With `on_channel_only`, drops frames parsed from a channel other
+    /// than `tuned`; either being unknown lets the frame through.
+    pub fn channel_allows(&self, channel: Option<u16>, tuned: Option<u16>) -> bool {
+        match (channel, tuned) {
+            (Some(channel), Some(tuned)) if self.on_channel_only => channel == tuned,
+            _ => true,
+        }
+    }
+
+    /// Frames without a signal reading pass the gate.
+    pub fn rssi_allows(&self, signal_dbm: Option<i8>) -> bool {
+        match (self.min_rssi_dbm, signal_dbm) {
+            (Some(min_dbm), Some(dbm)) => dbm >= min_dbm,
+            _ => true,
+        }
+    }
+
+    pub fn group_data_allows(&self, evt: &PacketEvent) -> bool {
+        !(self.suppress_group_data && evt.kind == EventKind::DataTick && evt.group_addressed)
+    }
+
+    /// Data frames aggregated into one data tick.
+    pub fn data_tick_n(&self) -> u32 {
+        self.data_tick_n.unwrap_or(match self.mode {
+            NoiseMode::Crowded => 100,
+            NoiseMode::Sparse => 10,
+        })
+    }
+
+    pub fn is_high_priority(&self, kind: &EventKind) -> bool {
+        self.high_priority.contains(kind)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct EventWindow {
     start: Instant,
@@ -117,7 +442,9 @@ impl EventWindow {
             | EventKind::ProbeResp
             | EventKind::Assoc
             | EventKind::Deauth
-            | EventKind::Eapol => {
+            | EventKind::Eapol
+            | EventKind::BeaconFlood
+            | EventKind::ProbeRespFlood => {
                 if self.counts_mgmt >= max_mgmt {
                     return false;
                 }
@@ -129,7 +456,7 @@ impl EventWindow {
                 }
                 self.counts_ctrl += 1;
             }
-            EventKind::DataTick => {
+            EventKind::DataTick | EventKind::NullData | EventKind::Other => {
                 if self.counts_data >= max_data {
                     return false;
                 }
@@ -138,6 +465,102 @@ impl EventWindow {
         }
         true
     }
+
+    /// `try_count`, except that alerts and `settings.high_priority` kinds
+    /// always pass and don't use up a slot.
+    pub fn admit(
+        &mut self,
+        kind: &EventKind,
+        settings: &EventSettings,
+        max_mgmt: u32,
+        max_ctrl: u32,
+        max_data: u32,
+    ) -> bool {
+        kind.is_alert()
+            || settings.is_high_priority(kind)
+            || self.try_count(kind, max_mgmt, max_ctrl, max_data)
+    }
+}
+
+/// Flags windows in which an abnormal number of distinct BSSIDs beacon, the
+/// signature of a beacon flood with randomised SSIDs. Fires at most once per
+/// window.
+#[derive(Clone, Debug)]
+pub struct BeaconFloodDetector {
+    start: Instant,
+    window: Duration,
+    threshold: usize,
+    seen: HashSet<[u8; 6]>,
+    alerted: bool,
+}
+
+impl BeaconFloodDetector {
+    /// A `threshold` of 0 disables detection.
+    pub fn new(window: Duration, threshold: usize) -> Self {
+        Self {
+            start: Instant::now(),
+            window,
+            threshold,
+            seen: HashSet::new(),
+            alerted: false,
+        }
+    }
+
+    /// Records a beacon's BSSID; returns true when this beacon pushes the
+    /// window's unique count to the threshold.
+    pub fn observe(&mut self, bssid: [u8; 6]) -> bool {
+        if self.threshold == 0 {
+            return false;
+        }
+        if self.start.elapsed() >= self.window {
+            self.start = Instant::now();
+            self.seen.clear();
+            self.alerted = false;
+        }
+        self.seen.insert(bssid);
+        if !self.alerted && self.seen.len() >= self.threshold {
+            self.alerted = true;
+            return true;
+        }
+        false
+    }
+}
+
+/// Counts probe responses per BSSID over a window and flags a BSSID once per
+/// window when it reaches the threshold.
+#[derive(Clone, Debug)]
+pub struct ProbeRespFloodDetector {
+    start: Instant,
+    window: Duration,
+    threshold: u32,
+    counts: HashMap<[u8; 6], u32>,
+}
+
+impl ProbeRespFloodDetector {
+    /// A `threshold` of 0 disables detection.
+    pub fn new(window: Duration, threshold: u32) -> Self {
+        Self {
+            start: Instant::now(),
+            window,
+            threshold,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Records a probe response; returns true when it brings its BSSID's
+    /// count for this window to exactly the threshold.
+    pub fn observe(&mut self, bssid: [u8; 6]) -> bool {
+        if self.threshold == 0 {
+            return false;
+        }
+        if self.start.elapsed() >= self.window {
+            self.start = Instant::now();
+            self.counts.clear();
+        }
+        let count = self.counts.entry(bssid).or_insert(0);
+        *count = count.saturating_add(1);
+        *count == self.threshold
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -170,3 +593,442 @@ impl RateLimiter {
         }
     }
 }
+
+/// Counts events the rate limiter drops, per kind and rate key like the
+/// limiter itself, and decides when one event has repeated enough to be
+/// announced with a single burst.
+#[derive(Clone, Debug)]
+pub struct BurstGate {
+    counts: HashMap<(EventKind, RateKey), BurstCount>,
+    min_suppressed: u32,
+    min_interval: Duration,
+}
+
+#[derive(Clone, Debug, Default)]
+struct BurstCount {
+    suppressed: u32,
+    last_burst: Option<Instant>,
+}
+
+impl BurstGate {
+    pub fn new(min_suppressed: u32, min_interval: Duration) -> Self {
+        Self {
+            counts: HashMap::new(),
+            min_suppressed,
+            min_interval,
+        }
+    }
+
+    /// Records one dropped `kind` event under `key`; returns how many of
+    /// them were dropped since their last burst when it is time for another.
+    pub fn suppressed(&mut self, kind: &EventKind, key: &RateKey) -> Option<u32> {
+        let count = self.counts.entry((kind.clone(), key.clone())).or_default();
+        count.suppressed = count.suppressed.saturating_add(1);
+        let due = count.suppressed >= self.min_suppressed
+            && count
+                .last_burst
+                .is_none_or(|at| at.elapsed() >= self.min_interval);
+        if !due {
+            return None;
+        }
+        count.last_burst = Some(Instant::now());
+        Some(std::mem::take(&mut count.suppressed))
+    }
+}
+
+/// Unless the family is merged, reassociation (management subtypes 2 and 3)
+/// plays a fifth above association.
+pub fn assoc_pitch(evt: &PacketEvent, merge_family: bool) -> f32 {
+    if merge_family || evt.kind != EventKind::Assoc {
+        return 1.0;
+    }
+    match (evt.fc >> 4) & 0xF {
+        2 | 3 => 1.5,
+        _ => 1.0,
+    }
+}
+
+/// Burst pitch rises with the number of swallowed events: 1.0 at the
+/// trigger count, one octave higher at 16 times that.
+pub fn burst_pitch(suppressed: u32, min_suppressed: u32) -> f32 {
+    let ratio = suppressed as f32 / min_suppressed.max(1) as f32;
+    1.0 + (ratio.max(1.0).log2() / 4.0).min(1.0)
+}
+
+/// Exponentially smoothed event rate in events per second.
+#[derive(Clone, Debug)]
+pub struct RateMeter {
+    rate: f32,
+    last: Instant,
+    smoothing: Duration,
+}
+
+impl RateMeter {
+    pub fn new(smoothing: Duration) -> Self {
+        Self {
+            rate: 0.0,
+            last: Instant::now(),
+            smoothing,
+        }
+    }
+
+    /// Counts one event and returns the updated rate.
+    pub fn record(&mut self) -> f32 {
+        self.record_at(Instant::now())
+    }
+
+    fn record_at(&mut self, now: Instant) -> f32 {
+        self.decay(now);
+        self.rate += 1.0 / self.smoothing.as_secs_f32();
+        self.rate
+    }
+
+    pub fn rate(&mut self) -> f32 {
+        self.decay(Instant::now());
+        self.rate
+    }
+
+    fn decay(&mut self, now: Instant) {
+        let dt = now.duration_since(self.last).as_secs_f32();
+        self.last = now;
+        self.rate *= (-dt / self.smoothing.as_secs_f32()).exp();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProbeVerdict {
+    /// Below the storm threshold: play as an individual probe.
+    Pass,
+    /// Storming: play one summary tick standing in for the whole burst.
+    Summary,
+    /// Storming and a summary already played this cadence.
+    Drop,
+}
+
+/// Collapses probe-request storms from many MACs into a summary tick at a
+/// fixed cadence once the aggregate probe rate crosses a threshold.
+#[derive(Clone, Debug)]
+pub struct ProbeCoalescer {
+    meter: RateMeter,
+    cadence: Duration,
+    last_summary: Option<Instant>,
+}
+
+impl ProbeCoalescer {
+    pub fn new(cadence: Duration) -> Self {
+        Self {
+            meter: RateMeter::new(Duration::from_secs(1)),
+            cadence,
+            last_summary: None,
+        }
+    }
+
+    /// Records one probe request, heard at `now`, and decides how to voice it.
+    pub fn admit(&mut self, threshold_per_sec: f32, now: Instant) -> ProbeVerdict {
+        if self.meter.record_at(now) <= threshold_per_sec {
+            return ProbeVerdict::Pass;
+        }
+        if self
+            .last_summary
+            .is_some_and(|at| now.saturating_duration_since(at) < self.cadence)
+        {
+            return ProbeVerdict::Drop;
+        }
+        self.last_summary = Some(now);
+        ProbeVerdict::Summary
+    }
+}
+
+/// APs to the left, clients to the right, everything else centred.
+pub fn role_pan(role: DeviceRole, width: f32) -> f32 {
+    match role {
+        DeviceRole::Ap => -width,
+        DeviceRole::Client => width,
+        DeviceRole::Unknown => 0.0,
+    }
+}
+
+/// Minimum gap between locator clicks: one per second at -90 dBm, falling
+/// exponentially to 40 ms at -30 dBm. Clicks only fire on the target's own
+/// frames, so a chattier device also clicks faster.
+pub fn locate_click_interval(signal_dbm: i8) -> Duration {
+    let strength = ((signal_dbm as f32 + 90.0) / 60.0).clamp(0.0, 1.0);
+    let ms = 1000.0 * (40.0f32 / 1000.0).powf(strength);
+    Duration::from_millis(ms.round() as u64)
+}
+
+/// Shortest gap between new-device pings, across all devices.
+const NEW_DEVICE_CUE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Whether `evt` should ping as a newly seen device: the cue is on, the
+/// locator isn't drowning it out, and no ping played in the last
+/// `NEW_DEVICE_CUE_INTERVAL`.
+pub fn new_device_cue_due(
+    evt: &PacketEvent,
+    enabled: bool,
+    locating: bool,
+    last_cue: Option<Instant>,
+    now: Instant,
+) -> bool {
+    enabled
+        && !locating
+        && evt.new_device
+        && last_cue.is_none_or(|at| now.saturating_duration_since(at) >= NEW_DEVICE_CUE_INTERVAL)
+}
+
+/// Maps a smoothed ACK rate to hiss gain: silent at 1 ACK/s or less, rising
+/// logarithmically to full at 500 ACK/s.
+pub fn ack_hiss_gain(acks_per_sec: f32) -> f32 {
+    if acks_per_sec <= 1.0 {
+        return 0.0;
+    }
+    (acks_per_sec.log10() / 500f32.log10()).min(1.0)
+}
+
+/// Maps a data frame rate to a pitch factor: unchanged up to 10 frames/s,
+/// then up a fifth per doubling, capped at one octave.
+pub fn data_tick_pitch(frames_per_sec: f32) -> f32 {
+    if frames_per_sec <= 10.0 {
+        return 1.0;
+    }
+    (1.5f32.powf((frames_per_sec / 10.0).log2())).min(2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn band_filter_drops_frames_from_the_other_band() {
+        let settings = EventSettings {
+            band_filter: Some(Band::Ghz24),
+            ..EventSettings::default()
+        };
+        assert!(!settings.band_allows(Some(36)));
+        assert!(settings.band_allows(Some(6)));
+        assert!(settings.band_allows(None));
+        assert!(EventSettings::default().band_allows(Some(36)));
+    }
+
+    #[test]
+    fn rssi_gate_silences_weak_frames() {
+        let settings = EventSettings {
+            min_rssi_dbm: Some(-80),
+            ..EventSettings::default()
+        };
+        assert!(!settings.rssi_allows(Some(-95)));
+        assert!(settings.rssi_allows(Some(-40)));
+        assert!(settings.rssi_allows(None));
+    }
+
+    #[test]
+    fn data_tick_pitch_rises_with_rate_buckets() {
+        assert_eq!(data_tick_pitch(0.0), 1.0);
+        assert_eq!(data_tick_pitch(10.0), 1.0);
+        assert!((data_tick_pitch(20.0) - 1.5).abs() < 1e-4);
+        assert!(data_tick_pitch(15.0) > 1.0 && data_tick_pitch(15.0) < 1.5);
+        assert_eq!(data_tick_pitch(40.0), 2.0);
+        assert_eq!(data_tick_pitch(10_000.0), 2.0);
+    }
+
+    #[test]
+    fn beacon_flood_fires_once_per_window() {
+        let mut detector = BeaconFloodDetector::new(Duration::from_secs(60), 50);
+        let alerts = (0..50u8)
+            .filter(|i| detector.observe([0x02, 0, 0, 0, 0, *i]))
+            .count();
+        assert_eq!(alerts, 1);
+        assert!(!detector.observe([0x02, 0, 0, 0, 1, 0]));
+    }
+
+    #[test]
+    fn repeated_bssids_do_not_trip_the_flood_detector() {
+        let mut detector = BeaconFloodDetector::new(Duration::from_secs(60), 50);
+        for _ in 0..10 {
+            for i in 0..49u8 {
+                assert!(!detector.observe([0x02, 0, 0, 0, 0, i]));
+            }
+        }
+        let mut disabled = BeaconFloodDetector::new(Duration::from_secs(60), 0);
+        assert!((0..100u8).all(|i| !disabled.observe([0x02, 0, 0, 0, 0, i])));
+    }
+
+    #[test]
+    fn locate_clicks_speed_up_with_rssi() {
+        let intervals: Vec<Duration> = [-95, -90, -75, -60, -45, -30, -20]
+            .into_iter()
+            .map(locate_click_interval)
+            .collect();
+        assert!(intervals.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert_eq!(intervals[0], Duration::from_secs(1));
+        assert_eq!(intervals[1], Duration::from_secs(1));
+        assert_eq!(intervals[5], Duration::from_millis(40));
+        assert_eq!(intervals[6], Duration::from_millis(40));
+        assert!(intervals[3] < Duration::from_millis(250));
+    }
+
+    #[test]
+    fn probe_storms_collapse_to_the_summary_cadence() {
+        // 100 probes/s, each from a different MAC as far as the coalescer
+        // cares: only the aggregate rate matters.
+        let mut coalescer = ProbeCoalescer::new(Duration::from_millis(500));
+        let start = Instant::now();
+        let verdicts: Vec<ProbeVerdict> = (0..100)
+            .map(|i| coalescer.admit(15.0, start + Duration::from_millis(10 * i)))
+            .collect();
+        let count = |v: ProbeVerdict| verdicts.iter().filter(|x| **x == v).count();
+        // One summary per 500 ms cadence once the rate passes 15/s.
+        assert_eq!(count(ProbeVerdict::Pass), 16);
+        assert_eq!(count(ProbeVerdict::Summary), 2);
+        assert_eq!(count(ProbeVerdict::Drop), 82);
+        assert_eq!(verdicts[0], ProbeVerdict::Pass);
+    }
+
+    #[test]
+    fn ack_rate_maps_to_a_rising_hiss_gain() {
+        assert_eq!(ack_hiss_gain(0.0), 0.0);
+        assert_eq!(ack_hiss_gain(1.0), 0.0);
+        let low = ack_hiss_gain(10.0);
+        let high = ack_hiss_gain(100.0);
+        assert!(0.0 < low && low < high && high < 1.0);
+        assert!((ack_hiss_gain(500.0) - 1.0).abs() < 1e-6);
+        assert_eq!(ack_hiss_gain(50_000.0), 1.0);
+    }
+
+    #[test]
+    fn probe_response_flood_from_one_bssid_alerts_once() {
+        let mut detector = ProbeRespFloodDetector::new(Duration::from_secs(60), 20);
+        let flooder = [0x02, 0, 0, 0, 0, 0x01];
+        let alerts = (0..100).filter(|_| detector.observe(flooder)).count();
+        assert_eq!(alerts, 1);
+        // Other BSSIDs answering normally stay below their own threshold.
+        assert!((0..19).all(|_| !detector.observe([0x02, 0, 0, 0, 0, 0x02])));
+        assert!(EventKind::ProbeRespFlood.is_alert());
+    }
+
+    #[test]
+    fn heavily_rate_limited_repeats_occasionally_burst() {
+        let mut limiter = RateLimiter::new();
+        let mut gate = BurstGate::new(8, Duration::ZERO);
+        let key = RateKey::Tx([0x02, 0, 0, 0, 0, 0x01]);
+        let mut played = 0;
+        let mut bursts = Vec::new();
+        for _ in 0..100 {
+            if limiter.allow(&EventKind::Ack, key.clone(), Duration::from_secs(60)) {
+                played += 1;
+            } else if let Some(count) = gate.suppressed(&EventKind::Ack, &key) {
+                bursts.push(count);
+            }
+        }
+        assert_eq!(played, 1);
+        // 99 drops: twelve bursts of eight, three left pending.
+        assert_eq!(bursts, vec![8; 12]);
+
+        // Drops of other events don't add up to a burst.
+        let mut mixed = BurstGate::new(8, Duration::ZERO);
+        let other = RateKey::Tx([0x02, 0, 0, 0, 0, 0x02]);
+        for _ in 0..7 {
+            assert_eq!(mixed.suppressed(&EventKind::Ack, &key), None);
+            assert_eq!(mixed.suppressed(&EventKind::Ack, &other), None);
+            assert_eq!(mixed.suppressed(&EventKind::Cts, &key), None);
+        }
+        assert_eq!(mixed.suppressed(&EventKind::Ack, &key), Some(8));
+
+        let mut spaced = BurstGate::new(8, Duration::from_secs(60));
+        assert_eq!(
+            (0..100)
+                .filter_map(|_| spaced.suppressed(&EventKind::Ack, &key))
+                .count(),
+            1
+        );
+        assert_eq!(burst_pitch(8, 8), 1.0);
+        assert_eq!(burst_pitch(128, 8), 2.0);
+    }
+
+    #[test]
+    fn on_channel_only_drops_leakage_while_dwelling_on_6() {
+        let mut settings = EventSettings::default();
+        assert!(settings.channel_allows(Some(5), Some(6)));
+
+        settings.on_channel_only = true;
+        assert!(settings.channel_allows(Some(6), Some(6)));
+        assert!(!settings.channel_allows(Some(5), Some(6)));
+        assert!(!settings.channel_allows(Some(7), Some(6)));
+        assert!(settings.channel_allows(None, Some(6)));
+        assert!(settings.channel_allows(Some(5), None));
+    }
+
+    #[test]
+    fn high_priority_kinds_pass_a_saturated_window() {
+        let settings = EventSettings::default();
+        let mut window = EventWindow::new(Duration::from_secs(60));
+        for _ in 0..3 {
+            assert!(window.admit(&EventKind::Beacon, &settings, 3, 2, 1));
+        }
+        assert!(!window.admit(&EventKind::Beacon, &settings, 3, 2, 1));
+        assert!(!window.admit(&EventKind::Assoc, &settings, 3, 2, 1));
+        assert!(window.admit(&EventKind::Deauth, &settings, 3, 2, 1));
+        assert!(window.admit(&EventKind::Eapol, &settings, 3, 2, 1));
+        assert!(window.admit(&EventKind::BeaconFlood, &settings, 3, 2, 1));
+        assert!(window.admit(&EventKind::ProbeRespFlood, &settings, 3, 2, 1));
+
+        // Alerts pass even when nothing is marked high priority.
+        let settings = EventSettings {
+            high_priority: Vec::new(),
+            ..EventSettings::default()
+        };
+        assert!(!window.admit(&EventKind::Deauth, &settings, 3, 2, 1));
+        assert!(window.admit(&EventKind::BeaconFlood, &settings, 3, 2, 1));
+    }
+
+    #[test]
+    fn retries_only_sounds_just_retransmissions() {
+        let first = PacketEvent::synthetic(EventKind::DataTick);
+        assert_eq!(retry_indicator(&first), None);
+        let retry = PacketEvent {
+            retry: true,
+            ..PacketEvent::synthetic(EventKind::DataTick)
+        };
+        assert_eq!(retry_indicator(&retry), Some(SoundId::RetryGlitch));
+    }
+
+    #[test]
+    fn spaced_tone_groups_follow_the_enabled_kinds() {
+        let mut settings = EventSettings::default();
+        let all = spaced_tone_groups(&settings);
+        assert!(
+            all.iter().any(|group| group.len() == 5),
+            "EAPOL keeps its motif and notes"
+        );
+        assert!(all.contains(&vec![SoundId::BeaconTick]));
+
+        settings.enabled.insert(EventKind::Beacon, false);
+        let fewer = spaced_tone_groups(&settings);
+        assert_eq!(fewer.len(), all.len() - 1);
+        assert!(!fewer.concat().contains(&SoundId::BeaconTick));
+    }
+
+    #[test]
+    fn new_device_pings_are_gated_and_spaced() {
+        let now = Instant::now();
+        let fresh = PacketEvent {
+            new_device: true,
+            ..PacketEvent::synthetic(EventKind::Beacon)
+        };
+        assert!(new_device_cue_due(&fresh, true, false, None, now));
+        assert!(!new_device_cue_due(&fresh, false, false, None, now));
+        assert!(!new_device_cue_due(&fresh, true, true, None, now));
+        let seen = PacketEvent::synthetic(EventKind::Beacon);
+        assert!(!new_device_cue_due(&seen, true, false, None, now));
+
+        let later = now + NEW_DEVICE_CUE_INTERVAL;
+        assert!(!new_device_cue_due(
+            &fresh,
+            true,
+            false,
+            Some(now),
+            later - Duration::from_millis(1)
+        ));
+        assert!(new_device_cue_due(&fresh, true, false, Some(now), later));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::devices::format_mac;
+use anyhow::{Context, Result};
+use pcap::{Capture, Linktype, Packet, PacketHeader};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Beacons kept while waiting for a handshake on their BSSID.
+const MAX_BEACONS: usize = 4096;
+/// Partial handshakes older than this are dropped when the table fills.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_PENDING: usize = 256;
+
+/// Writes each BSSID's first complete 4-way handshake, preceded by its
+/// latest beacon, to a pcap file in `dir` for offline analysis.
+pub struct HandshakeCapture {
+    dir: PathBuf,
+    inner: Mutex<HandshakeState>,
+}
+
+#[derive(Default)]
+struct HandshakeState {
+    /// Latest beacon and SSID per BSSID.
+    beacons: HashMap<[u8; 6], (Option<String>, RawFrame)>,
+    /// Messages 1-4 seen so far per `(bssid, station)`.
+    pending: HashMap<([u8; 6], [u8; 6]), PendingHandshake>,
+    /// BSSIDs already written, or whose file already existed.
+    saved: HashSet<[u8; 6]>,
+}
+
+struct PendingHandshake {
+    started: Instant,
+    messages: [Option<RawFrame>; 4],
+}
+
+/// A packet as captured, link header included.
+struct RawFrame {
+    header: PacketHeader,
+    data: Vec<u8>,
+}
+
+impl RawFrame {
+    fn new(header: &PacketHeader, data: &[u8]) -> Self {
+        Self {
+            header: *header,
+            data: data.to_vec(),
+        }
+    }
+}
+
+impl HandshakeCapture {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            inner: Mutex::new(HandshakeState::default()),
+        }
+    }
+
+    pub fn beacon(&self, bssid: [u8; 6], ssid: Option<&str>, header: &PacketHeader, data: &[u8]) {
+        let Ok(mut state) = self.inner.lock() else {
+            return;
+        };
+        if state.saved.contains(&bssid) {
+            return;
+        }
+        if state.beacons.len() >= MAX_BEACONS
+            && !state.beacons.contains_key(&bssid)
+            && let Some(stale) = state.beacons.keys().next().copied()
+        {
+            state.beacons.remove(&stale);
+        }
+        // Hidden networks beacon an empty SSID; keep a name learned earlier.
+        let ssid = ssid
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .or_else(|| state.beacons.get(&bssid).and_then(|(name, _)| name.clone()));
+        state
+            .beacons
+            .insert(bssid, (ssid, RawFrame::new(header, data)));
+    }
+
+    /// Records handshake message `msg` (1-4) between `bssid` and `station`,
+    /// writing the file once all four are in. A new message 1 starts over.
+    pub fn eapol(
+        &self,
+        linktype: Linktype,
+        bssid: [u8; 6],
+        station: [u8; 6],
+        msg: u8,
+        header: &PacketHeader,
+        data: &[u8],
+    ) {
+        let Some(slot) = usize::from(msg).checked_sub(1).filter(|slot| *slot < 4) else {
+            return;
+        };
+        let Ok(mut state) = self.inner.lock() else {
+            return;
+        };
+        if state.saved.contains(&bssid) {
+            return;
+        }
+        if state.pending.len() >= MAX_PENDING {
+            state
+                .pending
+                .retain(|_, pending| pending.started.elapsed() < HANDSHAKE_TIMEOUT);
+        }
+        let key = (bssid, station);
+        if slot == 0 {
+            state.pending.remove(&key);
+        }
+        let pending = state
+            .pending
+            .entry(key)
+            .or_insert_with(|| PendingHandshake {
+                started: Instant::now(),
+                messages: Default::default(),
+            });
+        pending.messages[slot] = Some(RawFrame::new(header, data));
+        if pending.messages.iter().any(Option::is_none) {
+            return;
+        }
+        let Some(pending) = state.pending.remove(&key) else {
+            return;
+        };
+        let beacon = state.beacons.get(&bssid);
+        let ssid = beacon.and_then(|(name, _)| name.as_deref());
+        let path = self.dir.join(file_name(bssid, ssid));
+        if path.exists() {
+            tracing::info!(
+                "Handshake for {} already saved at {}",
+                format_mac(&bssid),
+                path.display()
+            );
+        } else {
+            let frames = beacon
+                .map(|(_, frame)| frame)
+                .into_iter()
+                .chain(pending.messages.iter().flatten());
+            if let Err(err) = write_pcap(&path, linktype, frames) {
+                // Left unsaved, so the next complete handshake tries again.
+                tracing::warn!("Handshake for {} not saved: {err:#}", format_mac(&bssid));
+                return;
+            }
+            tracing::info!(
+                "Saved 4-way handshake between {} and {} to {}",
+                format_mac(&bssid),
+                format_mac(&station),
+                path.display()
+            );
+        }
+        state.beacons.remove(&bssid);
+        state.saved.insert(bssid);
+    }
+}
+
+/// `<ssid>_<bssid>.pcap`, with anything outside `[A-Za-z0-9._-]` in the
+/// SSID replaced so it can't escape the directory.
+fn file_name(bssid: [u8; 6], ssid: Option<&str>) -> String {
+    let bssid = format_mac(&bssid).replace(':', "-");
+    match ssid {
+        Some(name) => {
+            let name: String = name
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            format!("{}_{bssid}.pcap", name.trim_start_matches('.'))
+        }
+        None => format!("{bssid}.pcap"),
+    }
+}
+
+fn write_pcap<'a>(
+    path: &Path,
+    linktype: Linktype,
+    frames: impl Iterator<Item = &'a RawFrame>,
+) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    let dead = Capture::dead(linktype)?;
+    let mut file = dead
+        .savefile(path)
+        .with_context(|| format!("opening {}", path.display()))?;
+    for frame in frames {
+        file.write(&Packet::new(&frame.header, &frame.data));
+    }
+    file.flush()
+        .with_context(|| format!("writing {}", path.display()))
+}
@@ -2,19 +2,31 @@ mod audio;
 mod config;
 mod devices;
 mod events;
+mod handshake;
+mod record;
+mod simulate;
+mod sinks;
 mod sniffer;
 mod ui;
 mod web;
 
 use crate::audio::AudioEngine;
 use crate::config::AppConfig;
-use crate::devices::DeviceTracker;
-use crate::events::{EventKind, EventSettings, EventWindow, NoiseMode, PacketEvent, RateLimiter};
+use crate::devices::{DeviceTracker, RssiTrend};
+use crate::events::{
+    BeaconFloodDetector, BurstGate, EventKind, EventSettings, EventWindow, NoiseMode, PacketEvent,
+    ProbeCoalescer, ProbeRespFloodDetector, ProbeVerdict, RateKey, RateLimiter, RateMeter,
+    sound_for,
+};
+use crate::record::Recorder;
+use crate::sinks::EventSink;
+use crate::sniffer::SnifferController;
 use crate::web::{AppState, ChannelController};
 use anyhow::Result;
+use std::path::Path;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, mpsc};
 use tracing_subscriber::{EnvFilter, fmt};
 
@@ -29,29 +41,92 @@ async fn main() -> Result<()> {
         config.monitor_interface
     );
 
-    let audio_engine = AudioEngine::new()?;
+    let audio_engine = AudioEngine::new(&config)?;
     let audio_handle = audio_engine.handle();
+    if config.self_test {
+        spawn_self_test(audio_handle.clone());
+    }
 
     let audio_enabled = Arc::new(AtomicBool::new(true));
     let web_sound_enabled = Arc::new(AtomicBool::new(false));
     let volume_by_signal = Arc::new(AtomicBool::new(false));
+    let activity_drone = Arc::new(AtomicBool::new(false));
+    let activity_count = Arc::new(AtomicU64::new(0));
+    spawn_activity_drone(
+        audio_handle.clone(),
+        audio_enabled.clone(),
+        activity_drone.clone(),
+        activity_count.clone(),
+    );
     let (packet_notifier_tx, _) = broadcast::channel(64);
     let channel_controller = ChannelController::new(config.monitor_interface.clone());
     let channels_24 = Arc::new(tokio::sync::RwLock::new(Vec::new()));
     let channels_5 = Arc::new(tokio::sync::RwLock::new(Vec::new()));
+    let channels_6 = Arc::new(tokio::sync::RwLock::new(Vec::new()));
     let event_settings = Arc::new(tokio::sync::RwLock::new(EventSettings::default()));
-    let device_tracker = Arc::new(DeviceTracker::new());
+    // `/api/events` re-spreads the tones whenever the enabled set changes.
+    if let Some(range) = config.audio_spaced_tones {
+        let groups = events::spaced_tone_groups(&*event_settings.read().await);
+        audio_handle.set_spaced_tones(&groups, range);
+    }
+    let device_tracker = Arc::new(DeviceTracker::new(
+        config.max_devices,
+        config.ignore_mac,
+        config.cache_max_entries,
+    ));
+    if let Some(path) = &config.device_aliases {
+        match device_tracker.load_aliases(Path::new(path)) {
+            Ok(count) => tracing::info!("Loaded {count} device aliases from {path}"),
+            Err(err) => tracing::warn!("Device aliases not loaded: {err:#}"),
+        }
+    }
+
+    if config.channel_cue != audio::ChannelCueStyle::Off {
+        spawn_channel_cue(
+            &channel_controller,
+            audio_handle.clone(),
+            audio_enabled.clone(),
+            config.channel_cue,
+        );
+    }
 
     if let Err(err) = channel_controller.refresh_current().await {
         tracing::warn!("Unable to read initial channel: {err:?}");
     }
 
+    let recorder = config
+        .record_db
+        .as_deref()
+        .map(Recorder::open)
+        .transpose()?;
+
     let (packet_tx, mut packet_rx) = mpsc::unbounded_channel::<PacketEvent>();
-    let _sniffer_thread = sniffer::spawn_sniffer(
-        config.monitor_interface.clone(),
-        packet_tx,
+    let alert_tx = packet_tx.clone();
+    let sniffer = SnifferController::new(
+        packet_tx.clone(),
         Arc::clone(&device_tracker),
+        config.discovery_mode,
+        config.debug_recent_frames,
+        Duration::from_millis(config.capture_timeout_ms),
+        config.handshake_dir.as_deref(),
     );
+    if config.simulate {
+        simulate::spawn_simulator(
+            packet_tx.clone(),
+            Arc::clone(&device_tracker),
+            &config.simulate_mix,
+            config.simulate_rate,
+        );
+    } else {
+        sniffer.start(config.monitor_interface.clone());
+        if config.capture_watchdog_secs > 0 {
+            spawn_capture_watchdog(
+                sniffer.clone(),
+                channel_controller.clone(),
+                Duration::from_secs(config.capture_watchdog_secs),
+            );
+        }
+    }
 
     let audio_task_handle = audio_handle.clone();
     let audio_enabled_flag = audio_enabled.clone();
@@ -59,28 +134,215 @@ async fn main() -> Result<()> {
     let packet_notifier = packet_notifier_tx.clone();
     let settings_handle = event_settings.clone();
     let volume_by_signal_flag = volume_by_signal.clone();
+    let drone_flag = activity_drone.clone();
+    let activity_counter = activity_count.clone();
     let device_filter = device_tracker.clone();
+    let mut sinks = build_sinks(
+        &config,
+        recorder.as_ref(),
+        &device_tracker,
+        &channel_controller,
+    );
+    let flood_threshold = config.beacon_flood_threshold;
+    let probe_flood_threshold = config.probe_resp_flood_threshold;
+    let role_pan_width = config.audio_role_pan;
+    let tuned_channel = channel_controller.clone();
+    let reset_requested = Arc::new(AtomicBool::new(false));
+    let reset_flag = reset_requested.clone();
     let audio_task = tokio::spawn(async move {
         let mut window = EventWindow::new(Duration::from_millis(100));
         let mut limiter = RateLimiter::new();
         let mut data_counter: u32 = 0;
-        while let Some(evt) = packet_rx.recv().await {
-            if !device_filter.allows(evt.src, evt.bssid) {
-                continue;
+        let mut data_rate = RateMeter::new(Duration::from_secs(2));
+        let mut ack_rate = RateMeter::new(Duration::from_secs(1));
+        let mut last_hiss: Option<Instant> = None;
+        let mut flood_detector = BeaconFloodDetector::new(Duration::from_secs(2), flood_threshold);
+        let mut probe_flood_detector =
+            ProbeRespFloodDetector::new(Duration::from_secs(2), probe_flood_threshold);
+        let mut probe_coalescer = ProbeCoalescer::new(Duration::from_millis(500));
+        let mut burst_gate = BurstGate::new(BURST_MIN_SUPPRESSED, BURST_INTERVAL);
+        let mut last_trend_cue: Option<Instant> = None;
+        let mut last_new_device_cue: Option<Instant> = None;
+        let mut last_locate_click: Option<Instant> = None;
+        while let Some(mut evt) = packet_rx.recv().await {
+            // `/api/reset-all` starts every gate and detector from scratch.
+            if reset_flag.swap(false, Ordering::Relaxed) {
+                window = EventWindow::new(Duration::from_millis(100));
+                limiter = RateLimiter::new();
+                data_counter = 0;
+                data_rate = RateMeter::new(Duration::from_secs(2));
+                ack_rate = RateMeter::new(Duration::from_secs(1));
+                last_hiss = None;
+                flood_detector = BeaconFloodDetector::new(Duration::from_secs(2), flood_threshold);
+                probe_flood_detector =
+                    ProbeRespFloodDetector::new(Duration::from_secs(2), probe_flood_threshold);
+                probe_coalescer = ProbeCoalescer::new(Duration::from_millis(500));
+                burst_gate = BurstGate::new(BURST_MIN_SUPPRESSED, BURST_INTERVAL);
+                last_trend_cue = None;
+                last_new_device_cue = None;
+                last_locate_click = None;
+            }
+            // Alerts are queued behind the frame that tripped a detector so
+            // they run through the same gates as captured events.
+            if evt.kind == EventKind::Beacon
+                && let Some(bssid) = evt.bssid
+                && flood_detector.observe(bssid)
+            {
+                tracing::warn!(
+                    "Possible beacon flood: {flood_threshold} distinct BSSIDs within 2s"
+                );
+                let _ = alert_tx.send(PacketEvent {
+                    channel: evt.channel,
+                    ..PacketEvent::synthetic(EventKind::BeaconFlood)
+                });
+            } else if evt.kind == EventKind::ProbeResp
+                && let Some(bssid) = evt.bssid
+                && probe_flood_detector.observe(bssid)
+            {
+                tracing::warn!(
+                    "Possible probe-response flood from {}: {probe_flood_threshold} responses within 2s",
+                    devices::format_mac(&bssid)
+                );
+                let _ = alert_tx.send(PacketEvent {
+                    kind: EventKind::ProbeRespFlood,
+                    rate_key: RateKey::Bssid(bssid),
+                    retry: false,
+                    amplitude: 1.0,
+                    group_addressed: false,
+                    eapol_msg: None,
+                    fc: 0,
+                    new_device: false,
+                    ..evt.clone()
+                });
             }
             let settings = settings_handle.read().await.clone();
+            // Locator mode replaces normal playback with clicks from one
+            // device; events still reach the recorder and the WebSocket feed.
+            let locating = settings.locate_target.is_some();
+            if let Some(target) = settings.locate_target
+                && evt.src == Some(target)
+                && let Some(dbm) = evt.signal_dbm
+                && last_locate_click
+                    .is_none_or(|at| at.elapsed() >= events::locate_click_interval(dbm))
+            {
+                last_locate_click = Some(Instant::now());
+                if audio_enabled_flag.load(Ordering::Relaxed) {
+                    audio_task_handle.play(audio::SoundId::GeigerClick, false, 1.0, 1.0, 0.0);
+                }
+            }
+            if evt.kind == EventKind::Other && !settings.capture_unclassified {
+                continue;
+            }
+            // Alerts speak for the whole channel, so device and SSID focus
+            // don't apply to them.
+            if !evt.kind.is_alert() && !device_filter.allows(evt.filter_addr(), evt.bssid) {
+                continue;
+            }
+            // Like the trend cue, the new-device ping ignores per-kind gates;
+            // one global interval keeps a crowded room from chiming nonstop.
+            if events::new_device_cue_due(
+                &evt,
+                settings.new_device_cue,
+                locating,
+                last_new_device_cue,
+                Instant::now(),
+            ) {
+                last_new_device_cue = Some(Instant::now());
+                if audio_enabled_flag.load(Ordering::Relaxed) {
+                    audio_task_handle.play(audio::SoundId::NewDevice, false, 1.0, 1.0, 0.0);
+                }
+            }
+            // The trend cue follows the target's own frames and bypasses the
+            // event gates below, which would otherwise starve it.
+            if !locating
+                && let Some(target) = settings.trend_target
+                && evt.src == Some(target)
+                && last_trend_cue.is_none_or(|at| at.elapsed() >= TREND_CUE_INTERVAL)
+            {
+                last_trend_cue = Some(Instant::now());
+                let cue = match device_filter.rssi_trend(target, TREND_SPAN) {
+                    Some(RssiTrend::Approaching) => Some(audio::SoundId::ApproachCue),
+                    Some(RssiTrend::Leaving) => Some(audio::SoundId::LeaveCue),
+                    _ => None,
+                };
+                if let Some(cue) = cue
+                    && audio_enabled_flag.load(Ordering::Relaxed)
+                {
+                    audio_task_handle.play(cue, false, 1.0, 1.0, 0.0);
+                }
+            }
             if !*settings.enabled.get(&evt.kind).unwrap_or(&true) {
                 continue;
             }
+            if !settings.band_allows(evt.channel) {
+                continue;
+            }
+            if !settings.channel_allows(evt.channel, tuned_channel.tuned()) {
+                continue;
+            }
+            // Gate weak frames from audio only; the tracker has already seen them.
+            if !settings.rssi_allows(evt.signal_dbm) {
+                continue;
+            }
+
+            if !settings.group_data_allows(&evt) {
+                continue;
+            }
+
+            activity_counter.fetch_add(1, Ordering::Relaxed);
+            // The drone stands in for per-event sounds, except kinds that
+            // must never be missed. Muted SSIDs only lose their sounds; their
+            // events still reach the sinks and the WebSocket feed.
+            let per_event_audio = audio_enabled_flag.load(Ordering::Relaxed)
+                && (!drone_flag.load(Ordering::Relaxed) || settings.is_high_priority(&evt.kind))
+                && !device_filter.ssid_muted(&evt, &settings.mute_ssids);
+
+            // ACKs become a continuous hiss whose level follows their rate,
+            // refreshed one chunk at a time while they keep arriving. The
+            // hiss is played before the rate gates, which then only decide
+            // whether the ACK is forwarded.
+            let hissed =
+                evt.kind == EventKind::Ack && settings.ack_hiss_modes.contains(&settings.mode);
+            if hissed {
+                ack_rate.record();
+                if last_hiss.is_none_or(|at| at.elapsed() >= HISS_CHUNK_INTERVAL)
+                    && !locating
+                    && per_event_audio
+                {
+                    last_hiss = Some(Instant::now());
+                    let gain = events::ack_hiss_gain(ack_rate.rate());
+                    audio_task_handle.play(audio::SoundId::AckHiss, false, gain, 1.0, 0.0);
+                }
+            }
+
+            // The hiss above already covers hissed ACKs.
+            let audible = !hissed && !locating && per_event_audio;
+            // Every retransmission gets its marker, ahead of the aggregation
+            // and rate gates below that would swallow most of them; nothing
+            // else is sounded in this mode.
+            if settings.retries_only
+                && audible
+                && let Some(sound) = events::retry_indicator(&evt)
+            {
+                let pan = events::role_pan(evt.role, role_pan_width);
+                audio_task_handle.play(sound, false, RETRY_INDICATOR_GAIN, 1.0, pan);
+            }
+            let audible = audible && !settings.retries_only;
+
+            if evt.kind == EventKind::ProbeReq {
+                match probe_coalescer.admit(probe_storm_threshold(&settings.mode), Instant::now()) {
+                    ProbeVerdict::Pass => {}
+                    // The summary stands for many MACs, so drop the per-MAC key.
+                    ProbeVerdict::Summary => evt.rate_key = RateKey::none(),
+                    ProbeVerdict::Drop => continue,
+                }
+            }
 
             // Data tick aggregation
             if evt.kind == EventKind::DataTick {
+                data_rate.record();
                 data_counter += 1;
-                let threshold = match settings.mode {
-                    NoiseMode::Crowded => 100,
-                    NoiseMode::Sparse => 10,
-                };
-                if data_counter < threshold {
+                if data_counter < settings.data_tick_n() {
                     continue;
                 }
                 data_counter = 0;
@@ -91,23 +353,40 @@ async fn main() -> Result<()> {
                 NoiseMode::Sparse => (5, 3, 1),
             };
 
-            if !window.try_count(&evt.kind, max_mgmt, max_ctrl, max_data) {
+            if !window.admit(&evt.kind, &settings, max_mgmt, max_ctrl, max_data) {
                 continue;
             }
 
             let min_gap = min_interval_for(&evt.kind, &settings.mode);
             if !limiter.allow(&evt.kind, evt.rate_key.clone(), min_gap) {
+                if settings.burst_on_suppress
+                    && audible
+                    && let Some(count) = burst_gate.suppressed(&evt.kind, &evt.rate_key)
+                {
+                    let pitch = events::burst_pitch(count, BURST_MIN_SUPPRESSED);
+                    let pan = events::role_pan(evt.role, role_pan_width);
+                    audio_task_handle.play(audio::SoundId::Burst, false, 1.0, pitch, pan);
+                }
                 continue;
             }
 
-            if audio_enabled_flag.load(Ordering::Relaxed) {
-                let sound = sound_for(&evt.kind);
+            for sink in sinks.iter_mut() {
+                sink.handle(&evt);
+            }
+            if audible {
+                let sound = sound_for(&evt);
                 let gain = if volume_by_signal_flag.load(Ordering::Relaxed) {
                     evt.amplitude
                 } else {
                     1.0
                 };
-                audio_task_handle.play(sound, evt.retry, gain);
+                let pitch = if settings.data_tick_pitch && evt.kind == EventKind::DataTick {
+                    events::data_tick_pitch(data_rate.rate())
+                } else {
+                    events::assoc_pitch(&evt, settings.merge_assoc_family)
+                };
+                let pan = events::role_pan(evt.role, role_pan_width);
+                audio_task_handle.play(sound, evt.retry, gain, pitch, pan);
             }
             if web_sound_flag.load(Ordering::Relaxed) {
                 let _ = packet_notifier.send(evt.clone());
@@ -120,12 +399,20 @@ async fn main() -> Result<()> {
         audio_enabled,
         web_sound_enabled,
         volume_by_signal,
+        activity_drone,
         packet_tx: packet_notifier_tx,
         channel: channel_controller,
         channels_24,
         channels_5,
+        channels_6,
         event_settings,
         device_tracker,
+        sniffer,
+        recorder,
+        paused_sound: Arc::new(std::sync::Mutex::new(None)),
+        meter: audio_handle.meter(),
+        audio: audio_handle.clone(),
+        reset_requested,
     };
 
     web::serve(state).await?;
@@ -134,10 +421,179 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Plays the whole palette once, then checks the output actually drained
+/// the mixer; a stalled or misconfigured device leaves samples behind.
+fn spawn_self_test(handle: audio::AudioHandle) {
+    let test = handle.self_test();
+    let queued = test.queued;
+    tracing::info!(
+        "Audio self-test: playing {queued} sounds over {:.1}s",
+        test.length.as_secs_f32()
+    );
+    tokio::spawn(async move {
+        tokio::time::sleep(test.length + Duration::from_millis(500)).await;
+        match handle.self_test_remaining(&test) {
+            0 => tracing::info!("Audio self-test passed: output played all {queued} sounds"),
+            left => tracing::warn!(
+                "Audio self-test failed: {left} samples still pending; is the output device running?"
+            ),
+        }
+    });
+}
+
+/// Steers the activity drone from the smoothed rate of events reaching the
+/// audio task, and silences it while the mode or the audio output is off.
+fn spawn_activity_drone(
+    handle: audio::AudioHandle,
+    audio_enabled: Arc<AtomicBool>,
+    drone: Arc<AtomicBool>,
+    events: Arc<AtomicU64>,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(DRONE_UPDATE_INTERVAL);
+        let alpha = DRONE_UPDATE_INTERVAL.as_secs_f32() / DRONE_SMOOTHING.as_secs_f32();
+        let mut last_count = events.load(Ordering::Relaxed);
+        let mut rate = 0.0f32;
+        let mut active = false;
+        loop {
+            ticker.tick().await;
+            let count = events.load(Ordering::Relaxed);
+            let instant =
+                count.wrapping_sub(last_count) as f32 / DRONE_UPDATE_INTERVAL.as_secs_f32();
+            last_count = count;
+            rate += (instant - rate) * alpha.min(1.0);
+            let on = drone.load(Ordering::Relaxed) && audio_enabled.load(Ordering::Relaxed);
+            if on {
+                handle.set_drone(Some(audio::drone_params(rate)));
+            } else if active {
+                handle.set_drone(None);
+            }
+            active = on;
+        }
+    });
+}
+
+/// Reopens the capture after `silence` without a single packet, the usual
+/// sign that the interface dropped out of monitor mode.
+fn spawn_capture_watchdog(
+    sniffer: SnifferController,
+    channel: ChannelController,
+    silence: Duration,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval((silence / 4).min(Duration::from_secs(5)));
+        loop {
+            ticker.tick().await;
+            if !sniffer.is_stalled(silence) {
+                continue;
+            }
+            let interface = channel.interface().await;
+            tracing::warn!(
+                "No frames on {interface} for {}s; reopening the capture",
+                silence.as_secs()
+            );
+            sniffer.stop().await;
+            sniffer.start(interface);
+        }
+    });
+}
+
+/// Plays the configured cue for every channel switch made through the
+/// controller.
+fn spawn_channel_cue(
+    channel: &ChannelController,
+    handle: audio::AudioHandle,
+    audio_enabled: Arc<AtomicBool>,
+    style: audio::ChannelCueStyle,
+) {
+    let mut changes = channel.subscribe_changes();
+    tokio::spawn(async move {
+        loop {
+            match changes.recv().await {
+                Ok(channel) => {
+                    if audio_enabled.load(Ordering::Relaxed) {
+                        handle.announce_channel(channel, style);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+fn build_sinks(
+    config: &AppConfig,
+    recorder: Option<&Recorder>,
+    #[cfg_attr(not(feature = "mqtt"), allow(unused_variables))] tracker: &Arc<DeviceTracker>,
+    #[cfg_attr(not(feature = "mqtt"), allow(unused_variables))] channel: &ChannelController,
+) -> Vec<Box<dyn EventSink>> {
+    let mut sinks: Vec<Box<dyn EventSink>> = Vec::new();
+    if let Some(recorder) = recorder {
+        sinks.push(Box::new(recorder.clone()));
+    }
+    #[cfg(feature = "gpio")]
+    if let Some(pin) = config.gpio_pin {
+        let kinds = sinks::KindFilter::parse(&config.gpio_kinds);
+        let pulse = Duration::from_millis(config.gpio_pulse_ms);
+        match sinks::GpioSink::new(pin, kinds, pulse) {
+            Ok(sink) => sinks.push(Box::new(sink)),
+            Err(err) => tracing::warn!("GPIO sink disabled: {err:?}"),
+        }
+    }
+    #[cfg(not(feature = "gpio"))]
+    if config.gpio_pin.is_some() {
+        tracing::warn!("GPIO_PIN is set but this build lacks the gpio feature");
+    }
+    #[cfg(feature = "mqtt")]
+    if let Some(broker) = &config.mqtt_broker {
+        match sinks::MqttSink::spawn(
+            broker,
+            &config.mqtt_client_id,
+            config.mqtt_topic.clone(),
+            Arc::clone(tracker),
+            channel.clone(),
+        ) {
+            Ok(sink) => sinks.push(Box::new(sink)),
+            Err(err) => tracing::warn!("MQTT sink disabled: {err:?}"),
+        }
+    }
+    #[cfg(not(feature = "mqtt"))]
+    if config.mqtt_broker.is_some() {
+        tracing::warn!("MQTT_BROKER is set but this build lacks the mqtt feature");
+    }
+    sinks
+}
+
 fn init_tracing() {
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("info,tower_http=info"));
-    let _ = fmt().with_env_filter(env_filter).try_init();
+    // Logs go to stderr so stdout stays clean for the pipe audio sink.
+    let _ = fmt()
+        .with_env_filter(env_filter)
+        .with_writer(std::io::stderr)
+        .try_init();
+}
+
+const TREND_CUE_INTERVAL: Duration = Duration::from_millis(1500);
+const TREND_SPAN: Duration = Duration::from_secs(5);
+/// Slightly shorter than the 120 ms hiss chunk so consecutive chunks overlap.
+const HISS_CHUNK_INTERVAL: Duration = Duration::from_millis(100);
+/// Rate-limited drops that trigger a burst, and the minimum gap between bursts.
+const BURST_MIN_SUPPRESSED: u32 = 8;
+const BURST_INTERVAL: Duration = Duration::from_millis(750);
+/// How often the drone follows the event rate, and the rate's smoothing.
+const DRONE_UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+const DRONE_SMOOTHING: Duration = Duration::from_secs(2);
+/// Retries-only mode plays its indicator below the normal event level.
+const RETRY_INDICATOR_GAIN: f32 = 0.5;
+
+/// Aggregate probe requests per second above which they are coalesced.
+fn probe_storm_threshold(mode: &NoiseMode) -> f32 {
+    match mode {
+        NoiseMode::Crowded => 15.0,
+        NoiseMode::Sparse => 30.0,
+    }
 }
 
 fn min_interval_for(kind: &EventKind, mode: &NoiseMode) -> Duration {
@@ -154,21 +610,8 @@ fn min_interval_for(kind: &EventKind, mode: &NoiseMode) -> Duration {
             NoiseMode::Sparse => 40,
         }),
         EventKind::DataTick => Duration::from_millis(200),
-    }
-}
-
-fn sound_for(kind: &EventKind) -> audio::SoundId {
-    use audio::SoundId::*;
-    match kind {
-        EventKind::Beacon => BeaconTick,
-        EventKind::ProbeReq => ProbeChirp,
-        EventKind::ProbeResp => ProbeReply,
-        EventKind::Assoc => AssocUp,
-        EventKind::Deauth => DeauthZap,
-        EventKind::Eapol => EapolMotif,
-        EventKind::Rts => RtsKnock,
-        EventKind::Cts => CtsKnockback,
-        EventKind::Ack => AckClick,
-        EventKind::DataTick => DataTick,
+        EventKind::BeaconFlood | EventKind::ProbeRespFlood => Duration::from_secs(2),
+        EventKind::Other => Duration::from_millis(100),
+        EventKind::NullData => Duration::from_millis(200),
     }
 }
@@ -0,0 +1,254 @@
+use crate::devices::format_mac;
+use crate::events::PacketEvent;
+use anyhow::{Context, Result};
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use utoipa::ToSchema;
+
+const QUEUE_DEPTH: usize = 4096;
+const MAX_BATCH: usize = 256;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    started_ms INTEGER NOT NULL,
+    ended_ms INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS events (
+    session_id INTEGER NOT NULL REFERENCES sessions(id),
+    ts_ms INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    src TEXT,
+    bssid TEXT,
+    rssi_dbm INTEGER,
+    channel INTEGER
+);
+CREATE INDEX IF NOT EXISTS events_session_ts ON events(session_id, ts_ms);
+";
+
+struct RecordRow {
+    ts_ms: i64,
+    kind: &'static str,
+    src: Option<String>,
+    bssid: Option<String>,
+    rssi_dbm: Option<i8>,
+    channel: Option<u16>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionRange {
+    pub id: i64,
+    pub started_ms: i64,
+    pub ended_ms: i64,
+    pub events: i64,
+}
+
+/// Persists accepted events to SQLite. Events are queued to a writer thread
+/// so the audio task never blocks on disk; when the queue is full rows are
+/// dropped rather than delaying playback.
+#[derive(Clone)]
+pub struct Recorder {
+    tx: mpsc::Sender<RecordRow>,
+    path: PathBuf,
+}
+
+impl Recorder {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let conn = Connection::open(&path)
+            .with_context(|| format!("Failed to open record database {}", path.display()))?;
+        conn.execute_batch(SCHEMA)
+            .context("Failed to create record schema")?;
+        let now = now_ms();
+        conn.execute(
+            "INSERT INTO sessions (started_ms, ended_ms) VALUES (?1, ?1)",
+            params![now],
+        )
+        .context("Failed to start record session")?;
+        let session_id = conn.last_insert_rowid();
+        tracing::info!(
+            "Recording events to {} (session {session_id})",
+            path.display()
+        );
+
+        let (tx, rx) = mpsc::channel(QUEUE_DEPTH);
+        thread::Builder::new()
+            .name("radioscope-record".into())
+            .spawn(move || run_writer(conn, session_id, rx))
+            .context("Failed to spawn record writer")?;
+        Ok(Self { tx, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(&self, evt: &PacketEvent) {
+        let row = RecordRow {
+            ts_ms: now_ms(),
+            kind: evt.kind.label(),
+            src: evt.src.as_ref().map(format_mac),
+            bssid: evt.bssid.as_ref().map(format_mac),
+            rssi_dbm: evt.signal_dbm,
+            channel: evt.channel,
+        };
+        if self.tx.try_send(row).is_err() {
+            tracing::debug!("Record queue full; dropping event");
+        }
+    }
+}
+
+fn run_writer(mut conn: Connection, session_id: i64, mut rx: mpsc::Receiver<RecordRow>) {
+    let mut batch = Vec::with_capacity(MAX_BATCH);
+    while let Some(row) = rx.blocking_recv() {
+        batch.push(row);
+        while batch.len() < MAX_BATCH {
+            match rx.try_recv() {
+                Ok(row) => batch.push(row),
+                Err(_) => break,
+            }
+        }
+        if let Err(err) = write_batch(&mut conn, session_id, &batch) {
+            tracing::warn!("Failed to write {} recorded events: {err:?}", batch.len());
+        }
+        batch.clear();
+    }
+}
+
+fn write_batch(conn: &mut Connection, session_id: i64, batch: &[RecordRow]) -> Result<()> {
+    let tx = conn.transaction()?;
+    {
+        let mut insert = tx.prepare_cached(
+            "INSERT INTO events (session_id, ts_ms, kind, src, bssid, rssi_dbm, channel)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        for row in batch {
+            insert.execute(params![
+                session_id,
+                row.ts_ms,
+                row.kind,
+                row.src,
+                row.bssid,
+                row.rssi_dbm,
+                row.channel,
+            ])?;
+        }
+    }
+    if let Some(last) = batch.last() {
+        tx.execute(
+            "UPDATE sessions SET ended_ms = ?1 WHERE id = ?2",
+            params![last.ts_ms, session_id],
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Lists recorded sessions, newest first. Opens its own connection so it can
+/// run alongside the writer thread.
+pub fn list_sessions(path: &Path) -> Result<Vec<SessionRange>> {
+    let conn = Connection::open(path)
+        .with_context(|| format!("Failed to open record database {}", path.display()))?;
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.started_ms, s.ended_ms, COUNT(e.session_id)
+         FROM sessions s LEFT JOIN events e ON e.session_id = s.id
+         GROUP BY s.id ORDER BY s.started_ms DESC",
+    )?;
+    let sessions = stmt
+        .query_map([], |row| {
+            Ok(SessionRange {
+                id: row.get(0)?,
+                started_ms: row.get(1)?,
+                ended_ms: row.get(2)?,
+                events: row.get(3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(sessions)
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::DeviceRole;
+    use crate::events::{EventKind, RateKey};
+    use std::time::{Duration, Instant};
+
+    fn event(kind: EventKind, src: [u8; 6]) -> PacketEvent {
+        PacketEvent {
+            kind,
+            rate_key: RateKey::None,
+            retry: false,
+            new_device: false,
+            amplitude: 1.0,
+            src: Some(src),
+            receiver: None,
+            bssid: None,
+            channel: Some(6),
+            signal_dbm: Some(-50),
+            eapol_msg: None,
+            group_addressed: false,
+            seq: None,
+            fc: 0,
+            role: DeviceRole::Unknown,
+        }
+    }
+
+    #[test]
+    fn recorded_events_are_queryable() {
+        let path = std::env::temp_dir().join(format!(
+            "radioscope-record-test-{}-{}.db",
+            std::process::id(),
+            now_ms()
+        ));
+        let recorder = Recorder::open(&path).unwrap();
+        recorder.record(&event(EventKind::Beacon, [2, 0, 0, 0, 0, 1]));
+        recorder.record(&event(EventKind::ProbeReq, [2, 0, 0, 0, 0, 2]));
+        recorder.record(&event(EventKind::Deauth, [2, 0, 0, 0, 0, 1]));
+
+        // Rows land asynchronously on the writer thread.
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let sessions = loop {
+            let sessions = list_sessions(&path).unwrap();
+            if sessions[0].events == 3 || Instant::now() > deadline {
+                break sessions;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].events, 3);
+        assert!(sessions[0].ended_ms >= sessions[0].started_ms);
+
+        let conn = Connection::open(&path).unwrap();
+        let kinds: Vec<String> = conn
+            .prepare("SELECT kind FROM events ORDER BY rowid")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(kinds, vec!["beacon", "probe-req", "deauth"]);
+        let src: String = conn
+            .query_row(
+                "SELECT src FROM events WHERE kind = 'probe-req'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(src, "02:00:00:00:00:02");
+
+        drop(recorder);
+        let _ = std::fs::remove_file(&path);
+    }
+}
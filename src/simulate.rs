@@ -0,0 +1,245 @@
+use crate::devices::{DeviceRole, DeviceTracker, Observation, Security};
+use crate::events::{EventKind, PacketEvent, RateKey};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Relative weights used when `SIMULATE_MIX` is unset, roughly the shape of
+/// a busy office: mostly data and ACKs, steady beacons, occasional joins.
+pub const DEFAULT_MIX: &str =
+    "beacon=20,probe-req=4,probe-resp=3,assoc=1,deauth=1,eapol=1,rts=3,cts=3,ack=25,data-tick=40";
+
+const SSIDS: [&str; 4] = ["radioscope-lab", "CoffeeShop", "printer-4F", "guest"];
+const CHANNELS: [u16; 5] = [1, 6, 11, 36, 149];
+const CLIENTS_PER_AP: usize = 3;
+
+struct FakeAp {
+    bssid: [u8; 6],
+    ssid: &'static str,
+    channel: u16,
+    rssi_dbm: i8,
+}
+
+struct FakeClient {
+    mac: [u8; 6],
+    ap: usize,
+    rssi_dbm: i8,
+}
+
+/// Generates plausible frames without a radio, feeding the tracker and the
+/// audio task exactly like the sniffer does.
+struct Simulator {
+    rng: StdRng,
+    aps: Vec<FakeAp>,
+    clients: Vec<FakeClient>,
+    kinds: Vec<EventKind>,
+    weights: WeightedIndex<u32>,
+    seq: u16,
+}
+
+/// Starts the generator on the runtime; it stops once the receiver is gone.
+pub fn spawn_simulator(
+    tx: UnboundedSender<PacketEvent>,
+    devices: Arc<DeviceTracker>,
+    mix: &str,
+    events_per_sec: u32,
+) {
+    let Some(mut sim) = Simulator::new(mix) else {
+        tracing::error!("SIMULATE_MIX {mix:?} has no usable entries; simulation disabled");
+        return;
+    };
+    let interval = Duration::from_secs(1) / events_per_sec.max(1);
+    tracing::info!("Simulating traffic at {events_per_sec} events/s (mix: {mix})");
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let (obs, mut evt) = sim.next_event();
+            evt.new_device = devices.observe(obs);
+            if tx.send(evt).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Parses `kind=weight` pairs; unknown kinds and zero weights are skipped.
+fn parse_mix(mix: &str) -> Vec<(EventKind, u32)> {
+    mix.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let (name, weight) = entry.split_once('=').unwrap_or((entry, "1"));
+            let kind = serde_json::from_value(serde_json::Value::String(name.trim().into()));
+            match (kind, weight.trim().parse::<u32>()) {
+                (Ok(EventKind::BeaconFlood | EventKind::ProbeRespFlood), _) => {
+                    tracing::warn!("SIMULATE_MIX: {name} is raised by a detector, not simulated");
+                    None
+                }
+                (Ok(kind), Ok(weight)) if weight > 0 => Some((kind, weight)),
+                (Ok(_), Ok(_)) => None,
+                _ => {
+                    tracing::warn!("Ignoring SIMULATE_MIX entry {entry:?}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+impl Simulator {
+    fn new(mix: &str) -> Option<Self> {
+        let (kinds, weights): (Vec<_>, Vec<_>) = parse_mix(mix).into_iter().unzip();
+        let weights = WeightedIndex::new(weights).ok()?;
+        let mut rng = StdRng::from_entropy();
+        let aps: Vec<FakeAp> = SSIDS
+            .iter()
+            .enumerate()
+            .map(|(i, ssid)| FakeAp {
+                bssid: fake_mac(&mut rng),
+                ssid,
+                channel: CHANNELS[i % CHANNELS.len()],
+                rssi_dbm: rng.gen_range(-85..=-40),
+            })
+            .collect();
+        let clients = (0..aps.len() * CLIENTS_PER_AP)
+            .map(|i| FakeClient {
+                mac: fake_mac(&mut rng),
+                ap: i % aps.len(),
+                rssi_dbm: rng.gen_range(-90..=-35),
+            })
+            .collect();
+        Some(Self {
+            rng,
+            aps,
+            clients,
+            kinds,
+            weights,
+            seq: 0,
+        })
+    }
+
+    fn next_event(&mut self) -> (Observation, PacketEvent) {
+        let kind = self.kinds[self.weights.sample(&mut self.rng)].clone();
+        // Management frames and beacons come from APs; everything else is
+        // split between a client and its AP.
+        let from_ap = match kind {
+            EventKind::Beacon | EventKind::ProbeResp => true,
+            EventKind::ProbeReq | EventKind::Assoc => false,
+            _ => self.rng.gen_bool(0.5),
+        };
+        let client = &self.clients[self.rng.gen_range(0..self.clients.len())];
+        let ap = &self.aps[client.ap];
+        let (src, base_dbm) = if from_ap {
+            (ap.bssid, ap.rssi_dbm)
+        } else {
+            (client.mac, client.rssi_dbm)
+        };
+        // Probe requests are mostly wildcard, before the client knows a BSSID.
+        let bssid = (kind != EventKind::ProbeReq).then_some(ap.bssid);
+        let dbm = base_dbm.saturating_add(self.rng.gen_range(-4..=4));
+        let role = match (from_ap, bssid) {
+            (true, _) => DeviceRole::Ap,
+            (false, Some(_)) => DeviceRole::Client,
+            (false, None) => DeviceRole::Unknown,
+        };
+        let rate_key = match kind {
+            EventKind::Beacon | EventKind::ProbeResp => RateKey::Bssid(ap.bssid),
+            EventKind::ProbeReq => RateKey::Tx(src),
+            EventKind::Assoc | EventKind::Deauth | EventKind::Eapol => {
+                RateKey::Pair(client.mac, ap.bssid)
+            }
+            _ => RateKey::none(),
+        };
+        self.seq = (self.seq + 1) & 0x0fff;
+        let announces = matches!(kind, EventKind::Beacon | EventKind::ProbeResp);
+        let obs = Observation {
+            mac: src,
+            bssid,
+            role,
+            rssi_dbm: Some(dbm),
+            ssid: announces.then(|| ap.ssid.to_string()),
+            channel: Some(ap.channel),
+            rx_channel: None,
+            additional_ssids: Vec::new(),
+            channel_width: None,
+            secondary_channel: None,
+            security: announces.then_some(Security::Wpa2),
+            probe_request: kind == EventKind::ProbeReq,
+            reassociation: false,
+            kind: Some(kind.clone()),
+            country: announces.then(|| "US".to_string()),
+            missed_frames: 0,
+            frame_bytes: frame_bytes(&kind, &mut self.rng),
+        };
+        let group_addressed =
+            kind == EventKind::Beacon || (kind == EventKind::DataTick && self.rng.gen_bool(0.1));
+        let eapol_msg = (kind == EventKind::Eapol).then(|| self.rng.gen_range(1..=4));
+        let evt = PacketEvent {
+            kind,
+            rate_key,
+            retry: self.rng.gen_bool(0.05),
+            new_device: false,
+            amplitude: (0.2 + (dbm as f32 + 90.0) / 60.0 * 0.8).clamp(0.2, 1.0),
+            src: Some(src),
+            receiver: None,
+            bssid,
+            channel: Some(ap.channel),
+            signal_dbm: Some(dbm),
+            seq: Some(self.seq),
+            group_addressed,
+            eapol_msg,
+            fc: 0,
+            role,
+        };
+        (obs, evt)
+    }
+}
+
+/// Typical on-air lengths: fixed-size control frames, mid-sized management
+/// frames and anything up to a full MTU for data.
+fn frame_bytes(kind: &EventKind, rng: &mut impl Rng) -> u32 {
+    match kind {
+        EventKind::Ack | EventKind::Cts => 14,
+        EventKind::Rts => 20,
+        EventKind::Deauth => 26,
+        EventKind::NullData => 28,
+        EventKind::Eapol => rng.gen_range(121..=155),
+        EventKind::Beacon | EventKind::ProbeResp => rng.gen_range(180..=320),
+        EventKind::ProbeReq | EventKind::Assoc => rng.gen_range(60..=140),
+        _ => rng.gen_range(60..=1540),
+    }
+}
+
+/// A random locally administered unicast address, so simulated devices
+/// can't collide with real vendor OUIs.
+fn fake_mac(rng: &mut impl Rng) -> [u8; 6] {
+    let mut mac: [u8; 6] = rng.r#gen();
+    mac[0] = (mac[0] | 0x02) & !0x01;
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn default_mix_produces_a_spread_of_kinds() {
+        let mut sim = Simulator::new(DEFAULT_MIX).expect("default mix parses");
+        let seen: HashSet<_> = (0..5000).map(|_| sim.next_event().1.kind).collect();
+        let mixed: HashSet<_> = parse_mix(DEFAULT_MIX).into_iter().map(|(k, _)| k).collect();
+        assert_eq!(mixed.len(), 10);
+        assert_eq!(seen, mixed);
+    }
+
+    #[test]
+    fn mix_skips_unknown_and_zero_weight_kinds() {
+        let mix = parse_mix("beacon=3, deauth=0, bogus=2, ack, beacon-flood=5");
+        assert_eq!(mix, vec![(EventKind::Beacon, 3), (EventKind::Ack, 1)]);
+        assert!(Simulator::new("bogus=1").is_none());
+    }
+}
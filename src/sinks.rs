@@ -0,0 +1,354 @@
+use crate::devices::format_mac;
+use crate::events::{EventKind, PacketEvent};
+use crate::record::Recorder;
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// Receives every event the audio task accepts, alongside playback. Sinks
+/// run inline on the audio task, so anything slow belongs on a worker.
+pub trait EventSink: Send {
+    fn handle(&mut self, evt: &PacketEvent);
+}
+
+impl EventSink for Recorder {
+    fn handle(&mut self, evt: &PacketEvent) {
+        self.record(evt);
+    }
+}
+
+/// Parses a comma-separated list of kebab-case kinds, skipping unknown ones.
+#[cfg_attr(not(feature = "gpio"), allow(dead_code))]
+fn parse_kinds(list: &str) -> Vec<EventKind> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .filter_map(|name| {
+            let kind = serde_json::from_value(serde_json::Value::String(name.to_string()));
+            if kind.is_err() {
+                tracing::warn!("Ignoring unknown event kind {name:?}");
+            }
+            kind.ok()
+        })
+        .collect()
+}
+
+/// The event kinds a sink reacts to.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(not(feature = "gpio"), allow(dead_code))]
+pub struct KindFilter(Vec<EventKind>);
+
+#[cfg_attr(not(feature = "gpio"), allow(dead_code))]
+impl KindFilter {
+    /// See `parse_kinds`.
+    pub fn parse(list: &str) -> Self {
+        Self(parse_kinds(list))
+    }
+
+    pub fn matches(&self, evt: &PacketEvent) -> bool {
+        self.0.contains(&evt.kind)
+    }
+}
+
+#[cfg(feature = "gpio")]
+pub use gpio::GpioSink;
+
+#[cfg(feature = "gpio")]
+mod gpio {
+    use super::{EventSink, KindFilter};
+    use crate::events::PacketEvent;
+    use anyhow::{Context, Result};
+    use rppal::gpio::Gpio;
+    use std::sync::mpsc::{self, SyncSender};
+    use std::thread;
+    use std::time::Duration;
+
+    /// Pulses a GPIO pin (an LED, typically) for selected event kinds. The
+    /// pin is driven from a worker thread; pulses arriving while one is
+    /// still lit are merged into it.
+    pub struct GpioSink {
+        kinds: KindFilter,
+        pulse: SyncSender<()>,
+    }
+
+    impl GpioSink {
+        pub fn new(pin: u8, kinds: KindFilter, pulse: Duration) -> Result<Self> {
+            let mut output = Gpio::new()
+                .context("Failed to open GPIO")?
+                .get(pin)
+                .with_context(|| format!("Failed to claim GPIO pin {pin}"))?
+                .into_output_low();
+            let (tx, rx) = mpsc::sync_channel::<()>(1);
+            thread::Builder::new()
+                .name("radioscope-gpio".into())
+                .spawn(move || {
+                    while rx.recv().is_ok() {
+                        output.set_high();
+                        thread::sleep(pulse);
+                        output.set_low();
+                    }
+                })
+                .context("Failed to spawn GPIO worker")?;
+            tracing::info!("GPIO pin {pin} pulses on {kinds:?}");
+            Ok(Self { kinds, pulse: tx })
+        }
+    }
+
+    impl EventSink for GpioSink {
+        fn handle(&mut self, evt: &PacketEvent) {
+            if self.kinds.matches(evt) {
+                let _ = self.pulse.try_send(());
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct EventJson<'a> {
+    kind: &'a EventKind,
+    retry: bool,
+    amplitude: f32,
+    src: Option<String>,
+    bssid: Option<String>,
+    channel: Option<u16>,
+    signal_dbm: Option<i8>,
+}
+
+/// The JSON body published per event, with MACs in colon notation.
+#[cfg_attr(not(feature = "mqtt"), allow(dead_code))]
+fn event_json(evt: &PacketEvent) -> serde_json::Result<Vec<u8>> {
+    serde_json::to_vec(&EventJson {
+        kind: &evt.kind,
+        retry: evt.retry,
+        amplitude: evt.amplitude,
+        src: evt.src.as_ref().map(format_mac),
+        bssid: evt.bssid.as_ref().map(format_mac),
+        channel: evt.channel,
+        signal_dbm: evt.signal_dbm,
+    })
+}
+
+const MQTT_DEFAULT_PORT: u16 = 1883;
+
+/// Splits `host[:port]` or `[v6]:port`. A bare IPv6 address keeps its
+/// colons and gets the default port.
+#[cfg_attr(not(feature = "mqtt"), allow(dead_code))]
+fn broker_addr(broker: &str) -> Result<(&str, u16)> {
+    let parse_port = |port: &str| {
+        port.parse()
+            .with_context(|| format!("Invalid MQTT port in {broker}"))
+    };
+    if let Some(rest) = broker.strip_prefix('[') {
+        let (host, after) = rest
+            .split_once(']')
+            .with_context(|| format!("Unclosed '[' in MQTT broker {broker}"))?;
+        return match after.strip_prefix(':') {
+            Some(port) => Ok((host, parse_port(port)?)),
+            None if after.is_empty() => Ok((host, MQTT_DEFAULT_PORT)),
+            None => anyhow::bail!("Unexpected {after:?} after ']' in MQTT broker {broker}"),
+        };
+    }
+    match broker.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => Ok((host, parse_port(port)?)),
+        _ => Ok((broker, MQTT_DEFAULT_PORT)),
+    }
+}
+
+#[cfg(feature = "mqtt")]
+pub use mqtt::MqttSink;
+
+#[cfg(feature = "mqtt")]
+mod mqtt {
+    use super::{EventSink, broker_addr, event_json};
+    use crate::devices::DeviceTracker;
+    use crate::events::PacketEvent;
+    use crate::web::ChannelController;
+    use anyhow::Result;
+    use rumqttc::{AsyncClient, MqttOptions, QoS};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    const QUEUE_DEPTH: usize = 1024;
+    const STATE_INTERVAL: Duration = Duration::from_secs(10);
+    const DEVICE_WINDOW: Duration = Duration::from_secs(600);
+
+    /// Publishes accepted events as JSON to `<topic>/events`, plus retained
+    /// `<topic>/devices` and `<topic>/channel` state every few seconds.
+    /// Events are queued to a publisher task and dropped when it falls behind.
+    pub struct MqttSink {
+        tx: mpsc::Sender<PacketEvent>,
+    }
+
+    impl MqttSink {
+        pub fn spawn(
+            broker: &str,
+            client_id: &str,
+            topic: String,
+            tracker: Arc<DeviceTracker>,
+            channel: ChannelController,
+        ) -> Result<Self> {
+            let (host, port) = broker_addr(broker)?;
+            let mut options = MqttOptions::new(client_id, host, port);
+            options.set_keep_alive(Duration::from_secs(30));
+            let (client, mut eventloop) = AsyncClient::new(options, 64);
+
+            tokio::spawn(async move {
+                loop {
+                    if let Err(err) = eventloop.poll().await {
+                        tracing::warn!("MQTT connection error: {err}");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }
+                }
+            });
+
+            let (tx, mut rx) = mpsc::channel::<PacketEvent>(QUEUE_DEPTH);
+            let events_topic = format!("{topic}/events");
+            let publisher = client.clone();
+            tokio::spawn(async move {
+                while let Some(evt) = rx.recv().await {
+                    let Ok(payload) = event_json(&evt) else {
+                        continue;
+                    };
+                    if let Err(err) = publisher
+                        .publish(&events_topic, QoS::AtMostOnce, false, payload)
+                        .await
+                    {
+                        tracing::debug!("MQTT publish failed: {err}");
+                    }
+                }
+            });
+
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(STATE_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    let devices = tracker.snapshot(DEVICE_WINDOW, 0).len().to_string();
+                    let channel = channel
+                        .current()
+                        .await
+                        .map(|ch| ch.to_string())
+                        .unwrap_or_default();
+                    for (suffix, payload) in [("devices", devices), ("channel", channel)] {
+                        let _ = client
+                            .publish(format!("{topic}/{suffix}"), QoS::AtLeastOnce, true, payload)
+                            .await;
+                    }
+                }
+            });
+
+            tracing::info!("Publishing events to MQTT broker {broker}");
+            Ok(Self { tx })
+        }
+    }
+
+    impl EventSink for MqttSink {
+        fn handle(&mut self, evt: &PacketEvent) {
+            if self.tx.try_send(evt.clone()).is_err() {
+                tracing::debug!("MQTT queue full; dropping event");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::DeviceRole;
+    use crate::events::RateKey;
+    use std::sync::{Arc, Mutex};
+
+    /// Stands in for `GpioSink`, filtering the same way: records a pulse
+    /// per selected kind.
+    struct MockSink {
+        kinds: KindFilter,
+        pulses: Arc<Mutex<Vec<EventKind>>>,
+    }
+
+    impl EventSink for MockSink {
+        fn handle(&mut self, evt: &PacketEvent) {
+            if self.kinds.matches(evt) {
+                self.pulses.lock().unwrap().push(evt.kind.clone());
+            }
+        }
+    }
+
+    fn event(kind: EventKind) -> PacketEvent {
+        PacketEvent {
+            kind,
+            rate_key: RateKey::None,
+            retry: false,
+            new_device: false,
+            amplitude: 1.0,
+            src: None,
+            receiver: None,
+            bssid: None,
+            channel: None,
+            signal_dbm: None,
+            eapol_msg: None,
+            group_addressed: false,
+            seq: None,
+            fc: 0,
+            role: DeviceRole::Unknown,
+        }
+    }
+
+    #[test]
+    fn sinks_pulse_for_their_selected_kinds() {
+        let kinds = KindFilter::parse("deauth, eapol,not-a-kind,");
+        assert_eq!(kinds, KindFilter(vec![EventKind::Deauth, EventKind::Eapol]));
+
+        let pulses = Arc::new(Mutex::new(Vec::new()));
+        let mut sinks: Vec<Box<dyn EventSink>> = vec![Box::new(MockSink {
+            kinds,
+            pulses: pulses.clone(),
+        })];
+        for kind in [
+            EventKind::Beacon,
+            EventKind::Deauth,
+            EventKind::DataTick,
+            EventKind::Eapol,
+            EventKind::Deauth,
+        ] {
+            for sink in sinks.iter_mut() {
+                sink.handle(&event(kind.clone()));
+            }
+        }
+        assert_eq!(
+            *pulses.lock().unwrap(),
+            vec![EventKind::Deauth, EventKind::Eapol, EventKind::Deauth]
+        );
+    }
+
+    #[test]
+    fn events_serialize_for_mqtt() {
+        let mut evt = event(EventKind::Deauth);
+        evt.src = Some([0x02, 0, 0, 0, 0, 0x0a]);
+        evt.channel = Some(11);
+        evt.signal_dbm = Some(-61);
+        let json: serde_json::Value = serde_json::from_slice(&event_json(&evt).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "deauth",
+                "retry": false,
+                "amplitude": 1.0,
+                "src": "02:00:00:00:00:0A",
+                "bssid": null,
+                "channel": 11,
+                "signal_dbm": -61,
+            })
+        );
+    }
+
+    #[test]
+    fn broker_addresses_split_off_the_port() {
+        assert_eq!(broker_addr("broker.lan").unwrap(), ("broker.lan", 1883));
+        assert_eq!(broker_addr("10.0.0.5:8883").unwrap(), ("10.0.0.5", 8883));
+        assert_eq!(broker_addr("::1").unwrap(), ("::1", 1883));
+        assert_eq!(broker_addr("fe80::1").unwrap(), ("fe80::1", 1883));
+        assert_eq!(broker_addr("[::1]:8883").unwrap(), ("::1", 8883));
+        assert_eq!(broker_addr("[fe80::1]").unwrap(), ("fe80::1", 1883));
+        assert!(broker_addr("broker.lan:mqtt").is_err());
+        assert!(broker_addr("[::1").is_err());
+    }
+}
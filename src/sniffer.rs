@@ -1,57 +1,386 @@
 use anyhow::{Context, Result};
-use pcap::{Capture, Error as PcapError};
-use std::sync::Arc;
+use pcap::{Active, Capture, Error as PcapError, Linktype, Packet, PacketHeader};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc::UnboundedSender;
+use utoipa::ToSchema;
 
-use crate::devices::{DeviceRole, DeviceTracker};
-use crate::events::{EventKind, PacketEvent, RateKey};
+use crate::devices::{self, DeviceRole, DeviceTracker, Observation, Security};
+use crate::events::{DiscoveryMode, EventKind, PacketEvent, RateKey};
+use crate::handshake::HandshakeCapture;
 
-pub fn spawn_sniffer(
-    interface: String,
+/// Owns the capture thread so it can be stopped and restarted on another
+/// interface without restarting the process.
+#[derive(Clone)]
+pub struct SnifferController {
     tx: UnboundedSender<PacketEvent>,
     devices: Arc<DeviceTracker>,
-) -> thread::JoinHandle<()> {
-    thread::spawn(move || {
-        let tx_clone = tx.clone();
-        match run(&interface, tx, Arc::clone(&devices)) {
-            Ok(_) => {}
-            Err(err) => {
-                tracing::warn!(
-                    "Primary sniffer setup failed on {interface}: {err:?}, retrying without rfmon flag"
-                );
-                if let Err(err2) = run_without_rfmon(&interface, tx_clone, devices) {
-                    tracing::error!("Sniffer error on {interface}: {err2:?}");
-                }
+    running: Arc<Mutex<Option<RunningSniffer>>>,
+    open: OpenSource,
+    /// Read when a capture opens; changing it takes a restart.
+    discovery: Arc<Mutex<DiscoveryMode>>,
+    /// Longest an idle capture blocks before re-checking its stop flag;
+    /// with immediate mode, packets never wait on it.
+    read_timeout_ms: i32,
+    /// Mirrors `EventSettings::capture_unclassified`; read per frame.
+    capture_unclassified: Arc<AtomicBool>,
+    /// Mirrors `EventSettings::retries_only`; read per frame.
+    retries_only: Arc<AtomicBool>,
+    last_frame: Arc<FrameClock>,
+    /// Unclassified frames kept for inspection; `None` unless
+    /// `DEBUG_RECENT_FRAMES` is set.
+    recent: Option<Arc<RecentFrames>>,
+    /// Shared across restarts so each BSSID is saved once per run.
+    handshakes: Option<Arc<HandshakeCapture>>,
+}
+
+/// Cap on bytes kept per frame in `RecentFrames`.
+const RECENT_FRAME_MAX_BYTES: usize = 512;
+
+/// Ring buffer of the last frames that didn't classify, or didn't parse at
+/// all, for `/api/frames/recent`.
+pub struct RecentFrames {
+    capacity: usize,
+    frames: Mutex<VecDeque<RecentFrame>>,
+}
+
+#[derive(Clone, Serialize, ToSchema)]
+pub struct RecentFrame {
+    unix_ms: u64,
+    /// Captured length, link header included.
+    len: usize,
+    /// The captured bytes, link header first, cut at 512 bytes.
+    hex: String,
+    /// `None` when the 802.11 header itself didn't parse.
+    summary: Option<FrameSummary>,
+}
+
+#[derive(Clone, Serialize, ToSchema)]
+pub struct FrameSummary {
+    /// Frame control as hex, e.g. `0x0208`.
+    fc: String,
+    /// 0 management, 1 control, 2 data, 3 extension.
+    frame_type: u8,
+    subtype: u8,
+    addr1: Option<String>,
+    addr2: Option<String>,
+    addr3: Option<String>,
+    bssid: Option<String>,
+    seq: Option<u16>,
+    channel: Option<u16>,
+    signal_dbm: Option<i8>,
+}
+
+impl RecentFrames {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            frames: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Oldest first.
+    pub fn snapshot(&self) -> Vec<RecentFrame> {
+        self.frames
+            .lock()
+            .map(|frames| frames.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn push(&self, data: &[u8], parsed: Option<&ParsedFrame>) {
+        let kept = &data[..data.len().min(RECENT_FRAME_MAX_BYTES)];
+        let frame = RecentFrame {
+            unix_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            len: data.len(),
+            hex: kept.iter().map(|b| format!("{b:02x}")).collect(),
+            summary: parsed.map(|frame| FrameSummary {
+                fc: format!("{:#06x}", frame.fc),
+                frame_type: ((frame.fc >> 2) & 0x3) as u8,
+                subtype: ((frame.fc >> 4) & 0xF) as u8,
+                addr1: frame.addr1.as_ref().map(devices::format_mac),
+                addr2: frame.addr2.as_ref().map(devices::format_mac),
+                addr3: frame.addr3.as_ref().map(devices::format_mac),
+                bssid: frame.bssid.as_ref().map(devices::format_mac),
+                seq: frame.seq,
+                channel: frame.channel,
+                signal_dbm: frame.signal_dbm,
+            }),
+        };
+        let Ok(mut frames) = self.frames.lock() else {
+            return;
+        };
+        if frames.len() == self.capacity {
+            frames.pop_front();
+        }
+        frames.push_back(frame);
+    }
+}
+
+/// When the capture last delivered a packet, as milliseconds since `epoch`
+/// so the capture thread can update it without a lock.
+struct FrameClock {
+    epoch: Instant,
+    last_ms: AtomicU64,
+}
+
+impl Default for FrameClock {
+    fn default() -> Self {
+        Self {
+            epoch: Instant::now(),
+            last_ms: AtomicU64::new(0),
+        }
+    }
+}
+
+impl FrameClock {
+    fn touch(&self) {
+        let ms = self.epoch.elapsed().as_millis().min(u128::from(u64::MAX)) as u64;
+        self.last_ms.store(ms, Ordering::Relaxed);
+    }
+
+    fn since_last(&self) -> Duration {
+        self.epoch
+            .elapsed()
+            .saturating_sub(Duration::from_millis(self.last_ms.load(Ordering::Relaxed)))
+    }
+}
+
+struct RunningSniffer {
+    stop: Arc<AtomicBool>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl SnifferController {
+    pub fn new(
+        tx: UnboundedSender<PacketEvent>,
+        devices: Arc<DeviceTracker>,
+        discovery: DiscoveryMode,
+        recent_frames: usize,
+        read_timeout: Duration,
+        handshake_dir: Option<&str>,
+    ) -> Self {
+        // pcap treats 0 as "wait forever", which would leave `stop` hanging
+        // on a quiet channel.
+        let read_timeout_ms = read_timeout.as_millis().clamp(1, MAX_READ_TIMEOUT_MS) as i32;
+        Self {
+            tx,
+            devices,
+            running: Arc::new(Mutex::new(None)),
+            open: open_device,
+            discovery: Arc::new(Mutex::new(discovery)),
+            read_timeout_ms,
+            capture_unclassified: Arc::new(AtomicBool::new(false)),
+            retries_only: Arc::new(AtomicBool::new(false)),
+            last_frame: Arc::default(),
+            recent: (recent_frames > 0).then(|| Arc::new(RecentFrames::new(recent_frames))),
+            handshakes: handshake_dir.map(|dir| Arc::new(HandshakeCapture::new(dir))),
+        }
+    }
+
+    /// Captures from a source that never delivers a packet instead of a
+    /// pcap device.
+    #[cfg(test)]
+    pub(crate) fn silent(self) -> Self {
+        Self {
+            open: tests::open_silent,
+            ..self
+        }
+    }
+
+    pub fn recent_frames(&self) -> Option<&RecentFrames> {
+        self.recent.as_deref()
+    }
+
+    /// Time since the capture last delivered any packet, counted from the
+    /// latest `start`; `None` while stopped.
+    pub fn since_last_frame(&self) -> Option<Duration> {
+        self.is_running().then(|| self.last_frame.since_last())
+    }
+
+    /// A running capture that has delivered nothing for `limit` has most
+    /// likely lost monitor mode. A zero `limit` never trips.
+    pub fn is_stalled(&self, limit: Duration) -> bool {
+        !limit.is_zero()
+            && self
+                .since_last_frame()
+                .is_some_and(|silence| silence >= limit)
+    }
+
+    pub fn discovery_mode(&self) -> DiscoveryMode {
+        *self.discovery.lock().expect("sniffer controller poisoned")
+    }
+
+    /// Records the mode for the next `start`; returns whether it changed.
+    pub fn set_discovery_mode(&self, mode: DiscoveryMode) -> bool {
+        let mut current = self.discovery.lock().expect("sniffer controller poisoned");
+        std::mem::replace(&mut *current, mode) != mode
+    }
+
+    /// Whether frames no classifier recognises are sent as `Other` events
+    /// instead of dropped. Applies to a running capture immediately.
+    pub fn set_capture_unclassified(&self, enabled: bool) {
+        self.capture_unclassified.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Whether retransmissions are sent even when the original was, so
+    /// each one can be sounded. Applies to a running capture immediately.
+    pub fn set_retries_only(&self, enabled: bool) {
+        self.retries_only.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn start(&self, interface: String) {
+        let stop = Arc::new(AtomicBool::new(false));
+        self.last_frame.touch();
+        let thread = self.spawn_capture(interface, Arc::clone(&stop));
+        let previous = self
+            .running
+            .lock()
+            .expect("sniffer controller poisoned")
+            .replace(RunningSniffer { stop, thread });
+        if let Some(previous) = previous {
+            previous.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn spawn_capture(&self, interface: String, stop: Arc<AtomicBool>) -> thread::JoinHandle<()> {
+        let open = self.open;
+        let read_timeout_ms = self.read_timeout_ms;
+        let tx = self.tx.clone();
+        let devices = Arc::clone(&self.devices);
+        let discovery = self.discovery_mode();
+        let capture_unclassified = Arc::clone(&self.capture_unclassified);
+        let retries_only = Arc::clone(&self.retries_only);
+        let last_frame = Arc::clone(&self.last_frame);
+        let recent = self.recent.clone();
+        let handshakes = self.handshakes.clone();
+        thread::spawn(move || {
+            let result = open(&interface, read_timeout_ms).and_then(|mut source| {
+                let mut state = FrameState::for_linktype(source.linktype())?;
+                state.capture_unclassified = capture_unclassified;
+                state.retries_only = retries_only;
+                state.last_frame = last_frame;
+                state.recent = recent;
+                state.handshakes = handshakes;
+                apply_discovery_mode(source.as_mut(), &mut state, discovery);
+                run(&interface, source.as_mut(), tx, &devices, &stop, state)
+            });
+            if let Err(err) = result {
+                tracing::error!("Sniffer error on {interface}: {err:?}");
             }
+            tracing::info!("Sniffer on {interface} stopped");
+        })
+    }
+
+    /// Whether the capture thread is alive; false once it has exited, e.g.
+    /// because the interface failed to open.
+    pub fn is_running(&self) -> bool {
+        self.running
+            .lock()
+            .expect("sniffer controller poisoned")
+            .as_ref()
+            .is_some_and(|running| !running.thread.is_finished())
+    }
+
+    /// Signals the capture thread and waits for it to exit. The loop checks
+    /// the flag between packets, so this takes at most one capture timeout.
+    pub async fn stop(&self) {
+        let running = self
+            .running
+            .lock()
+            .expect("sniffer controller poisoned")
+            .take();
+        if let Some(running) = running {
+            running.stop.store(true, Ordering::Relaxed);
+            let _ = tokio::task::spawn_blocking(move || running.thread.join()).await;
         }
-    })
+    }
 }
 
-fn run(
-    interface: &str,
-    tx: UnboundedSender<PacketEvent>,
-    devices: Arc<DeviceTracker>,
-) -> Result<()> {
-    let mut cap = Capture::from_device(interface)
+/// Where the capture loop reads packets from: a live device, or canned
+/// frames in tests.
+trait PacketSource: Send {
+    fn next_packet(&mut self) -> Result<Packet<'_>, PcapError>;
+
+    /// Header format in front of each frame.
+    fn linktype(&self) -> Linktype {
+        Linktype::IEEE802_11_RADIOTAP
+    }
+
+    /// Installs a BPF filter so the kernel drops unwanted frames early.
+    fn set_filter(&mut self, _program: &str) -> Result<(), PcapError> {
+        Ok(())
+    }
+}
+
+impl PacketSource for Capture<Active> {
+    fn next_packet(&mut self) -> Result<Packet<'_>, PcapError> {
+        Capture::next_packet(self)
+    }
+
+    fn linktype(&self) -> Linktype {
+        self.get_datalink()
+    }
+
+    fn set_filter(&mut self, program: &str) -> Result<(), PcapError> {
+        self.filter(program, true)
+    }
+}
+
+const MAX_READ_TIMEOUT_MS: u128 = 60_000;
+
+/// Opens an interface with the given read timeout in milliseconds.
+type OpenSource = fn(&str, i32) -> Result<Box<dyn PacketSource>>;
+
+/// Opens `interface` for capture, retrying once if the first attempt fails.
+fn open_device(interface: &str, read_timeout_ms: i32) -> Result<Box<dyn PacketSource>> {
+    let cap = match open_capture(interface, read_timeout_ms) {
+        Ok(cap) => cap,
+        Err(err) => {
+            tracing::warn!(
+                "Primary sniffer setup failed on {interface}: {err:?}, retrying without rfmon flag"
+            );
+            open_capture(interface, read_timeout_ms).context("Fallback capture setup failed")?
+        }
+    };
+    Ok(Box::new(cap))
+}
+
+fn open_capture(interface: &str, read_timeout_ms: i32) -> Result<Capture<Active>> {
+    Capture::from_device(interface)
         .with_context(|| format!("Unable to open device {interface}"))?
         .rfmon(false)
         .promisc(true)
         .immediate_mode(true)
-        .timeout(1_000)
+        .timeout(read_timeout_ms)
         .open()
-        .with_context(|| format!("Failed to start capture on {interface}"))?;
+        .with_context(|| format!("Failed to start capture on {interface}"))
+}
 
-    // No filter yet; we want all management/control/data frames.
-    loop {
-        match cap.next_packet() {
+fn run(
+    interface: &str,
+    source: &mut dyn PacketSource,
+    tx: UnboundedSender<PacketEvent>,
+    devices: &DeviceTracker,
+    stop: &AtomicBool,
+    mut state: FrameState,
+) -> Result<()> {
+    // No filter outside beacon-only mode; we want all management/control/data frames.
+    while !stop.load(Ordering::Relaxed) {
+        match source.next_packet() {
             Ok(packet) => {
-                if let Some(frame) = parse_radiotap_and_frame(packet.data) {
-                    observe_device(&devices, &frame);
-                    if let Some(evt) = classify_frame(&frame) {
-                        let _ = tx.send(evt);
-                    }
-                }
+                state.last_frame.touch();
+                process_packet(packet.data, packet.header, devices, &tx, &mut state)
             }
             Err(PcapError::TimeoutExpired) => continue,
             Err(err) => {
@@ -60,222 +389,541 @@ fn run(
             }
         }
     }
+    Ok(())
 }
 
-fn run_without_rfmon(
-    interface: &str,
-    tx: UnboundedSender<PacketEvent>,
-    devices: Arc<DeviceTracker>,
-) -> Result<()> {
-    let mut cap = Capture::from_device(interface)
-        .with_context(|| format!("Unable to open device {interface} (fallback)"))?
-        .rfmon(false)
-        .promisc(true)
-        .immediate_mode(true)
-        .timeout(1_000)
-        .open()
-        .with_context(|| format!("Failed to start capture on {interface} (fallback)"))?;
+/// Per-capture state carried between packets.
+#[derive(Default)]
+struct FrameState {
+    link: LinkHeader,
+    linktype: Option<Linktype>,
+    /// Drop everything except beacons and probe responses before decoding.
+    beacon_only: bool,
+    dedup: RetryDedup,
+    fragments: Reassembler,
+    capture_unclassified: Arc<AtomicBool>,
+    retries_only: Arc<AtomicBool>,
+    last_frame: Arc<FrameClock>,
+    seq_gaps: SeqGaps,
+    recent: Option<Arc<RecentFrames>>,
+    handshakes: Option<Arc<HandshakeCapture>>,
+}
 
-    loop {
-        match cap.next_packet() {
-            Ok(packet) => {
-                if let Some(frame) = parse_radiotap_and_frame(packet.data) {
-                    observe_device(&devices, &frame);
-                    if let Some(evt) = classify_frame(&frame) {
-                        let _ = tx.send(evt);
-                    }
-                }
+impl FrameState {
+    /// Fails for linktypes that don't carry 802.11 frames (e.g. Ethernet
+    /// from an interface that isn't in monitor mode).
+    fn for_linktype(linktype: Linktype) -> Result<Self> {
+        let name = linktype
+            .get_name()
+            .unwrap_or_else(|_| linktype.0.to_string());
+        let Some(link) = LinkHeader::from_linktype(linktype) else {
+            anyhow::bail!("Unsupported capture linktype {name}; is the interface in monitor mode?");
+        };
+        tracing::info!("Capture linktype {name} ({link:?} headers)");
+        Ok(Self {
+            link,
+            linktype: Some(linktype),
+            ..Self::default()
+        })
+    }
+}
+
+const BEACON_ONLY_FILTER: &str = "type mgt subtype beacon or type mgt subtype probe-resp";
+
+/// In beacon-only mode, lets the kernel discard everything but beacons and
+/// probe responses. `process_packet` checks too, in case the filter can't be
+/// installed.
+fn apply_discovery_mode(
+    source: &mut dyn PacketSource,
+    state: &mut FrameState,
+    mode: DiscoveryMode,
+) {
+    state.beacon_only = mode == DiscoveryMode::BeaconOnly;
+    if !state.beacon_only {
+        return;
+    }
+    match source.set_filter(BEACON_ONLY_FILTER) {
+        Ok(()) => tracing::info!("Beacon-only discovery: BPF filter installed"),
+        Err(err) => tracing::warn!("Beacon-only discovery: unable to install BPF filter: {err}"),
+    }
+}
+
+/// The capture metadata header in front of each 802.11 frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum LinkHeader {
+    #[default]
+    Radiotap,
+    /// wlan-ng style 144-byte header, host-endian.
+    Prism,
+    /// AVS (`DLT_IEEE802_11_RADIO_AVS`) header, big-endian.
+    Avs,
+    /// Bare 802.11 (`DLT_IEEE802_11`): no metadata, so no signal or channel.
+    Bare,
+}
+
+impl LinkHeader {
+    fn from_linktype(linktype: Linktype) -> Option<Self> {
+        match linktype {
+            Linktype::IEEE802_11_RADIOTAP => Some(LinkHeader::Radiotap),
+            Linktype::IEEE802_11_PRISM => Some(LinkHeader::Prism),
+            Linktype::IEEE802_11_AVS => Some(LinkHeader::Avs),
+            Linktype::IEEE802_11 => Some(LinkHeader::Bare),
+            _ => None,
+        }
+    }
+
+    /// Some drivers hand out prism headers under the radiotap linktype;
+    /// radiotap always starts with version 0, so the prism message code is
+    /// unambiguous.
+    fn detect(self, data: &[u8]) -> Self {
+        if self == LinkHeader::Radiotap && data.starts_with(&PRISM_MSGCODE) {
+            LinkHeader::Prism
+        } else {
+            self
+        }
+    }
+}
+
+fn process_packet(
+    data: &[u8],
+    header: &PacketHeader,
+    devices: &DeviceTracker,
+    tx: &UnboundedSender<PacketEvent>,
+    state: &mut FrameState,
+) {
+    let Some(frame) = parse_frame(data, state.link) else {
+        if let Some(recent) = &state.recent {
+            recent.push(data, None);
+        }
+        return;
+    };
+    if state.beacon_only && !is_beacon_or_probe_resp(frame.fc) {
+        return;
+    }
+    if let Some(handshakes) = &state.handshakes
+        && (frame.fc >> 2) & 0x3 == 0
+        && (frame.fc >> 4) & 0xF == 8
+        && let Some(bssid) = frame.bssid
+    {
+        handshakes.beacon(bssid, frame.ssid.as_deref(), header, data);
+    }
+    let capture_unclassified = state.capture_unclassified.load(Ordering::Relaxed);
+    // The debug buffer wants unclassified frames even when they aren't sent.
+    let keep_other = capture_unclassified || state.recent.is_some();
+    // Retransmissions still count towards the device, but aren't re-sounded
+    // unless retries are all that is being listened for.
+    let duplicate = state.dedup.is_duplicate(&frame, Instant::now());
+    let mut evt = if duplicate && !state.retries_only.load(Ordering::Relaxed) {
+        None
+    } else if is_fragment(&frame) {
+        // Fragmented data frames are classified once, on the reassembled
+        // body, so an LLC/EAPOL header split across fragments is still
+        // recognised.
+        state.fragments.push(&frame).and_then(|body| {
+            classify_frame(
+                &ParsedFrame {
+                    payload: &body,
+                    ..frame.clone()
+                },
+                keep_other,
+            )
+        })
+    } else {
+        classify_frame(&frame, keep_other)
+    };
+    if evt.as_ref().is_some_and(|evt| evt.kind == EventKind::Other) {
+        if let Some(recent) = &state.recent {
+            recent.push(data, Some(&frame));
+        }
+        if !capture_unclassified {
+            evt = None;
+        }
+    }
+    let missed = state.seq_gaps.missed(&frame);
+    // `len` is the length on the wire, before any snaplen truncation.
+    let truncated = (header.len as usize).saturating_sub(data.len());
+    let frame_bytes = u32::try_from(frame.len + truncated).unwrap_or(u32::MAX);
+    let new_device = observe_device(
+        devices,
+        &frame,
+        evt.as_ref().map(|evt| evt.kind.clone()),
+        missed,
+        frame_bytes,
+    );
+    if let Some(mut evt) = evt {
+        evt.new_device = new_device;
+        // A reassembled message only has its last fragment in `data`, which
+        // would write a truncated frame to the file.
+        if let (Some(handshakes), Some(linktype), Some(msg), Some(bssid)) =
+            (&state.handshakes, state.linktype, evt.eapol_msg, evt.bssid)
+            && !is_fragment(&frame)
+        {
+            let station = if frame.addr2 == Some(bssid) {
+                frame.addr1
+            } else {
+                frame.addr2
+            };
+            if let Some(station) = station {
+                handshakes.eapol(linktype, bssid, station, msg, header, data);
             }
-            Err(PcapError::TimeoutExpired) => continue,
-            Err(err) => {
-                tracing::warn!("pcap error on {interface} (fallback): {err:?}");
-                continue;
+        }
+        let _ = tx.send(evt);
+    }
+}
+
+fn is_beacon_or_probe_resp(fc: u16) -> bool {
+    let kind_bits = (fc >> 2) & 0x3;
+    let subtype = (fc >> 4) & 0xF;
+    kind_bits == 0 && (subtype == 8 || subtype == 5)
+}
+
+fn is_fragment(frame: &ParsedFrame) -> bool {
+    let is_data = (frame.fc >> 2) & 0x3 == 2;
+    let more_fragments = frame.fc & 0x0400 != 0;
+    is_data && (more_fragments || frame.frag.is_some_and(|frag| frag > 0))
+}
+
+const REASSEMBLY_TIMEOUT: Duration = Duration::from_millis(500);
+const REASSEMBLY_MAX_PENDING: usize = 64;
+const REASSEMBLY_MAX_BYTES: usize = 4096;
+
+struct PendingFragments {
+    next_frag: u8,
+    data: Vec<u8>,
+    started: Instant,
+}
+
+/// Minimal MSDU reassembly keyed by (transmitter, sequence number). Bounded
+/// in entry count and size; gaps or stale entries discard the MSDU.
+#[derive(Default)]
+struct Reassembler {
+    pending: HashMap<([u8; 6], u16), PendingFragments>,
+}
+
+impl Reassembler {
+    /// Adds a fragment's body; returns the whole body once the last
+    /// fragment arrives.
+    fn push(&mut self, frame: &ParsedFrame) -> Option<Vec<u8>> {
+        let (Some(src), Some(seq), Some(frag)) = (frame.addr2, frame.seq, frame.frag) else {
+            return None;
+        };
+        let key = (src, seq);
+        let now = Instant::now();
+        self.pending
+            .retain(|_, p| now.duration_since(p.started) < REASSEMBLY_TIMEOUT);
+        if frag == 0 {
+            if self.pending.len() < REASSEMBLY_MAX_PENDING {
+                self.pending.insert(
+                    key,
+                    PendingFragments {
+                        next_frag: 1,
+                        data: frame.payload.to_vec(),
+                        started: now,
+                    },
+                );
             }
+            return None;
+        }
+        let entry = self.pending.get_mut(&key)?;
+        if entry.next_frag != frag || entry.data.len() + frame.payload.len() > REASSEMBLY_MAX_BYTES
+        {
+            self.pending.remove(&key);
+            return None;
+        }
+        entry.data.extend_from_slice(frame.payload);
+        entry.next_frag += 1;
+        if frame.fc & 0x0400 != 0 {
+            return None;
+        }
+        self.pending.remove(&key).map(|p| p.data)
+    }
+}
+
+const DEDUP_WINDOW: Duration = Duration::from_millis(100);
+const DEDUP_CAPACITY: usize = 4096;
+
+/// Remembers recent (transmitter, sequence, fragment, frame control) keys so
+/// a retried frame arriving shortly after its original isn't classified
+/// twice. The retry bit is masked out of the frame control for the key.
+#[derive(Default)]
+struct RetryDedup {
+    seen: HashMap<([u8; 6], u16, u8, u16), Instant>,
+}
+
+impl RetryDedup {
+    fn is_duplicate(&mut self, frame: &ParsedFrame, now: Instant) -> bool {
+        let (Some(src), Some(seq)) = (frame.addr2, frame.seq) else {
+            return false;
+        };
+        if self.seen.len() >= DEDUP_CAPACITY {
+            self.seen
+                .retain(|_, at| now.saturating_duration_since(*at) < DEDUP_WINDOW);
         }
+        let retry = frame.fc & 0x0800 != 0;
+        let key = (src, seq, frame.frag.unwrap_or(0), frame.fc & !0x0800);
+        let previous = self.seen.insert(key, now);
+        retry && previous.is_some_and(|at| now.saturating_duration_since(at) < DEDUP_WINDOW)
     }
 }
 
-#[derive(Debug)]
+const SEQ_TRACK_CAPACITY: usize = 4096;
+/// Larger forward jumps are taken as a restarted counter, reordering or
+/// time spent tuned elsewhere, not as drops.
+const MAX_SEQ_GAP: u16 = 64;
+
+/// Last sequence number per transmitter, for estimating how many frames
+/// the capture missed. QoS data is keyed apart from everything else since
+/// it numbers from its own per-TID counters.
+#[derive(Default)]
+struct SeqGaps {
+    last: HashMap<([u8; 6], bool), u16>,
+}
+
+impl SeqGaps {
+    /// Frames the transmitter sent between its previous captured frame and
+    /// this one. Retries reuse their number and are skipped.
+    fn missed(&mut self, frame: &ParsedFrame) -> u32 {
+        let (Some(src), Some(seq)) = (frame.addr2, frame.seq) else {
+            return 0;
+        };
+        if frame.fc & 0x0800 != 0 {
+            return 0;
+        }
+        let key = (src, is_qos_data(frame.fc));
+        if self.last.len() >= SEQ_TRACK_CAPACITY && !self.last.contains_key(&key) {
+            self.last.clear();
+        }
+        self.last
+            .insert(key, seq)
+            .map_or(0, |previous| seq_gap(previous, seq))
+    }
+}
+
+/// Sequence numbers skipped going from `previous` to `seq`, across the
+/// 12-bit wrap (4095 -> 2 skipped 0 and 1).
+fn seq_gap(previous: u16, seq: u16) -> u32 {
+    let delta = seq.wrapping_sub(previous) & 0x0fff;
+    if delta == 0 || delta > MAX_SEQ_GAP {
+        0
+    } else {
+        u32::from(delta - 1)
+    }
+}
+
+fn is_qos_data(fc: u16) -> bool {
+    (fc >> 2) & 0x3 == 2 && (fc >> 4) & 0x8 != 0
+}
+
+#[derive(Debug, Clone)]
 struct ParsedFrame<'a> {
     fc: u16,
     _header_len: usize,
+    /// 802.11 bytes captured, from frame control on; no capture header.
+    len: usize,
     payload: &'a [u8],
-    _addr1: Option<[u8; 6]>,
+    addr1: Option<[u8; 6]>,
     addr2: Option<[u8; 6]>,
     addr3: Option<[u8; 6]>,
     bssid: Option<[u8; 6]>,
+    /// Sequence number and fragment number from the sequence control
+    /// field; control frames have neither.
+    seq: Option<u16>,
+    frag: Option<u8>,
     signal_gain: f32,
     signal_dbm: Option<i8>,
     ssid: Option<String>,
+    /// The DS Parameter Set channel for beacons and probe responses that
+    /// carry one, otherwise `rx_channel`.
     channel: Option<u16>,
+    /// The channel the radio received the frame on, from the capture header.
+    rx_channel: Option<u16>,
+    additional_ssids: Vec<String>,
+    width: Option<ChannelWidth>,
+    security: Option<Security>,
+    country: Option<String>,
 }
 
-fn classify_frame(parsed: &ParsedFrame) -> Option<PacketEvent> {
+/// Operating width advertised by an AP's HT/VHT Operation elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ChannelWidth {
+    mhz: u16,
+    /// The 20 MHz secondary channel of a 40 MHz (or wider) BSS.
+    secondary_channel: Option<u16>,
+}
+
+/// Frames no classifier recognises become `Other` events only when
+/// `capture_unclassified` is set. Today that is mgmt ATIM (9) and action
+/// (13, 14), control BlockAckReq (8), PS-Poll (10) and CF-End (14, 15), and
+/// reserved subtypes.
+fn classify_frame(parsed: &ParsedFrame, capture_unclassified: bool) -> Option<PacketEvent> {
     let kind_bits = (parsed.fc >> 2) & 0x3;
     let subtype = (parsed.fc >> 4) & 0xF;
     let retry = parsed.fc & 0x0800 != 0;
 
-    match kind_bits {
+    let evt = match kind_bits {
         0 => classify_mgmt(subtype, retry, parsed),
         1 => classify_ctrl(subtype, retry, parsed),
         2 => classify_data(subtype, retry, parsed),
         _ => None,
-    }
+    };
+    evt.or_else(|| {
+        capture_unclassified.then(|| {
+            frame_event(
+                EventKind::Other,
+                RateKey::none(),
+                retry,
+                parsed,
+                parsed.bssid,
+            )
+        })
+    })
 }
 
 fn classify_mgmt(subtype: u16, retry: bool, frame: &ParsedFrame) -> Option<PacketEvent> {
-    let amplitude = frame.signal_gain;
-    match subtype {
-        8 => {
-            let key = frame
-                .bssid
-                .or(frame.addr3)
-                .map(RateKey::Bssid)
-                .unwrap_or(RateKey::none());
-            Some(PacketEvent {
-                kind: EventKind::Beacon,
-                rate_key: key,
-                retry,
-                amplitude,
-                src: frame.addr2,
-                bssid: frame.bssid,
-            })
-        }
+    let bssid_key = frame
+        .bssid
+        .or(frame.addr3)
+        .map(RateKey::Bssid)
+        .unwrap_or(RateKey::none());
+    let (kind, key, bssid) = match subtype {
+        8 => (EventKind::Beacon, bssid_key, frame.bssid),
         4 => {
             let key = frame.addr2.map(RateKey::Tx).unwrap_or(RateKey::none());
-            Some(PacketEvent {
-                kind: EventKind::ProbeReq,
-                rate_key: key,
-                retry,
-                amplitude,
-                src: frame.addr2,
-                bssid: frame.bssid,
-            })
+            (EventKind::ProbeReq, key, frame.bssid)
         }
-        5 => {
-            let key = frame
-                .bssid
-                .or(frame.addr3)
-                .map(RateKey::Bssid)
-                .unwrap_or(RateKey::none());
-            Some(PacketEvent {
-                kind: EventKind::ProbeResp,
-                rate_key: key,
-                retry,
-                amplitude,
-                src: frame.addr2,
-                bssid: frame.bssid,
-            })
-        }
-        0 | 1 | 2 | 3 => {
+        5 => (EventKind::ProbeResp, bssid_key, frame.bssid),
+        0..=3 => {
             let bssid = frame.bssid.or(frame.addr3);
-            let sta = frame.addr2;
-            let key = match (sta, bssid) {
-                (Some(s), Some(b)) => RateKey::Pair(s, b),
-                _ => RateKey::none(),
-            };
-            Some(PacketEvent {
-                kind: EventKind::Assoc,
-                rate_key: key,
-                retry,
-                amplitude,
-                src: frame.addr2,
-                bssid,
-            })
+            (EventKind::Assoc, pair_key(frame.addr2, bssid), bssid)
         }
         10 | 12 => {
             let bssid = frame.bssid.or(frame.addr3);
-            let sta = frame.addr2;
-            let key = match (sta, bssid) {
-                (Some(s), Some(b)) => RateKey::Pair(s, b),
-                _ => RateKey::none(),
-            };
-            Some(PacketEvent {
-                kind: EventKind::Deauth,
-                rate_key: key,
-                retry,
-                amplitude,
-                src: frame.addr2,
-                bssid,
-            })
+            (EventKind::Deauth, pair_key(frame.addr2, bssid), bssid)
         }
-        _ => None,
-    }
+        _ => return None,
+    };
+    Some(frame_event(kind, key, retry, frame, bssid))
 }
 
 fn classify_ctrl(subtype: u16, retry: bool, frame: &ParsedFrame) -> Option<PacketEvent> {
-    let amplitude = frame.signal_gain;
-    match subtype {
-        11 => Some(PacketEvent {
-            kind: EventKind::Rts,
-            rate_key: RateKey::none(),
-            retry,
-            amplitude,
-            src: frame.addr2,
-            bssid: frame.bssid,
-        }),
-        12 => Some(PacketEvent {
-            kind: EventKind::Cts,
-            rate_key: RateKey::none(),
-            retry,
-            amplitude,
-            src: frame.addr2,
-            bssid: frame.bssid,
-        }),
-        13 | 9 => Some(PacketEvent {
-            kind: EventKind::Ack,
-            rate_key: RateKey::none(),
-            retry,
-            amplitude,
-            src: frame.addr2,
-            bssid: frame.bssid,
-        }),
-        _ => None,
-    }
+    let kind = match subtype {
+        11 => EventKind::Rts,
+        12 => EventKind::Cts,
+        13 | 9 => EventKind::Ack,
+        _ => return None,
+    };
+    Some(frame_event(
+        kind,
+        RateKey::none(),
+        retry,
+        frame,
+        frame.bssid,
+    ))
 }
 
+/// Data subtypes 8-15 are the QoS variants of 0-7; bit 2 marks the
+/// subtypes (Null, CF-Ack, CF-Poll, CF-Ack+CF-Poll and their QoS forms)
+/// that carry no frame body.
 fn classify_data(subtype: u16, retry: bool, frame: &ParsedFrame) -> Option<PacketEvent> {
-    let amplitude = frame.signal_gain;
-    if is_eapol(subtype, frame.payload) {
-        let bssid = frame.bssid.or(frame.addr3);
-        let sta = frame.addr2;
-        let key = match (sta, bssid) {
-            (Some(s), Some(b)) => RateKey::Pair(s, b),
-            _ => RateKey::none(),
-        };
-        return Some(PacketEvent {
-            kind: EventKind::Eapol,
-            rate_key: key,
+    if subtype & 0x04 != 0 {
+        return Some(frame_event(
+            EventKind::NullData,
+            RateKey::none(),
             retry,
-            amplitude,
-            src: frame.addr2,
-            bssid,
-        });
+            frame,
+            frame.bssid,
+        ));
+    }
+    if let Some(body) = eapol_body(subtype, frame.payload) {
+        let bssid = frame.bssid.or(frame.addr3);
+        let key = pair_key(frame.addr2, bssid);
+        let mut evt = frame_event(EventKind::Eapol, key, retry, frame, bssid);
+        evt.eapol_msg = eapol_message(body);
+        return Some(evt);
     }
 
-    Some(PacketEvent {
-        kind: EventKind::DataTick,
-        rate_key: RateKey::none(),
+    Some(frame_event(
+        EventKind::DataTick,
+        RateKey::none(),
         retry,
-        amplitude,
-        src: frame.addr2,
-        bssid: frame.bssid,
-    })
+        frame,
+        frame.bssid,
+    ))
 }
 
-fn observe_device(tracker: &DeviceTracker, frame: &ParsedFrame) {
-    if let Some(mac) = frame.addr2 {
-        tracker.observe(
-            mac,
-            frame.bssid,
-            role_for_frame(frame),
-            frame.signal_dbm,
-            frame.ssid.clone(),
-            frame.channel,
-        );
+fn pair_key(sta: Option<[u8; 6]>, bssid: Option<[u8; 6]>) -> RateKey {
+    match (sta, bssid) {
+        (Some(s), Some(b)) => RateKey::Pair(s, b),
+        _ => RateKey::none(),
+    }
+}
+
+fn frame_event(
+    kind: EventKind,
+    rate_key: RateKey,
+    retry: bool,
+    frame: &ParsedFrame,
+    bssid: Option<[u8; 6]>,
+) -> PacketEvent {
+    PacketEvent {
+        kind,
+        rate_key,
+        retry,
+        new_device: false,
+        amplitude: frame.signal_gain,
+        src: frame.addr2,
+        receiver: frame.addr1.filter(|_| frame.addr2.is_none()),
+        bssid,
+        channel: frame.channel,
+        signal_dbm: frame.signal_dbm,
+        seq: frame.seq,
+        group_addressed: frame.addr1.is_some_and(is_group_addr),
+        eapol_msg: None,
+        fc: frame.fc,
+        role: role_for_frame(frame),
     }
 }
 
+/// The I/G bit: set for broadcast (ff:ff:ff:ff:ff:ff) and multicast groups.
+fn is_group_addr(addr: [u8; 6]) -> bool {
+    addr[0] & 0x01 != 0
+}
+
+/// Frames without a transmitter address (ACK, CTS) are not tracked: their
+/// signal belongs to an unnamed sender, so crediting addr1 would skew its
+/// RSSI. They still reach the audio filter through `PacketEvent::receiver`.
+/// Returns whether the transmitter was new to the tracker.
+fn observe_device(
+    tracker: &DeviceTracker,
+    frame: &ParsedFrame,
+    kind: Option<EventKind>,
+    missed_frames: u32,
+    frame_bytes: u32,
+) -> bool {
+    let Some(mac) = frame.addr2 else {
+        return false;
+    };
+    tracker.observe(Observation {
+        mac,
+        bssid: frame.bssid,
+        role: role_for_frame(frame),
+        rssi_dbm: frame.signal_dbm,
+        ssid: frame.ssid.clone(),
+        channel: frame.channel,
+        rx_channel: frame.rx_channel,
+        additional_ssids: frame.additional_ssids.clone(),
+        channel_width: frame.width.map(|w| w.mhz),
+        secondary_channel: frame.width.and_then(|w| w.secondary_channel),
+        security: frame.security,
+        probe_request: (frame.fc >> 2) & 0x3 == 0 && (frame.fc >> 4) & 0xF == 4,
+        reassociation: (frame.fc >> 2) & 0x3 == 0 && matches!((frame.fc >> 4) & 0xF, 2 | 3),
+        kind,
+        country: frame.country.clone(),
+        missed_frames,
+        frame_bytes,
+    })
+}
+
 fn role_for_frame(frame: &ParsedFrame) -> DeviceRole {
     match (frame.addr2, frame.bssid) {
         (Some(tx), Some(bssid)) if tx == bssid => DeviceRole::Ap,
@@ -284,32 +932,39 @@ fn role_for_frame(frame: &ParsedFrame) -> DeviceRole {
     }
 }
 
+/// Walks tagged information elements as `(id, body)` pairs, stopping at the
+/// first element whose length runs past the end of the buffer.
+struct InfoElements<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for InfoElements<'a> {
+    type Item = (u8, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = *self.data.first()?;
+        let len = *self.data.get(1)? as usize;
+        let body = self.data.get(2..2 + len)?;
+        self.data = &self.data[2 + len..];
+        Some((id, body))
+    }
+}
+
+fn info_elements(data: &[u8]) -> InfoElements<'_> {
+    InfoElements { data }
+}
+
+fn mgmt_elements(subtype: u16, payload: &[u8]) -> Option<InfoElements<'_>> {
+    mgmt_ie_start(subtype, payload).map(|start| info_elements(&payload[start..]))
+}
+
 fn parse_ssid(kind: u16, subtype: u16, payload: &[u8]) -> Option<String> {
     if kind != 0 {
         return None;
     }
-    let start = mgmt_ie_start(subtype, payload)?;
-    if payload.len() < start + 2 {
-        return None;
-    }
-    let mut idx = start;
-    while idx + 2 <= payload.len() {
-        let id = payload[idx];
-        let len = payload[idx + 1] as usize;
-        idx += 2;
-        if idx + len > payload.len() {
-            break;
-        }
-        if id == 0 {
-            let raw = &payload[idx..idx + len];
-            if raw.is_empty() {
-                return Some("<hidden>".to_string());
-            }
-            return decode_ssid(raw);
-        }
-        idx += len;
-    }
-    None
+    mgmt_elements(subtype, payload)?
+        .find(|(id, _)| *id == 0)
+        .and_then(|(_, raw)| decode_ssid(raw))
 }
 
 fn decode_ssid(bytes: &[u8]) -> Option<String> {
@@ -330,21 +985,141 @@ fn decode_ssid(bytes: &[u8]) -> Option<String> {
 }
 
 fn parse_ds_channel(subtype: u16, payload: &[u8]) -> Option<u16> {
-    let start = mgmt_ie_start(subtype, payload)?;
-    let mut idx = start;
-    while idx + 2 <= payload.len() {
-        let id = payload[idx];
-        let len = payload[idx + 1] as usize;
-        idx += 2;
-        if idx + len > payload.len() {
-            break;
+    mgmt_elements(subtype, payload)?
+        .find(|(id, body)| *id == 3 && !body.is_empty())
+        .map(|(_, body)| body[0] as u16)
+}
+
+/// SSIDs of the non-transmitted BSSIDs an AP advertises through Multiple
+/// BSSID elements (id 71). Each element is a max-BSSID indicator followed by
+/// subelements; profile subelements (id 0) nest their own SSID element.
+fn parse_multi_bssid_ssids(subtype: u16, payload: &[u8]) -> Vec<String> {
+    let Some(elements) = mgmt_elements(subtype, payload) else {
+        return Vec::new();
+    };
+    let mut ssids = Vec::new();
+    for (_, body) in elements.filter(|(id, body)| *id == 71 && !body.is_empty()) {
+        for (_, profile) in info_elements(&body[1..]).filter(|(sub_id, _)| *sub_id == 0) {
+            let ssid = info_elements(profile)
+                .find(|(id, _)| *id == 0)
+                .and_then(|(_, raw)| decode_ssid(raw));
+            if let Some(ssid) = ssid
+                && !ssids.contains(&ssid)
+            {
+                ssids.push(ssid);
+            }
         }
-        if id == 3 && len >= 1 {
-            return Some(payload[idx] as u16);
+    }
+    ssids
+}
+
+/// Channel width from HT Operation (id 61) and VHT Operation (id 192).
+/// Beacons and probe responses without HT Operation are legacy 20 MHz.
+fn parse_channel_width(subtype: u16, payload: &[u8]) -> Option<ChannelWidth> {
+    if subtype != 8 && subtype != 5 {
+        return None;
+    }
+    let mut ht = None;
+    let mut vht = None;
+    for (id, body) in mgmt_elements(subtype, payload)? {
+        match id {
+            61 if body.len() >= 2 => ht = Some((body[0] as u16, body[1])),
+            192 if body.len() >= 3 => vht = Some((body[0], body[1], body[2])),
+            _ => {}
         }
-        idx += len;
     }
-    None
+    let Some((primary, ht_info)) = ht else {
+        return Some(ChannelWidth {
+            mhz: 20,
+            secondary_channel: None,
+        });
+    };
+    // Secondary channel offset is only in effect when the STA channel width
+    // bit allows more than 20 MHz.
+    let any_width = ht_info & 0x04 != 0;
+    let secondary_channel = match ht_info & 0x03 {
+        1 if any_width => Some(primary + 4),
+        3 if any_width => primary.checked_sub(4),
+        _ => None,
+    };
+    let mut mhz = if secondary_channel.is_some() { 40 } else { 20 };
+    if let Some((vht_width, _, seg1)) = vht
+        && vht_width >= 1
+    {
+        // Width 1 with a second segment signals 160 or 80+80; 2 and 3 are
+        // the deprecated explicit 160 and 80+80 encodings.
+        mhz = if vht_width >= 2 || seg1 != 0 { 160 } else { 80 };
+    }
+    Some(ChannelWidth {
+        mhz,
+        secondary_channel,
+    })
+}
+
+/// Two-letter code from the Country element (id 7). The third byte of the
+/// country string (indoor/outdoor/any) and the channel triplets are ignored.
+fn parse_country(subtype: u16, payload: &[u8]) -> Option<String> {
+    if subtype != 8 && subtype != 5 {
+        return None;
+    }
+    mgmt_elements(subtype, payload)?
+        .find(|(id, body)| *id == 7 && body.len() >= 2)
+        .and_then(|(_, body)| {
+            let code = &body[..2];
+            code.iter()
+                .all(u8::is_ascii_alphabetic)
+                .then(|| String::from_utf8_lossy(code).to_ascii_uppercase())
+        })
+}
+
+const RSN_OUI: [u8; 3] = [0x00, 0x0F, 0xAC];
+const WPA_OUI_TYPE: [u8; 4] = [0x00, 0x50, 0xF2, 0x01];
+/// SAE and SAE with a group-dependent hash (IEEE 802.11 Table 9-151).
+const AKM_SAE: [u8; 2] = [8, 24];
+
+/// Protection advertised by a beacon or probe response: RSN (id 48) is WPA2,
+/// or WPA3 when it offers an SAE AKM; the vendor WPA element is WPA; the
+/// capability privacy bit alone is WEP.
+fn parse_security(subtype: u16, payload: &[u8]) -> Option<Security> {
+    if subtype != 8 && subtype != 5 {
+        return None;
+    }
+    let capability = u16::from_le_bytes([*payload.get(10)?, *payload.get(11)?]);
+    let mut security = None;
+    for (id, body) in mgmt_elements(subtype, payload)? {
+        match id {
+            48 => {
+                let sae = rsn_akm_types(body).any(|akm| AKM_SAE.contains(&akm));
+                return Some(if sae { Security::Wpa3 } else { Security::Wpa2 });
+            }
+            221 if body.starts_with(&WPA_OUI_TYPE) => security = Some(Security::Wpa),
+            _ => {}
+        }
+    }
+    Some(security.unwrap_or(if capability & 0x0010 != 0 {
+        Security::Wep
+    } else {
+        Security::Open
+    }))
+}
+
+/// AKM suite types from an RSN element body: version, group cipher, then
+/// counted pairwise cipher and AKM suite lists.
+fn rsn_akm_types(body: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    let pairwise = body
+        .get(6..8)
+        .map(|n| u16::from_le_bytes([n[0], n[1]]) as usize);
+    let akm_start = pairwise.map(|n| 8 + n * 4);
+    let akms = akm_start
+        .and_then(|start| {
+            let count = body.get(start..start + 2)?;
+            let count = u16::from_le_bytes([count[0], count[1]]) as usize;
+            body.get(start + 2..(start + 2 + count * 4).min(body.len()))
+        })
+        .unwrap_or(&[]);
+    akms.chunks_exact(4)
+        .filter(|suite| suite[..3] == RSN_OUI)
+        .map(|suite| suite[3])
 }
 
 fn mgmt_ie_start(subtype: u16, payload: &[u8]) -> Option<usize> {
@@ -361,34 +1136,59 @@ fn mgmt_ie_start(subtype: u16, payload: &[u8]) -> Option<usize> {
     }
 }
 
-fn is_eapol(subtype: u16, payload: &[u8]) -> bool {
-    let hdr_len = if subtype & 0x08 != 0 { 26 } else { 24 };
-    if payload.len() < hdr_len + 8 {
-        return false;
-    }
-    let llc = &payload[hdr_len..];
-    if llc.len() < 8 {
-        return false;
+/// Returns the 802.1X body of an EAPOL data frame. `payload` already starts
+/// after the MAC header, so the LLC/SNAP header sits at offset 0.
+fn eapol_body(subtype: u16, payload: &[u8]) -> Option<&[u8]> {
+    // Null-function subtypes carry no frame body.
+    if subtype & 0x04 != 0 || payload.len() < 8 {
+        return None;
     }
+    let llc = &payload[..8];
     if llc[0] != 0xAA || llc[1] != 0xAA || llc[2] != 0x03 {
-        return false;
+        return None;
     }
     if llc[3] != 0x00 || llc[4] != 0x00 || llc[5] != 0x00 {
-        return false;
+        return None;
     }
     let eth_type = u16::from_be_bytes([llc[6], llc[7]]);
-    eth_type == 0x888E
+    (eth_type == 0x888E).then(|| &payload[8..])
 }
 
-fn parse_radiotap_and_frame(data: &[u8]) -> Option<ParsedFrame<'_>> {
-    if data.len() < 4 {
+/// Works out which message (1-4) of the pairwise 4-way handshake an
+/// EAPOL-Key frame is, from the ack/mic/secure bits of its key info.
+fn eapol_message(body: &[u8]) -> Option<u8> {
+    // 802.1X header: version, type (3 = Key), length; then descriptor type
+    // and the big-endian key info field.
+    if body.len() < 7 || body[1] != 3 {
         return None;
     }
-    let rt_len = u16::from_le_bytes([data[2], data[3]]) as usize;
-    if data.len() < rt_len + 10 {
+    let key_info = u16::from_be_bytes([body[5], body[6]]);
+    if key_info & 0x0008 == 0 {
+        return None; // group key handshake
+    }
+    let ack = key_info & 0x0080 != 0;
+    let mic = key_info & 0x0100 != 0;
+    let secure = key_info & 0x0200 != 0;
+    match (ack, mic, secure) {
+        (true, false, _) => Some(1),
+        (false, true, false) => Some(2),
+        (true, true, _) => Some(3),
+        (false, true, true) => Some(4),
+        _ => None,
+    }
+}
+
+fn parse_frame(data: &[u8], link: LinkHeader) -> Option<ParsedFrame<'_>> {
+    let (header_len, signal) = match link.detect(data) {
+        LinkHeader::Radiotap => radiotap_header(data)?,
+        LinkHeader::Prism => prism_header(data)?,
+        LinkHeader::Avs => avs_header(data)?,
+        LinkHeader::Bare => (0, None),
+    };
+    if data.len() < header_len + 10 {
         return None;
     }
-    let frame = &data[rt_len..];
+    let frame = &data[header_len..];
     if frame.len() < 10 {
         return None;
     }
@@ -415,6 +1215,15 @@ fn parse_radiotap_and_frame(data: &[u8]) -> Option<ParsedFrame<'_>> {
     } else {
         None
     };
+    let seq_ctrl = if kind_bits != 1 {
+        frame
+            .get(22..24)
+            .map(|sc| u16::from_le_bytes([sc[0], sc[1]]))
+    } else {
+        None
+    };
+    let seq = seq_ctrl.map(|sc| sc >> 4);
+    let frag = seq_ctrl.map(|sc| (sc & 0x0F) as u8);
     let bssid = match kind_bits {
         0 => addr3,
         2 => {
@@ -436,29 +1245,58 @@ fn parse_radiotap_and_frame(data: &[u8]) -> Option<ParsedFrame<'_>> {
         &[]
     };
 
-    let signal = radiotap_signal(data);
     let signal_gain = signal.as_ref().map(|s| s.gain).unwrap_or(1.0);
     let signal_dbm = signal.as_ref().and_then(|s| s.dbm);
-    let mut channel = signal.as_ref().and_then(|s| s.channel);
+    let rx_channel = signal.as_ref().and_then(|s| s.channel);
+    let mut channel = rx_channel;
     let ssid = parse_ssid(kind_bits, subtype, payload);
-    if kind_bits == 0 {
-        if let Some(ds) = parse_ds_channel(subtype, payload) {
-            channel = Some(ds);
-        }
+    let additional_ssids = if kind_bits == 0 {
+        parse_multi_bssid_ssids(subtype, payload)
+    } else {
+        Vec::new()
+    };
+    if kind_bits == 0
+        && let Some(ds) = parse_ds_channel(subtype, payload)
+    {
+        channel = Some(ds);
     }
+    let width = if kind_bits == 0 {
+        parse_channel_width(subtype, payload)
+    } else {
+        None
+    };
+
+    let security = if kind_bits == 0 {
+        parse_security(subtype, payload)
+    } else {
+        None
+    };
+    let country = if kind_bits == 0 {
+        parse_country(subtype, payload)
+    } else {
+        None
+    };
 
     Some(ParsedFrame {
         fc,
         _header_len: base_hdr_len,
+        len: frame.len(),
         payload,
-        _addr1: addr1,
+        addr1,
         addr2,
         addr3,
         bssid,
+        seq,
+        frag,
         signal_gain,
         signal_dbm,
         ssid,
         channel,
+        rx_channel,
+        additional_ssids,
+        width,
+        security,
+        country,
     })
 }
 
@@ -474,6 +1312,87 @@ struct SignalInfo {
     channel: Option<u16>,
 }
 
+impl SignalInfo {
+    fn from_dbm(dbm: Option<i8>, channel: Option<u16>) -> Self {
+        Self {
+            gain: dbm.map(dbm_to_gain).unwrap_or(1.0),
+            dbm,
+            channel,
+        }
+    }
+}
+
+/// Header length and signal info for a radiotap-prefixed packet.
+fn radiotap_header(data: &[u8]) -> Option<(usize, Option<SignalInfo>)> {
+    if data.len() < 4 {
+        return None;
+    }
+    let rt_len = u16::from_le_bytes([data[2], data[3]]) as usize;
+    Some((rt_len, radiotap_signal(data)))
+}
+
+const PRISM_MSGCODE: [u8; 4] = [0x44, 0x00, 0x00, 0x00];
+const PRISM_HEADER_LEN: usize = 144;
+/// Items follow msgcode, msglen and a 16-byte device name; each is
+/// `did: u32, status: u16, len: u16, data: u32`.
+const PRISM_ITEMS_OFFSET: usize = 24;
+const PRISM_ITEM_CHANNEL: usize = 2;
+const PRISM_ITEM_SIGNAL: usize = 5;
+
+fn prism_header(data: &[u8]) -> Option<(usize, Option<SignalInfo>)> {
+    if data.len() < PRISM_HEADER_LEN {
+        return None;
+    }
+    let msglen = u32::from_le_bytes(data[4..8].try_into().ok()?) as usize;
+    let header_len = if (PRISM_HEADER_LEN..=data.len()).contains(&msglen) {
+        msglen
+    } else {
+        PRISM_HEADER_LEN
+    };
+    // Status 0 means the driver filled the item in.
+    let item = |index: usize| {
+        let at = PRISM_ITEMS_OFFSET + index * 12;
+        let status = u16::from_le_bytes([data[at + 4], data[at + 5]]);
+        (status == 0)
+            .then(|| u32::from_le_bytes([data[at + 8], data[at + 9], data[at + 10], data[at + 11]]))
+    };
+    let channel = item(PRISM_ITEM_CHANNEL)
+        .and_then(|ch| u16::try_from(ch).ok())
+        .filter(|ch| *ch > 0);
+    let dbm = item(PRISM_ITEM_SIGNAL).and_then(|v| plausible_dbm(v as i32));
+    Some((header_len, Some(SignalInfo::from_dbm(dbm, channel))))
+}
+
+const AVS_VERSION_1: u32 = 0x8021_1001;
+const AVS_HEADER_LEN: usize = 64;
+const AVS_SSI_DBM: u32 = 2;
+
+fn avs_header(data: &[u8]) -> Option<(usize, Option<SignalInfo>)> {
+    if data.len() < AVS_HEADER_LEN {
+        return None;
+    }
+    let word = |at: usize| u32::from_be_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]);
+    if word(0) != AVS_VERSION_1 {
+        return None;
+    }
+    let header_len = word(4) as usize;
+    if header_len < AVS_HEADER_LEN || header_len > data.len() {
+        return None;
+    }
+    let channel = u16::try_from(word(28)).ok().filter(|ch| *ch > 0);
+    let dbm = if word(44) == AVS_SSI_DBM {
+        plausible_dbm(word(48) as i32)
+    } else {
+        None
+    };
+    Some((header_len, Some(SignalInfo::from_dbm(dbm, channel))))
+}
+
+/// Drivers that can't measure signal often report 0 or a raw RSSI instead.
+fn plausible_dbm(value: i32) -> Option<i8> {
+    (-120..0).contains(&value).then_some(value as i8)
+}
+
 fn radiotap_signal(data: &[u8]) -> Option<SignalInfo> {
     if data.len() < 8 {
         return None;
@@ -541,13 +1460,944 @@ fn dbm_to_gain(dbm: i8) -> f32 {
 }
 
 fn freq_to_channel(freq: u32) -> Option<u16> {
-    if freq >= 2412 && freq <= 2472 {
+    if (2412..=2472).contains(&freq) {
         Some(((freq as i32 - 2407) / 5) as u16)
     } else if freq == 2484 {
         Some(14)
-    } else if freq >= 5000 && freq <= 5900 {
+    } else if (5000..=5900).contains(&freq) {
         Some(((freq as i32 - 5000) / 5) as u16)
     } else {
         None
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::audio::SoundId;
+    use crate::events::{self, EventSettings};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    const BSSID: [u8; 6] = [0x02, 0, 0, 0, 0, 0x01];
+
+    /// Radiotap header carrying a channel frequency and an antenna signal.
+    fn radiotap(freq: u16, dbm: i8) -> Vec<u8> {
+        let mut header = vec![0, 0, 13, 0, 0x28, 0, 0, 0];
+        header.extend_from_slice(&freq.to_le_bytes());
+        header.extend_from_slice(&[0, 0, dbm as u8]);
+        header
+    }
+
+    /// 24-byte management header from `src` to broadcast in `bssid`.
+    fn mgmt_header(subtype: u8, src: [u8; 6], bssid: [u8; 6]) -> Vec<u8> {
+        let mut header = vec![subtype << 4, 0, 0, 0];
+        header.extend_from_slice(&[0xff; 6]);
+        header.extend_from_slice(&src);
+        header.extend_from_slice(&bssid);
+        header.extend_from_slice(&[0, 0]);
+        header
+    }
+
+    /// A radiotap-framed beacon from `bssid` with the given elements after
+    /// the fixed fields.
+    fn beacon_from(bssid: [u8; 6], freq: u16, elements: &[u8]) -> Vec<u8> {
+        let mut data = radiotap(freq, -40);
+        data.extend_from_slice(&mgmt_header(8, bssid, bssid));
+        data.extend_from_slice(&[0; 12]);
+        data.extend_from_slice(elements);
+        data
+    }
+
+    pub(crate) fn beacon(freq: u16, elements: &[u8]) -> Vec<u8> {
+        beacon_from(BSSID, freq, elements)
+    }
+
+    pub(crate) fn classify(data: &[u8]) -> PacketEvent {
+        let parsed = parse_frame(data, LinkHeader::Radiotap).expect("frame parses");
+        classify_frame(&parsed, false).expect("frame classifies")
+    }
+
+    #[test]
+    fn beacon_ds_channel_reaches_the_event() {
+        // Heard on channel 6 but advertising channel 11: the AP's own
+        // channel wins.
+        let evt = classify(&beacon(2437, &[0, 3, b'l', b'a', b'b', 3, 1, 11]));
+        assert_eq!(evt.kind, EventKind::Beacon);
+        assert_eq!(evt.channel, Some(11));
+    }
+
+    #[test]
+    fn beacon_advertising_another_channel_flags_a_mismatch() {
+        let tracker = DeviceTracker::new(0, None, 0);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut state = FrameState::default();
+        feed(
+            &beacon(2437, &[0, 3, b'l', b'a', b'b', 3, 1, 11]),
+            &tracker,
+            &tx,
+            &mut state,
+        );
+        let devices = tracker.snapshot(Duration::from_secs(60), 0);
+        assert_eq!(devices[0].channel, Some(11));
+        assert!(devices[0].channel_mismatch);
+    }
+
+    #[test]
+    fn signal_dbm_survives_classification() {
+        let mut data = radiotap(2437, -63);
+        data.extend_from_slice(&mgmt_header(8, BSSID, BSSID));
+        data.extend_from_slice(&[0; 12]);
+        let evt = classify(&data);
+        assert_eq!(evt.signal_dbm, Some(-63));
+    }
+
+    /// A pcap header for `data` captured without snaplen truncation.
+    fn captured_whole(data: &[u8]) -> PacketHeader {
+        PacketHeader {
+            ts: libc::timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+            caplen: data.len() as u32,
+            len: data.len() as u32,
+        }
+    }
+
+    /// Runs `data` through `process_packet` as if captured whole.
+    fn feed(
+        data: &[u8],
+        tracker: &DeviceTracker,
+        tx: &UnboundedSender<PacketEvent>,
+        state: &mut FrameState,
+    ) {
+        process_packet(data, &captured_whole(data), tracker, tx, state);
+    }
+
+    #[test]
+    fn snaplen_truncated_bytes_still_count_toward_frame_size() {
+        let data = beacon(2437, &[0, 3, b'l', b'a', b'b']);
+        let avg_frame_bytes = |header: &PacketHeader| {
+            let tracker = DeviceTracker::new(0, None, 0);
+            let (tx, _rx) = mpsc::unbounded_channel();
+            process_packet(&data, header, &tracker, &tx, &mut FrameState::default());
+            tracker.snapshot(Duration::from_secs(60), 0)[0]
+                .avg_frame_bytes
+                .unwrap()
+        };
+        let whole = captured_whole(&data);
+        let truncated = PacketHeader {
+            len: whole.caplen + 100,
+            ..whole
+        };
+        assert_eq!(avg_frame_bytes(&truncated), avg_frame_bytes(&whole) + 100);
+    }
+
+    /// Beacons from one BSSID, endlessly, standing in for a capture device.
+    struct Beacons {
+        header: PacketHeader,
+        frame: Vec<u8>,
+    }
+
+    impl PacketSource for Beacons {
+        fn next_packet(&mut self) -> Result<Packet<'_>, PcapError> {
+            thread::sleep(Duration::from_millis(1));
+            Ok(Packet::new(&self.header, &self.frame))
+        }
+    }
+
+    const OTHER_BSSID: [u8; 6] = [0x02, 0, 0, 0, 0, 0x02];
+
+    fn open_beacons(interface: &str, _read_timeout_ms: i32) -> Result<Box<dyn PacketSource>> {
+        let bssid = match interface {
+            "wlan-a" => BSSID,
+            "wlan-b" => OTHER_BSSID,
+            _ => anyhow::bail!("no such interface"),
+        };
+        let frame = beacon_from(bssid, 2437, &[]);
+        let header = captured_whole(&frame);
+        Ok(Box::new(Beacons { header, frame }))
+    }
+
+    /// A capture that opens fine but never delivers a packet.
+    struct Silent;
+
+    impl PacketSource for Silent {
+        fn next_packet(&mut self) -> Result<Packet<'_>, PcapError> {
+            thread::sleep(Duration::from_millis(1));
+            Err(PcapError::TimeoutExpired)
+        }
+    }
+
+    pub(super) fn open_silent(
+        _interface: &str,
+        _read_timeout_ms: i32,
+    ) -> Result<Box<dyn PacketSource>> {
+        Ok(Box::new(Silent))
+    }
+
+    fn controller(open: OpenSource) -> (SnifferController, mpsc::UnboundedReceiver<PacketEvent>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let sniffer = SnifferController {
+            tx,
+            devices: Arc::new(DeviceTracker::new(0, None, 0)),
+            running: Arc::new(Mutex::new(None)),
+            open,
+            discovery: Arc::new(Mutex::new(DiscoveryMode::Full)),
+            read_timeout_ms: 1_000,
+            capture_unclassified: Arc::new(AtomicBool::new(false)),
+            retries_only: Arc::new(AtomicBool::new(false)),
+            last_frame: Arc::default(),
+            recent: None,
+            handshakes: None,
+        };
+        (sniffer, rx)
+    }
+
+    /// Read timeouts `open_recording` was called with, in order.
+    static OPENED_WITH: Mutex<Vec<i32>> = Mutex::new(Vec::new());
+
+    fn open_recording(_interface: &str, read_timeout_ms: i32) -> Result<Box<dyn PacketSource>> {
+        OPENED_WITH.lock().unwrap().push(read_timeout_ms);
+        Ok(Box::new(Silent))
+    }
+
+    #[tokio::test]
+    async fn read_timeouts_are_clamped_before_reaching_pcap() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let tracker = Arc::new(DeviceTracker::new(0, None, 0));
+        for timeout in [Duration::ZERO, Duration::from_secs(600)] {
+            let sniffer = SnifferController {
+                open: open_recording,
+                ..SnifferController::new(
+                    tx.clone(),
+                    Arc::clone(&tracker),
+                    DiscoveryMode::Full,
+                    0,
+                    timeout,
+                    None,
+                )
+            };
+            sniffer.start("wlan-a".to_string());
+            sniffer.stop().await;
+        }
+        assert_eq!(*OPENED_WITH.lock().unwrap(), vec![1, 60_000]);
+    }
+
+    #[tokio::test]
+    async fn switching_interfaces_swaps_the_capture_source() {
+        let (sniffer, mut rx) = controller(open_beacons);
+        sniffer.start("wlan-a".to_string());
+        assert_eq!(rx.recv().await.unwrap().bssid, Some(BSSID));
+
+        sniffer.stop().await;
+        while rx.try_recv().is_ok() {}
+        sniffer.start("wlan-b".to_string());
+        assert_eq!(rx.recv().await.unwrap().bssid, Some(OTHER_BSSID));
+        sniffer.stop().await;
+    }
+
+    /// 802.1X EAPOL-Key header with the given key info.
+    fn eapol_key(key_info: u16) -> Vec<u8> {
+        let [hi, lo] = key_info.to_be_bytes();
+        vec![2, 3, 0, 95, 2, hi, lo]
+    }
+
+    #[test]
+    fn eapol_message_numbers_the_4_way_handshake() {
+        assert_eq!(eapol_message(&eapol_key(0x008a)), Some(1));
+        assert_eq!(eapol_message(&eapol_key(0x010a)), Some(2));
+        assert_eq!(eapol_message(&eapol_key(0x13ca)), Some(3));
+        assert_eq!(eapol_message(&eapol_key(0x030a)), Some(4));
+    }
+
+    #[test]
+    fn eapol_message_rejects_group_and_non_key_frames() {
+        assert_eq!(eapol_message(&eapol_key(0x0382)), None);
+        let mut start = eapol_key(0x008a);
+        start[1] = 1;
+        assert_eq!(eapol_message(&start), None);
+        assert_eq!(eapol_message(&[2, 3, 0]), None);
+    }
+
+    /// Multiple BSSID element with one nontransmitted profile per SSID.
+    fn multiple_bssid(ssids: &[&str]) -> Vec<u8> {
+        let mut body = vec![2];
+        for ssid in ssids {
+            let mut profile = vec![83, 2, 0x11, 0x04, 0, ssid.len() as u8];
+            profile.extend_from_slice(ssid.as_bytes());
+            profile.extend_from_slice(&[85, 3, 1, 0, 0]);
+            body.extend_from_slice(&[0, profile.len() as u8]);
+            body.extend_from_slice(&profile);
+        }
+        let mut element = vec![71, body.len() as u8];
+        element.extend_from_slice(&body);
+        element
+    }
+
+    #[test]
+    fn multiple_bssid_element_lists_cohosted_ssids() {
+        let mut elements = vec![0, 3, b'l', b'a', b'b'];
+        elements.extend_from_slice(&multiple_bssid(&["guest", "iot"]));
+        let data = beacon(2437, &elements);
+        let parsed = parse_frame(&data, LinkHeader::Radiotap).expect("frame parses");
+        assert_eq!(parsed.ssid.as_deref(), Some("lab"));
+        assert_eq!(parsed.additional_ssids, vec!["guest", "iot"]);
+
+        let tracker = DeviceTracker::new(0, None, 0);
+        observe_device(&tracker, &parsed, None, 0, 0);
+        let devices = tracker.snapshot(Duration::from_secs(60), 0);
+        assert_eq!(devices[0].additional_ssids, vec!["guest", "iot"]);
+    }
+
+    /// A radiotap-framed FromDS data frame relayed by the AP from `src` to
+    /// `dst`.
+    fn data_frame(dst: [u8; 6], src: [u8; 6]) -> Vec<u8> {
+        let mut data = radiotap(2437, -50);
+        data.extend_from_slice(&[0x08, 0x02, 0, 0]);
+        data.extend_from_slice(&dst);
+        data.extend_from_slice(&BSSID);
+        data.extend_from_slice(&src);
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(&[0xaa, 0xaa, 0x03, 0, 0, 0, 0x08, 0x00]);
+        data
+    }
+
+    #[test]
+    fn group_data_suppression_passes_unicast() {
+        let station = [0x02, 0, 0, 0, 0, 0x10];
+        let broadcast = classify(&data_frame([0xff; 6], station));
+        let unicast = classify(&data_frame([0x02, 0, 0, 0, 0, 0x20], station));
+        assert_eq!(broadcast.kind, EventKind::DataTick);
+        assert!(broadcast.group_addressed);
+        assert!(!unicast.group_addressed);
+
+        let mut settings = EventSettings::default();
+        assert!(settings.group_data_allows(&broadcast));
+        settings.suppress_group_data = true;
+        assert!(!settings.group_data_allows(&broadcast));
+        assert!(settings.group_data_allows(&unicast));
+    }
+
+    #[test]
+    fn ht_operation_reports_40_mhz_above() {
+        // HT Operation: primary 36, secondary above (offset 1), any width.
+        let mut elements = vec![0, 3, b'l', b'a', b'b', 61, 22, 36, 0x05];
+        elements.extend_from_slice(&[0; 20]);
+        let data = beacon(5180, &elements);
+        let parsed = parse_frame(&data, LinkHeader::Radiotap).expect("frame parses");
+        assert_eq!(
+            parsed.width,
+            Some(ChannelWidth {
+                mhz: 40,
+                secondary_channel: Some(40),
+            })
+        );
+
+        let tracker = DeviceTracker::new(0, None, 0);
+        observe_device(&tracker, &parsed, None, 0, 0);
+        let device = &tracker.snapshot(Duration::from_secs(60), 0)[0];
+        assert_eq!(device.channel_width, Some(40));
+        assert_eq!(device.secondary_channel, Some(40));
+    }
+
+    #[test]
+    fn legacy_beacons_are_20_mhz() {
+        let data = beacon(2437, &[0, 3, b'l', b'a', b'b']);
+        let parsed = parse_frame(&data, LinkHeader::Radiotap).expect("frame parses");
+        assert_eq!(parsed.width.map(|w| w.mhz), Some(20));
+    }
+
+    #[test]
+    fn retransmissions_within_the_window_are_not_resounded() {
+        let original = beacon(2437, &[0, 3, b'l', b'a', b'b']);
+        let mut retry = original.clone();
+        retry[13 + 1] |= 0x08;
+        let tracker = DeviceTracker::new(0, None, 0);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = FrameState::default();
+
+        feed(&original, &tracker, &tx, &mut state);
+        feed(&retry, &tracker, &tx, &mut state);
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_err());
+        assert_eq!(tracker.snapshot(Duration::from_secs(60), 0)[0].frames, 2);
+
+        // A retry after the window has passed is heard again.
+        let original = parse_frame(&original, LinkHeader::Radiotap).expect("frame parses");
+        let retry = parse_frame(&retry, LinkHeader::Radiotap).expect("frame parses");
+        let mut dedup = RetryDedup::default();
+        let start = Instant::now();
+        assert!(!dedup.is_duplicate(&original, start));
+        let within = start + DEDUP_WINDOW - Duration::from_millis(1);
+        assert!(dedup.is_duplicate(&retry, within));
+        assert!(!dedup.is_duplicate(&retry, within + DEDUP_WINDOW));
+    }
+
+    #[test]
+    fn retries_only_sends_every_retransmission() {
+        let original = beacon(2437, &[0, 3, b'l', b'a', b'b']);
+        let mut retry = original.clone();
+        retry[13 + 1] |= 0x08;
+        let tracker = DeviceTracker::new(0, None, 0);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = FrameState::default();
+        state.retries_only.store(true, Ordering::Relaxed);
+
+        feed(&original, &tracker, &tx, &mut state);
+        feed(&retry, &tracker, &tx, &mut state);
+        let indicators: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok())
+            .filter_map(|evt| events::retry_indicator(&evt))
+            .collect();
+        assert_eq!(indicators, [SoundId::RetryGlitch]);
+    }
+
+    #[test]
+    fn sequence_control_yields_seq_and_fragment() {
+        let mut data = beacon(2437, &[0, 3, b'l', b'a', b'b']);
+        let sc = (0x123u16 << 4) | 2;
+        data[13 + 22..13 + 24].copy_from_slice(&sc.to_le_bytes());
+        let parsed = parse_frame(&data, LinkHeader::Radiotap).expect("frame parses");
+        assert_eq!(parsed.seq, Some(0x123));
+        assert_eq!(parsed.frag, Some(2));
+        assert_eq!(classify(&data).seq, Some(0x123));
+    }
+
+    /// ToDS data fragment from a station carrying `body`.
+    fn data_fragment(seq: u16, frag: u8, more: bool, body: &[u8]) -> Vec<u8> {
+        let mut data = radiotap(2437, -50);
+        data.extend_from_slice(&[0x08, if more { 0x05 } else { 0x01 }, 0, 0]);
+        data.extend_from_slice(&BSSID);
+        data.extend_from_slice(&[0x02, 0, 0, 0, 0, 0x10]);
+        data.extend_from_slice(&BSSID);
+        data.extend_from_slice(&((seq << 4) | u16::from(frag)).to_le_bytes());
+        data.extend_from_slice(body);
+        data
+    }
+
+    #[test]
+    fn fragmented_eapol_is_reassembled_before_classification() {
+        let mut msdu = vec![0xaa, 0xaa, 0x03, 0, 0, 0, 0x88, 0x8e];
+        msdu.extend_from_slice(&eapol_key(0x008a));
+        let (head, tail) = msdu.split_at(5);
+        let tracker = DeviceTracker::new(0, None, 0);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = FrameState::default();
+
+        feed(&data_fragment(7, 0, true, head), &tracker, &tx, &mut state);
+        assert!(rx.try_recv().is_err());
+        feed(&data_fragment(7, 1, false, tail), &tracker, &tx, &mut state);
+        let evt = rx.try_recv().expect("reassembled frame classifies");
+        assert_eq!(evt.kind, EventKind::Eapol);
+        assert_eq!(evt.eapol_msg, Some(1));
+
+        // A trailing fragment without its head is dropped.
+        feed(&data_fragment(8, 1, false, tail), &tracker, &tx, &mut state);
+        assert!(rx.try_recv().is_err());
+    }
+
+    /// EAPOL-Key frames for messages 1-4 of a 4-way handshake, as an LLC
+    /// body for `data_fragment`.
+    fn handshake_messages() -> Vec<Vec<u8>> {
+        [0x008a, 0x010a, 0x13ca, 0x030a]
+            .into_iter()
+            .map(|key_info| {
+                let mut msdu = vec![0xaa, 0xaa, 0x03, 0, 0, 0, 0x88, 0x8e];
+                msdu.extend_from_slice(&eapol_key(key_info));
+                msdu
+            })
+            .collect()
+    }
+
+    /// A radiotap `FrameState` saving handshakes under a fresh `name`
+    /// directory in the system temp dir.
+    fn handshake_state(name: &str) -> (FrameState, std::path::PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("radioscope-{name}-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = std::fs::remove_file(&dir);
+        let state = FrameState {
+            handshakes: Some(Arc::new(HandshakeCapture::new(&dir))),
+            ..FrameState::for_linktype(Linktype::IEEE802_11_RADIOTAP).unwrap()
+        };
+        (state, dir)
+    }
+
+    fn saved_files(dir: &std::path::Path) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        entries
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn complete_handshakes_are_saved_once() {
+        let (mut state, dir) = handshake_state("handshake");
+        let tracker = DeviceTracker::new(0, None, 0);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut seq = 0;
+        let mut replay = |state: &mut FrameState| {
+            for msdu in handshake_messages() {
+                seq += 1;
+                feed(&data_fragment(seq, 0, false, &msdu), &tracker, &tx, state);
+            }
+        };
+
+        // With the directory path taken by a file, the write fails and the
+        // BSSID stays eligible.
+        std::fs::write(&dir, b"").unwrap();
+        feed(
+            &beacon(2437, &[0, 3, b'l', b'a', b'b']),
+            &tracker,
+            &tx,
+            &mut state,
+        );
+        replay(&mut state);
+        std::fs::remove_file(&dir).unwrap();
+
+        replay(&mut state);
+        let file = format!("lab_{}.pcap", devices::format_mac(&BSSID).replace(':', "-"));
+        assert_eq!(saved_files(&dir), vec![file.clone()]);
+        let saved = std::fs::read(dir.join(&file)).unwrap();
+        // pcap global header, then the beacon and four messages.
+        assert!(saved.len() > 24);
+
+        // A second handshake on the same BSSID writes nothing.
+        std::fs::remove_file(dir.join(&file)).unwrap();
+        replay(&mut state);
+        let files = saved_files(&dir);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(files.is_empty(), "{files:?}");
+    }
+
+    #[test]
+    fn fragmented_handshake_messages_are_not_saved() {
+        let (mut state, dir) = handshake_state("fragmented-handshake");
+        let tracker = DeviceTracker::new(0, None, 0);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let messages = handshake_messages();
+        let (head, tail) = messages[0].split_at(5);
+        feed(&data_fragment(1, 0, true, head), &tracker, &tx, &mut state);
+        feed(&data_fragment(1, 1, false, tail), &tracker, &tx, &mut state);
+        assert_eq!(rx.try_recv().unwrap().eapol_msg, Some(1));
+        for (seq, msdu) in (2..).zip(&messages[1..]) {
+            feed(
+                &data_fragment(seq, 0, false, msdu),
+                &tracker,
+                &tx,
+                &mut state,
+            );
+        }
+        let files = saved_files(&dir);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(files.is_empty(), "{files:?}");
+    }
+
+    #[test]
+    fn ap_and_client_frames_pan_to_opposite_sides() {
+        let ap = classify(&beacon(2437, &[0, 3, b'l', b'a', b'b']));
+        let llc = [0xaa, 0xaa, 0x03, 0, 0, 0, 0x08, 0x00];
+        let client = classify(&data_fragment(1, 0, false, &llc));
+        assert_eq!(ap.role, DeviceRole::Ap);
+        assert_eq!(client.role, DeviceRole::Client);
+        assert_eq!(events::role_pan(ap.role, 0.7), -0.7);
+        assert_eq!(events::role_pan(client.role, 0.7), 0.7);
+        assert_eq!(events::role_pan(client.role, 0.0), 0.0);
+    }
+
+    /// A prism-headed beacon heard on `channel` at `dbm`.
+    fn prism_beacon(channel: u32, dbm: i32) -> Vec<u8> {
+        let mut data = vec![0; PRISM_HEADER_LEN];
+        data[..4].copy_from_slice(&PRISM_MSGCODE);
+        data[4..8].copy_from_slice(&(PRISM_HEADER_LEN as u32).to_le_bytes());
+        for index in 0..10 {
+            let at = PRISM_ITEMS_OFFSET + index * 12;
+            // Status 1: item not supplied by the driver.
+            data[at + 4] = 1;
+        }
+        for (index, value) in [
+            (PRISM_ITEM_CHANNEL, channel),
+            (PRISM_ITEM_SIGNAL, dbm as u32),
+        ] {
+            let at = PRISM_ITEMS_OFFSET + index * 12;
+            data[at + 4] = 0;
+            data[at + 8..at + 12].copy_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&mgmt_header(8, BSSID, BSSID));
+        data.extend_from_slice(&[0; 12]);
+        data.extend_from_slice(&[0, 3, b'l', b'a', b'b']);
+        data
+    }
+
+    #[test]
+    fn prism_headers_yield_channel_and_signal() {
+        let data = prism_beacon(6, -55);
+        let parsed = parse_frame(&data, LinkHeader::Prism).expect("frame parses");
+        assert_eq!(parsed.channel, Some(6));
+        assert_eq!(parsed.signal_dbm, Some(-55));
+        assert_eq!(parsed.ssid.as_deref(), Some("lab"));
+
+        // Prism frames mislabelled as radiotap are recognised by magic.
+        let parsed = parse_frame(&data, LinkHeader::Radiotap).expect("frame parses");
+        assert_eq!(parsed.signal_dbm, Some(-55));
+    }
+
+    #[test]
+    fn linktype_selects_radiotap_or_bare_parsing() {
+        let radiotap = FrameState::for_linktype(Linktype::IEEE802_11_RADIOTAP).unwrap();
+        let bare = FrameState::for_linktype(Linktype::IEEE802_11).unwrap();
+        assert_eq!(radiotap.link, LinkHeader::Radiotap);
+        assert_eq!(bare.link, LinkHeader::Bare);
+        assert!(FrameState::for_linktype(Linktype::ETHERNET).is_err());
+
+        let framed = beacon(2437, &[0, 3, b'l', b'a', b'b']);
+        let parsed = parse_frame(&framed, radiotap.link).expect("frame parses");
+        assert_eq!(parsed.signal_dbm, Some(-40));
+        assert_eq!(parsed.ssid.as_deref(), Some("lab"));
+
+        // The same beacon without its radiotap header.
+        let unframed = &framed[framed[2] as usize..];
+        let parsed = parse_frame(unframed, bare.link).expect("frame parses");
+        assert_eq!(parsed.signal_dbm, None);
+        assert_eq!(parsed.ssid.as_deref(), Some("lab"));
+    }
+
+    #[test]
+    fn action_frames_surface_as_other_only_when_enabled() {
+        let mut action = radiotap(2437, -50);
+        action.extend_from_slice(&mgmt_header(13, BSSID, BSSID));
+        action.extend_from_slice(&[3, 0]);
+        let tracker = DeviceTracker::new(0, None, 0);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = FrameState::default();
+
+        feed(&action, &tracker, &tx, &mut state);
+        assert!(rx.try_recv().is_err());
+
+        state.capture_unclassified.store(true, Ordering::Relaxed);
+        feed(&action, &tracker, &tx, &mut state);
+        let evt = rx.try_recv().expect("action frame surfaces");
+        assert_eq!(evt.kind, EventKind::Other);
+        assert_eq!(evt.fc, 0x00d0);
+    }
+
+    #[test]
+    fn beacon_only_discovery_skips_data_and_control_frames() {
+        let mut rts = radiotap(2437, -50);
+        rts.extend_from_slice(&[0xb4, 0, 0, 0]);
+        rts.extend_from_slice(&BSSID);
+        rts.extend_from_slice(&[0x02, 0, 0, 0, 0, 0x10]);
+        let data = data_frame([0xff; 6], [0x02, 0, 0, 0, 0, 0x10]);
+        let tracker = DeviceTracker::new(0, None, 0);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = FrameState {
+            beacon_only: true,
+            ..FrameState::default()
+        };
+
+        feed(&rts, &tracker, &tx, &mut state);
+        feed(&data, &tracker, &tx, &mut state);
+        assert!(rx.try_recv().is_err());
+        assert!(tracker.snapshot(Duration::from_secs(60), 0).is_empty());
+
+        feed(&beacon(2437, &[]), &tracker, &tx, &mut state);
+        assert_eq!(rx.try_recv().unwrap().kind, EventKind::Beacon);
+    }
+
+    /// Beacon body: timestamp, interval, capability, then `elements`.
+    fn beacon_body(capability: u16, elements: &[u8]) -> Vec<u8> {
+        let mut payload = vec![0; 10];
+        payload.extend_from_slice(&capability.to_le_bytes());
+        payload.extend_from_slice(&[0, 3, b'l', b'a', b'b']);
+        payload.extend_from_slice(elements);
+        payload
+    }
+
+    fn rsn(akm: u8) -> Vec<u8> {
+        let mut element = vec![48, 18, 1, 0, 0x00, 0x0f, 0xac, 4, 1, 0, 0x00, 0x0f, 0xac, 4];
+        element.extend_from_slice(&[1, 0, 0x00, 0x0f, 0xac, akm]);
+        element
+    }
+
+    #[test]
+    fn parse_security_reads_rsn_akms() {
+        assert_eq!(
+            parse_security(8, &beacon_body(0x0011, &rsn(2))),
+            Some(Security::Wpa2)
+        );
+        assert_eq!(
+            parse_security(5, &beacon_body(0x0011, &rsn(8))),
+            Some(Security::Wpa3)
+        );
+    }
+
+    #[test]
+    fn parse_security_falls_back_to_wpa_and_privacy_bit() {
+        let wpa = [221, 6, 0x00, 0x50, 0xf2, 0x01, 1, 0];
+        assert_eq!(
+            parse_security(8, &beacon_body(0x0011, &wpa)),
+            Some(Security::Wpa)
+        );
+        assert_eq!(
+            parse_security(8, &beacon_body(0x0011, &[])),
+            Some(Security::Wep)
+        );
+        assert_eq!(
+            parse_security(8, &beacon_body(0x0001, &[])),
+            Some(Security::Open)
+        );
+    }
+
+    #[test]
+    fn parse_security_ignores_other_subtypes_and_short_bodies() {
+        assert_eq!(parse_security(4, &beacon_body(0x0011, &rsn(2))), None);
+        assert_eq!(parse_security(8, &[0; 11]), None);
+    }
+
+    /// ToDS data frame of `subtype` from a station; QoS subtypes get a QoS
+    /// Control field and body-carrying ones an IPv4 LLC header.
+    fn data_subtype(subtype: u8) -> Vec<u8> {
+        let mut data = radiotap(2437, -50);
+        data.extend_from_slice(&[(subtype << 4) | 0x08, 0x01, 0, 0]);
+        data.extend_from_slice(&BSSID);
+        data.extend_from_slice(&[0x02, 0, 0, 0, 0, 0x10]);
+        data.extend_from_slice(&BSSID);
+        data.extend_from_slice(&[0, 0]);
+        if subtype & 0x08 != 0 {
+            data.extend_from_slice(&[0, 0]);
+        }
+        if subtype & 0x04 == 0 {
+            data.extend_from_slice(&[0xaa, 0xaa, 0x03, 0, 0, 0, 0x08, 0x00]);
+        }
+        data
+    }
+
+    #[test]
+    fn bodiless_data_subtypes_are_null_data() {
+        // Data, Data+CF-Ack, QoS Data, QoS Data+CF-Ack+CF-Poll.
+        for subtype in [0, 1, 8, 11] {
+            assert_eq!(classify(&data_subtype(subtype)).kind, EventKind::DataTick);
+        }
+        // Null, CF-Ack, CF-Ack+CF-Poll, QoS Null, QoS CF-Poll.
+        for subtype in [4, 5, 7, 12, 14] {
+            assert_eq!(classify(&data_subtype(subtype)).kind, EventKind::NullData);
+        }
+        assert!(!EventSettings::default().enabled[&EventKind::NullData]);
+    }
+
+    #[test]
+    fn merged_assoc_family_plays_one_sound() {
+        let station = [0x02, 0, 0, 0, 0, 0x10];
+        let frame = |subtype| {
+            let mut data = radiotap(2437, -50);
+            data.extend_from_slice(&mgmt_header(subtype, station, BSSID));
+            data.extend_from_slice(&[0; 6]);
+            data
+        };
+        // Association request, association response, reassociation request.
+        let raw: Vec<Vec<u8>> = [0, 1, 2].into_iter().map(frame).collect();
+        let family: Vec<ParsedFrame> = raw
+            .iter()
+            .map(|data| parse_frame(data, LinkHeader::Radiotap).expect("frame parses"))
+            .collect();
+        let events: Vec<PacketEvent> = family
+            .iter()
+            .map(|parsed| classify_frame(parsed, false).expect("frame classifies"))
+            .collect();
+        assert!(events.iter().all(|evt| evt.kind == EventKind::Assoc));
+        // Authentication stays unclassified.
+        assert!(
+            classify_frame(
+                &parse_frame(&frame(11), LinkHeader::Radiotap).unwrap(),
+                false
+            )
+            .is_none()
+        );
+
+        let pitches = |merge| -> Vec<f32> {
+            events
+                .iter()
+                .map(|evt| events::assoc_pitch(evt, merge))
+                .collect()
+        };
+        assert_eq!(pitches(true), [1.0, 1.0, 1.0]);
+        assert_eq!(pitches(false), [1.0, 1.0, 1.5]);
+
+        // Merged or not, the tracker keeps reassociation apart.
+        let tracker = DeviceTracker::new(0, None, 0);
+        for (parsed, evt) in family.iter().zip(&events) {
+            observe_device(&tracker, parsed, Some(evt.kind.clone()), 0, 0);
+        }
+        let seen = tracker
+            .detail(station)
+            .expect("station is tracked")
+            .last_kind_seen_ms;
+        assert_eq!(
+            seen.keys().copied().collect::<Vec<_>>(),
+            ["assoc", "reassoc"]
+        );
+    }
+
+    #[test]
+    fn country_element_reaches_the_ap_view() {
+        // Country "US", any environment, channels 1-11 at 30 dBm.
+        let frame = beacon(2437, &[7, 6, b'U', b'S', b' ', 1, 11, 30]);
+        let parsed = parse_frame(&frame, LinkHeader::Radiotap).expect("frame parses");
+        assert_eq!(parsed.country.as_deref(), Some("US"));
+
+        let tracker = DeviceTracker::new(0, None, 0);
+        observe_device(&tracker, &parsed, Some(EventKind::Beacon), 0, 0);
+        let list = tracker.snapshot(Duration::from_secs(60), 0);
+        assert_eq!(list[0].country.as_deref(), Some("US"));
+
+        let odd = beacon(2437, &[7, 3, b'1', b'2', b' ']);
+        let parsed = parse_frame(&odd, LinkHeader::Radiotap).expect("frame parses");
+        assert_eq!(parsed.country, None);
+    }
+
+    #[test]
+    fn ack_and_cts_are_filtered_by_their_receiver() {
+        let blocked = [0x02, 0, 0, 0, 0, 0x30];
+        let other = [0x02, 0, 0, 0, 0, 0x31];
+        // Frame control, duration, then only the receiver address.
+        let control = |subtype: u8, ra: [u8; 6]| {
+            let mut data = radiotap(2437, -50);
+            data.extend_from_slice(&[0x04 | subtype << 4, 0, 0, 0]);
+            data.extend_from_slice(&ra);
+            data
+        };
+        let tracker = DeviceTracker::new(0, None, 0);
+        tracker.set_many(&[(blocked, false)]);
+
+        for (subtype, kind) in [(13, EventKind::Ack), (12, EventKind::Cts)] {
+            let evt = classify(&control(subtype, blocked));
+            assert_eq!(evt.kind, kind);
+            assert_eq!((evt.src, evt.receiver), (None, Some(blocked)));
+            assert!(!tracker.allows(evt.filter_addr(), evt.bssid));
+            assert!(tracker.allows(classify(&control(subtype, other)).filter_addr(), None));
+        }
+        // With no address at all nothing is blocked.
+        assert!(tracker.allows(None, None));
+
+        // Untracked: the signal belongs to whoever sent it, not the receiver.
+        let ack = control(13, other);
+        let parsed = parse_frame(&ack, LinkHeader::Radiotap).unwrap();
+        observe_device(&tracker, &parsed, None, 0, 0);
+        assert!(tracker.snapshot(Duration::from_secs(60), 0).is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_capture_that_fails_to_open_is_not_running() {
+        let (sniffer, _rx) = controller(open_beacons);
+        sniffer.start("wlan-missing".to_string());
+        for _ in 0..1000 {
+            if !sniffer.is_running() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert!(!sniffer.is_running());
+        assert_eq!(sniffer.since_last_frame(), None);
+        sniffer.stop().await;
+    }
+
+    #[tokio::test]
+    async fn only_a_silent_running_capture_is_stalled() {
+        let limit = Duration::from_millis(30);
+        let (silent, _rx) = controller(open_silent);
+        assert!(!silent.is_stalled(limit), "stopped captures never stall");
+        silent.start("wlan-a".to_string());
+        tokio::time::sleep(limit * 2).await;
+        assert!(silent.is_stalled(limit));
+        assert!(
+            !silent.is_stalled(Duration::ZERO),
+            "zero disables the watchdog"
+        );
+        // Reopening restarts the count.
+        silent.stop().await;
+        silent.start("wlan-a".to_string());
+        assert!(!silent.is_stalled(limit));
+        silent.stop().await;
+
+        let (busy, mut rx) = controller(open_beacons);
+        busy.start("wlan-a".to_string());
+        rx.recv().await.unwrap();
+        tokio::time::sleep(limit * 2).await;
+        assert!(!busy.is_stalled(limit));
+        busy.stop().await;
+    }
+
+    #[test]
+    fn missed_frames_are_counted_across_the_sequence_wrap() {
+        assert_eq!(seq_gap(4095, 2), 2);
+        assert_eq!(seq_gap(4094, 4095), 0);
+        assert_eq!(seq_gap(10, 10), 0);
+        // A jump this far is a reset, not 999 drops.
+        assert_eq!(seq_gap(1, 1000), 0);
+
+        let with_seq = |seq: u16, retry: bool| {
+            let mut data = beacon(2437, &[0, 3, b'l', b'a', b'b']);
+            let header = data.len() - 12 - 5 - 24;
+            data[header + 22..header + 24].copy_from_slice(&(seq << 4).to_le_bytes());
+            if retry {
+                data[header + 1] |= 0x08;
+            }
+            data
+        };
+        let mut gaps = SeqGaps::default();
+        let mut missed = |data: Vec<u8>| {
+            let parsed = parse_frame(&data, LinkHeader::Radiotap).expect("frame parses");
+            gaps.missed(&parsed)
+        };
+        assert_eq!(missed(with_seq(4094, false)), 0);
+        assert_eq!(missed(with_seq(1, false)), 2);
+        assert_eq!(missed(with_seq(1, true)), 0);
+        assert_eq!(missed(with_seq(2, false)), 0);
+    }
+
+    #[test]
+    fn recent_frames_keep_only_the_last_n_unclassified() {
+        let action = |category: u8| {
+            let mut data = radiotap(2437, -50);
+            data.extend_from_slice(&mgmt_header(13, BSSID, BSSID));
+            data.extend_from_slice(&[category, 0]);
+            data
+        };
+        let tracker = DeviceTracker::new(0, None, 0);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = FrameState {
+            recent: Some(Arc::new(RecentFrames::new(3))),
+            ..FrameState::default()
+        };
+
+        for category in 1..=5 {
+            feed(&action(category), &tracker, &tx, &mut state);
+        }
+        feed(
+            &beacon(2437, &[0, 3, b'l', b'a', b'b']),
+            &tracker,
+            &tx,
+            &mut state,
+        );
+        // Recorded for inspection, but still not sent while capture_unclassified is off.
+        assert_eq!(rx.try_recv().unwrap().kind, EventKind::Beacon);
+        assert!(rx.try_recv().is_err());
+
+        let recent = state.recent.as_ref().unwrap().snapshot();
+        let categories: Vec<&str> = recent.iter().map(|f| &f.hex[f.hex.len() - 4..]).collect();
+        assert_eq!(categories, ["0300", "0400", "0500"]);
+        assert!(
+            recent
+                .iter()
+                .all(|f| f.summary.as_ref().unwrap().subtype == 13)
+        );
+
+        // Frames too short to parse are kept without a summary.
+        feed(&radiotap(2437, -50), &tracker, &tx, &mut state);
+        let recent = state.recent.as_ref().unwrap().snapshot();
+        assert_eq!(recent.len(), 3);
+        assert!(recent[2].summary.is_none());
+    }
+}
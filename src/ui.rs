@@ -1,22 +1,52 @@
 use dioxus::core::NoOpMutations;
 use dioxus::prelude::*;
 
+pub const DEFAULT_ACCENT: &str = "#ff5f7a";
+pub const DEFAULT_BACKGROUND: &str = "#0b0d13";
+
+/// Operator colour overrides, injected as CSS custom properties. Unset
+/// values keep the built-in palette.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Theme {
+    pub accent: Option<String>,
+    pub background: Option<String>,
+}
+
+impl Theme {
+    fn css(&self) -> String {
+        let mut vars = String::new();
+        if let Some(accent) = &self.accent {
+            // A single colour replaces both ends of the accent gradient.
+            vars.push_str(&format!("--accent: {accent}; --accent-deep: {accent}; "));
+        }
+        if let Some(background) = &self.background {
+            vars.push_str(&format!("--background: {background}; "));
+        }
+        if vars.is_empty() {
+            return String::new();
+        }
+        format!(":root {{ {vars}}}")
+    }
+}
+
 #[derive(Props, Clone, PartialEq)]
 pub struct AppProps {
     pub monitor_interface: String,
+    pub theme: Theme,
 }
 
-pub fn render_html(monitor_interface: &str) -> String {
+pub fn render_html(monitor_interface: &str, theme: &Theme) -> String {
     let mut app = VirtualDom::new_with_props(
         App,
         AppProps {
             monitor_interface: monitor_interface.to_string(),
+            theme: theme.clone(),
         },
     );
     // Build the tree before rendering to avoid SSR panics.
     let mut noop = NoOpMutations {};
-    let _ = app.rebuild(&mut noop);
-    dioxus_ssr::render(&mut app)
+    app.rebuild(&mut noop);
+    dioxus_ssr::render(&app)
 }
 
 #[component]
@@ -24,12 +54,15 @@ fn App(props: AppProps) -> Element {
     let styles = r#"
 :root {
     color-scheme: light;
+    --accent: #ff5f7a;
+    --accent-deep: #ff3c5a;
+    --background: #0b0d13;
 }
 * { box-sizing: border-box; }
 body, html {
     margin: 0;
     padding: 0;
-    background: radial-gradient(circle at 20% 20%, #171a24, #0b0d13 40%), #0b0d13;
+    background: radial-gradient(circle at 20% 20%, #171a24, var(--background) 40%), var(--background);
 }
 .page { min-height: 100vh; display: flex; justify-content: center; padding: 36px 18px; color: #e9ecf5; font-family: "Space Grotesk", "Inter", system-ui, -apple-system, sans-serif; }
 .shell { width: min(900px, 100%); display: flex; flex-direction: column; gap: 12px; }
@@ -39,7 +72,7 @@ body, html {
 .tag { display: inline-flex; align-items: center; gap: 8px; width: fit-content; padding: 8px 12px; border-radius: 999px; background: #10131c; border: 1px solid #1f2431; color: #c5cee3; font-size: 14px; }
 .nav { display: flex; flex-wrap: wrap; gap: 10px; }
 .nav-btn { padding: 10px 14px; border-radius: 12px; border: 1px solid #1f2230; background: #11131b; color: #c5cee3; font-weight: 700; letter-spacing: 0.2px; cursor: pointer; transition: transform 120ms ease, background 140ms ease, border 140ms ease; }
-.nav-btn.active { background: linear-gradient(135deg, #ff5f7a, #ff3c5a); color: #0a0c12; border-color: #ff90a3; box-shadow: 0 12px 28px rgba(255, 79, 100, 0.28); transform: translateY(-1px); }
+.nav-btn.active { background: linear-gradient(135deg, var(--accent), var(--accent-deep)); color: #0a0c12; border-color: #ff90a3; box-shadow: 0 12px 28px rgba(255, 79, 100, 0.28); transform: translateY(-1px); }
 .content { display: flex; flex-direction: column; gap: 12px; }
 .card { width: 100%; background: linear-gradient(145deg, #161a23, #0f1219); border: 1px solid #1f2230; border-radius: 16px; padding: 22px; box-shadow: 0 18px 44px rgba(0,0,0,0.35); }
 .section { display: none; }
@@ -57,9 +90,11 @@ body, html {
 .sound-options { display: grid; grid-template-columns: repeat(auto-fit, minmax(240px, 1fr)); gap: 12px; }
 .checkbox { display: flex; align-items: center; gap: 10px; padding: 12px; background: #10141d; border: 1px solid #1f2230; border-radius: 12px; cursor: pointer; }
 .checkbox input { width: 18px; height: 18px; }
-.primary { width: 100%; padding: 14px 16px; border-radius: 12px; border: none; background: linear-gradient(135deg, #ff5f7a, #ff3c5a); color: #0b0d12; font-weight: 800; font-size: 16px; letter-spacing: 0.3px; box-shadow: 0 12px 30px rgba(255,79,100,0.35); transition: transform 120ms ease, box-shadow 120ms ease, filter 120ms ease; cursor: pointer; }
+.primary { width: 100%; padding: 14px 16px; border-radius: 12px; border: none; background: linear-gradient(135deg, var(--accent), var(--accent-deep)); color: #0b0d12; font-weight: 800; font-size: 16px; letter-spacing: 0.3px; box-shadow: 0 12px 30px rgba(255,79,100,0.35); transition: transform 120ms ease, box-shadow 120ms ease, filter 120ms ease; cursor: pointer; }
 .primary:active { transform: translateY(1px); box-shadow: 0 8px 20px rgba(255,79,100,0.28); filter: brightness(0.95); }
 .status { margin-top: 10px; color: #8f98ac; font-size: 14px; min-height: 18px; }
+.meter { height: 8px; margin-top: 12px; border-radius: 4px; background: #10141d; border: 1px solid #1f2230; overflow: hidden; }
+.meter-bar { height: 100%; width: 0%; background: linear-gradient(90deg, #3ddc97, var(--accent)); transition: width 0.1s linear; }
 .caption { margin: 6px 0 0 0; color: #7c859c; font-size: 13px; }
 .mode-row { display: flex; gap: 12px; align-items: center; margin: 8px 0 12px 0; flex-wrap: wrap; }
 .mode-select { padding: 10px 12px; border-radius: 10px; border: 1px solid #262b38; background: #0f1118; color: #dfe4f3; font-weight: 700; }
@@ -111,6 +146,7 @@ body, html {
     .primary { font-size: 15px; }
 }
 "#;
+    let theme_css = props.theme.css();
 
     let script = r#"
 (() => {
@@ -118,12 +154,16 @@ body, html {
   const sections = document.querySelectorAll('[data-section]');
   const channel24 = document.getElementById('channels-24');
   const channel5 = document.getElementById('channels-5');
+  const channelPresets = document.getElementById('channels-presets');
+  const channelPresetsGroup = document.getElementById('channel-presets-group');
   let channelButtons = [];
   const channelStatus = document.getElementById('channel-status');
   const soundStatus = document.getElementById('sound-status');
+  const vuBar = document.getElementById('vu-bar');
   const audioJack = document.getElementById('audio-jack');
   const webUi = document.getElementById('web-ui');
   const volumeBySignal = document.getElementById('volume-by-signal');
+  const activityDrone = document.getElementById('activity-drone');
   const packetList = document.getElementById('packet-list');
   const packetStatus = document.getElementById('packet-status');
   const modeSelect = document.getElementById('mode-select');
@@ -133,6 +173,9 @@ body, html {
   const cancel = document.getElementById('cancel-btn');
   const confirm = document.getElementById('confirm-btn');
   const shutdownStatus = document.getElementById('status');
+  const captureBtn = document.getElementById('capture-btn');
+  const captureStatus = document.getElementById('capture-status');
+  let capturing = true;
   const deviceList = document.getElementById('device-list');
   const deviceStatus = document.getElementById('device-status');
   const deviceWindow = document.getElementById('device-window');
@@ -140,11 +183,27 @@ body, html {
   const deviceSelectAll = document.getElementById('devices-select-all');
   const deviceDeselectAll = document.getElementById('devices-deselect-all');
   const deviceReset = document.getElementById('devices-reset');
+  const deviceFreeze = document.getElementById('devices-freeze');
   let ws;
   let deviceWs;
   let audioCtx;
   let packetsState = [];
   let devicesState = [];
+  let devicesFrozen = false;
+  // Mirrors encode_ws_event's kind codes for /ws/packets?format=bin.
+  const BIN_KINDS = [
+    'beacon', 'probe-req', 'probe-resp', 'assoc', 'deauth',
+    'eapol', 'rts', 'cts', 'ack', 'data-tick', 'beacon-flood',
+    'other', 'probe-resp-flood', 'null-data',
+  ];
+  let reconnect = { packets_ms: 1500, devices_ms: 2000, max_ms: 30000 };
+  let packetRetries = 0;
+  let deviceRetries = 0;
+  let meterRetries = 0;
+
+  function reconnectDelay(base, attempts) {
+    return Math.min(base * 2 ** attempts, Math.max(base, reconnect.max_ms));
+  }
 
   function setSection(target) {
     sections.forEach((section) => {
@@ -172,12 +231,18 @@ body, html {
       const data = await res.json();
       renderChannels(data.available_channels_24ghz || [], channel24);
       renderChannels(data.available_channels_5ghz || [], channel5);
+      renderPresets(data.channel_presets || []);
       if (typeof data.channel === 'number') {
         setActiveChannel(data.channel);
       }
+      if (data.reconnect) {
+        reconnect = { ...reconnect, ...data.reconnect };
+      }
+      setCapturing(data.capturing !== false);
       audioJack.checked = !!data.audio_jack;
       webUi.checked = !!data.web_ui_sound;
       volumeBySignal.checked = !!data.volume_by_signal;
+      activityDrone.checked = !!data.activity_drone;
       packetsState = data.packet_events || [];
       renderPackets(packetsState);
       if (modeSelect && data.mode) {
@@ -217,6 +282,7 @@ body, html {
       }
       btn.dataset.channel = channel;
       btn.textContent = channel;
+      if (item.frequency_mhz) btn.title = `${item.frequency_mhz} MHz`;
       if (enabled) {
         btn.addEventListener('click', () => setChannel(btn, channel));
       }
@@ -231,6 +297,28 @@ body, html {
     }
   }
 
+  function renderPresets(list) {
+    if (!channelPresets) return;
+    channelPresets.innerHTML = '';
+    channelButtons = channelButtons.filter((btn) => btn.isConnected);
+    if (channelPresetsGroup) channelPresetsGroup.hidden = !list.length;
+    list.forEach((preset) => {
+      const btn = document.createElement('button');
+      btn.className = 'channel-btn';
+      btn.dataset.channel = preset.channel;
+      btn.textContent = preset.channel;
+      if (!preset.supported) {
+        btn.classList.add('disabled');
+        btn.disabled = true;
+        btn.title = 'Not supported on this device';
+      } else {
+        btn.addEventListener('click', () => setChannelPreset(btn, preset));
+      }
+      channelPresets.appendChild(btn);
+      channelButtons.push(btn);
+    });
+  }
+
   function renderPackets(list) {
     if (!packetList) return;
     packetList.innerHTML = '';
@@ -313,7 +401,7 @@ body, html {
         topRow.style.gap = '8px';
         const mac = document.createElement('div');
         mac.className = 'device-mac';
-        mac.textContent = item.mac;
+        mac.textContent = item.alias ? `${item.alias} (${item.mac})` : item.mac;
         topRow.appendChild(mac);
         const meta = document.createElement('div');
         meta.className = 'device-meta';
@@ -322,9 +410,16 @@ body, html {
           ssid.textContent = `SSID ${item.ssid}`;
           meta.appendChild(ssid);
         }
+        if (item.additional_ssids && item.additional_ssids.length) {
+          const extra = document.createElement('span');
+          extra.textContent = `Also ${item.additional_ssids.join(', ')}`;
+          meta.appendChild(extra);
+        }
         if (item.channel) {
           const ch = document.createElement('span');
-          ch.textContent = `Ch ${item.channel}`;
+          ch.textContent = item.channel_width
+            ? `Ch ${item.channel} · ${item.channel_width} MHz`
+            : `Ch ${item.channel}`;
           meta.appendChild(ch);
         }
         if (item.bssid) {
@@ -360,6 +455,18 @@ body, html {
     });
   }
 
+  // Picks up channel changes the driver makes on its own (e.g. DFS).
+  async function pollChannel() {
+    try {
+      const res = await fetch('/api/channel');
+      if (!res.ok) return;
+      const data = await res.json();
+      if (typeof data.channel === 'number') setActiveChannel(data.channel);
+    } catch {
+      // Try again on the next poll.
+    }
+  }
+
   async function setChannel(btn, channel) {
     if (btn && btn.disabled) return;
     channelStatus.textContent = `Setting channel ${channel}...`;
@@ -381,8 +488,31 @@ body, html {
     }
   }
 
+  async function setChannelPreset(btn, preset) {
+    if (btn && btn.disabled) return;
+    channelStatus.textContent = `Setting channel ${preset.channel}...`;
+    channelButtons.forEach((b) => (b.disabled = true));
+    try {
+      const res = await fetch('/api/channel/preset', {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ index: preset.index }),
+      });
+      if (!res.ok) throw new Error('channel preset failed');
+      const data = await res.json();
+      setActiveChannel(data.channel);
+      channelStatus.textContent = `Switched to channel ${data.channel}`;
+    } catch (err) {
+      channelStatus.textContent = 'Failed to change channel';
+    } finally {
+      channelButtons.forEach((b) => (b.disabled = b.classList.contains('disabled')));
+    }
+  }
+
+  // The input is the source of truth: whole minutes clamped to the server's
+  // 60-7200 s range, sent as seconds so the echoed window_seconds matches.
   function deviceWindowSeconds() {
-    const val = parseInt(deviceWindow?.value || '10', 10) || 10;
+    const val = Math.round(Number(deviceWindow?.value)) || 10;
     const clamped = Math.max(1, Math.min(120, val));
     if (deviceWindow) deviceWindow.value = clamped;
     return clamped * 60;
@@ -393,7 +523,7 @@ body, html {
     const windowSeconds = deviceWindowSeconds();
     deviceStatus.textContent = 'Loading devices...';
     try {
-      const res = await fetch(`/api/devices?window_minutes=${windowSeconds / 60}`);
+      const res = await fetch(`/api/devices?window_seconds=${windowSeconds}`);
       if (!res.ok) throw new Error('device fetch failed');
       const data = await res.json();
       devicesState = data.devices || [];
@@ -408,16 +538,19 @@ body, html {
     const windowSeconds = deviceWindowSeconds();
     const proto = location.protocol === 'https:' ? 'wss' : 'ws';
     deviceStatus.textContent = 'Connecting...';
-    deviceWs = new WebSocket(`${proto}://${location.host}/ws/devices?window_minutes=${windowSeconds / 60}`);
+    deviceWs = new WebSocket(`${proto}://${location.host}/ws/devices?window_seconds=${windowSeconds}`);
+    deviceWs.onopen = () => {
+      deviceRetries = 0;
+      if (devicesFrozen) sendDeviceControl({ freeze: true });
+    };
     deviceWs.onmessage = (evt) => {
       try {
         const data = JSON.parse(evt.data);
         devicesState = data.devices || [];
         renderDevices(devicesState);
         deviceStatus.textContent = devicesState.length ? '' : 'No devices in this window yet';
-        if (deviceWindow && data.window_seconds) {
-          deviceWindow.value = Math.round((data.window_seconds || 600) / 60);
-        }
+        // Don't copy window_seconds back into the input: a snapshot still in
+        // flight from the previous window would undo the user's edit.
       } catch {
         deviceStatus.textContent = 'Failed to parse devices';
       }
@@ -426,13 +559,27 @@ body, html {
     deviceWs.onclose = () => {
       if (!deviceWs) return;
       deviceStatus.textContent = 'Reconnecting...';
+      const delay = reconnectDelay(reconnect.devices_ms, deviceRetries++);
       setTimeout(() => {
         deviceWs = null;
         openDeviceSocket();
-      }, 2000);
+      }, delay);
     };
   }
 
+  function sendDeviceControl(msg) {
+    if (deviceWs && deviceWs.readyState === WebSocket.OPEN) {
+      deviceWs.send(JSON.stringify(msg));
+    }
+  }
+
+  function setDevicesFrozen(frozen) {
+    devicesFrozen = frozen;
+    if (deviceFreeze) deviceFreeze.textContent = frozen ? 'Resume' : 'Freeze';
+    deviceStatus.textContent = frozen ? 'Updates paused' : '';
+    sendDeviceControl({ freeze: frozen });
+  }
+
   function ensureDeviceSocket() {
     if (deviceWs && (deviceWs.readyState === WebSocket.OPEN || deviceWs.readyState === WebSocket.CONNECTING)) {
       return;
@@ -489,10 +636,14 @@ body, html {
           audio_jack: audioJack.checked,
           web_ui: webUi.checked,
           volume_by_signal: volumeBySignal.checked,
+          activity_drone: activityDrone.checked,
         }),
       });
       if (!res.ok) throw new Error('sound update failed');
       const data = await res.json();
+      if (data.reconnect) {
+        reconnect = { ...reconnect, ...data.reconnect };
+      }
       audioJack.checked = !!data.audio_jack;
       webUi.checked = !!data.web_ui_sound;
       soundStatus.textContent = 'Sound preferences saved';
@@ -535,9 +686,19 @@ body, html {
       return;
     }
     const proto = location.protocol === 'https:' ? 'wss' : 'ws';
-    ws = new WebSocket(`${proto}://${location.host}/ws/packets`);
+    ws = new WebSocket(`${proto}://${location.host}/ws/packets?format=bin`);
+    ws.binaryType = 'arraybuffer';
+    ws.onopen = () => {
+      packetRetries = 0;
+    };
     ws.onmessage = (evt) => {
       try {
+        if (evt.data instanceof ArrayBuffer) {
+          const bytes = new Uint8Array(evt.data);
+          const kind = BIN_KINDS[bytes[0]] || 'data-tick';
+          playEventSound(kind, (bytes[1] & 1) !== 0, bytes[2] / 255);
+          return;
+        }
         const data = JSON.parse(evt.data);
         playEventSound(data.kind, !!data.retry, data.amplitude ?? 1);
       } catch {
@@ -547,9 +708,30 @@ body, html {
     ws.onerror = () => ws && ws.close();
     ws.onclose = () => {
       if (webUi.checked) {
-        setTimeout(ensureWebsocket, 1500);
+        setTimeout(ensureWebsocket, reconnectDelay(reconnect.packets_ms, packetRetries++));
+      }
+    };
+  }
+
+  function openMeterSocket() {
+    const proto = location.protocol === 'https:' ? 'wss' : 'ws';
+    const meterWs = new WebSocket(`${proto}://${location.host}/ws/meter`);
+    meterWs.onopen = () => {
+      meterRetries = 0;
+    };
+    meterWs.onmessage = (evt) => {
+      try {
+        const data = JSON.parse(evt.data);
+        if (vuBar) vuBar.style.width = `${Math.min(100, (data.peak || 0) * 100)}%`;
+      } catch {
+        // Ignore malformed levels; the next one arrives shortly.
       }
     };
+    meterWs.onerror = () => meterWs.close();
+    meterWs.onclose = () => {
+      if (vuBar) vuBar.style.width = '0%';
+      setTimeout(openMeterSocket, reconnectDelay(reconnect.packets_ms, meterRetries++));
+    };
   }
 
   function closeWebsocket() {
@@ -582,6 +764,10 @@ body, html {
       'cts': { freq: 480, dur: 0.03, vol: 0.12 },
       'ack': { freq: 2200, dur: 0.02, vol: 0.05 },
       'data-tick': { freq: 820, dur: 0.03, vol: 0.09 },
+      'beacon-flood': { freq: [1400, 700], dur: 0.09, vol: 0.16 },
+      'probe-resp-flood': { freq: [1400, 700], dur: 0.09, vol: 0.16 },
+      'other': { freq: 1600, dur: 0.02, vol: 0.05 },
+      'null-data': { freq: 2200, dur: 0.015, vol: 0.04 },
     };
     const entry = palette[kind] || palette['data-tick'];
     const gainScale = Math.max(0.1, Math.min(1.2, amplitude || 1));
@@ -652,10 +838,50 @@ body, html {
     }
   }
 
+  function setCapturing(value) {
+    capturing = value;
+    if (captureBtn) captureBtn.textContent = capturing ? 'Stop capture' : 'Start capture';
+  }
+
+  captureBtn?.addEventListener('click', async () => {
+    captureBtn.disabled = true;
+    try {
+      const res = await fetch(capturing ? '/api/stop-capture' : '/api/start-capture', {
+        method: 'POST'
+      });
+      if (!res.ok) throw new Error('capture toggle failed');
+      const data = await res.json();
+      setCapturing(!!data.capturing);
+      audioJack.checked = !!data.audio_jack;
+      webUi.checked = !!data.web_ui_sound;
+      if (webUi.checked) {
+        ensureWebsocket();
+      } else {
+        closeWebsocket();
+      }
+      captureStatus.textContent = capturing ? 'Capturing' : 'Capture stopped, audio muted';
+    } catch (err) {
+      captureStatus.textContent = 'Failed to toggle capture';
+    } finally {
+      captureBtn.disabled = false;
+    }
+  });
+
+  // Keeps the speaker quiet while the dialog is up; the server resumes on
+  // its own if the page goes away before cancel.
+  function pauseAudio(seconds) {
+    fetch('/api/audio-pause', {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ seconds }),
+    }).catch(() => {});
+  }
+
   function toggle(show) {
     if (!modal) return;
     modal.classList.toggle('hidden', !show);
     if (!show && shutdownStatus) shutdownStatus.textContent = '';
+    pauseAudio(show ? 60 : 0);
   }
 
   open?.addEventListener('click', () => toggle(true));
@@ -686,6 +912,7 @@ body, html {
   audioJack?.addEventListener('change', updateSound);
   webUi?.addEventListener('change', updateSound);
   volumeBySignal?.addEventListener('change', updateSound);
+  activityDrone?.addEventListener('change', updateSound);
   modeSelect?.addEventListener('change', savePackets);
   toggleAll?.addEventListener('click', () => {
     const allOn = packetsState.every((p) => !!p.enabled);
@@ -696,8 +923,13 @@ body, html {
   deviceRefresh?.addEventListener('click', fetchDevices);
   deviceWindow?.addEventListener('change', () => {
     fetchDevices();
-    restartDeviceSocket();
+    if (deviceWs && deviceWs.readyState === WebSocket.OPEN) {
+      sendDeviceControl({ window_seconds: deviceWindowSeconds() });
+    } else {
+      restartDeviceSocket();
+    }
   });
+  deviceFreeze?.addEventListener('click', () => setDevicesFrozen(!devicesFrozen));
   deviceSelectAll?.addEventListener('click', () => toggleAllDevices(true));
   deviceDeselectAll?.addEventListener('click', () => toggleAllDevices(false));
   deviceReset?.addEventListener('click', async () => {
@@ -715,6 +947,8 @@ body, html {
   fetchSettings();
   fetchDevices();
   ensureDeviceSocket();
+  openMeterSocket();
+  setInterval(pollChannel, 30000);
   setSection('channels');
 })();
 "#;
@@ -740,6 +974,10 @@ body, html {
                         h2 { class: "card-title", "Channel select" }
                         p { class: "muted", "Pick a Wi-Fi channel for the monitor interface. Channels are grouped by band." }
                         div { class: "channel-groups",
+                            div { id: "channel-presets-group", class: "group", hidden: true,
+                                p { class: "group-title", "Favourites" }
+                                div { id: "channels-presets", class: "channel-buttons" }
+                            }
                             div { class: "group",
                                 p { class: "group-title", "2.4 GHz" }
                                 div { id: "channels-24", class: "channel-buttons" }
@@ -761,6 +999,7 @@ body, html {
                             }
                             button { id: "devices-refresh", class: "pill-btn", "Refresh" }
                             button { id: "devices-reset", class: "pill-btn", "Reset frame counts" }
+                            button { id: "devices-freeze", class: "pill-btn", "Freeze" }
                         }
                         div { class: "device-actions",
                             button { id: "devices-select-all", class: "pill-btn", "Select all" }
@@ -801,13 +1040,21 @@ body, html {
                                 input { id: "volume-by-signal", r#type: "checkbox" }
                                 span { "Volume follows signal strength" }
                             }
+                            label { class: "checkbox",
+                                input { id: "activity-drone", r#type: "checkbox" }
+                                span { "Activity drone instead of ticks" }
+                            }
                         }
                         p { class: "caption", "When Web UI is on, ticks play in your browser via WebSocket notifications." }
+                        div { class: "meter", div { id: "vu-bar", class: "meter-bar" } }
+                        p { class: "caption", "Audio jack output level." }
                         div { id: "sound-status", class: "status" }
                     }
                     div { id: "section-system", class: "card section", "data-section": "system",
                         h2 { class: "card-title", "System" }
                         p { class: "muted", "Power controls for the device." }
+                        button { id: "capture-btn", class: "primary", "Stop capture" }
+                        div { id: "capture-status", class: "status" }
                         button { id: "shutdown-btn", class: "primary", "Shutdown" }
                     }
                 }
@@ -825,6 +1072,7 @@ body, html {
             }
         }
         style { "{styles}" }
+        style { "{theme_css}" }
         script { "{script}" }
     }
 }
@@ -1,28 +1,46 @@
+use crate::audio::{AudioHandle, OutputMeter, SoundId};
 use crate::config::AppConfig;
-use crate::devices::{self, DeviceTracker};
-use crate::events::{EventKind, EventSettings, NoiseMode, PacketEvent};
+use crate::devices::{self, DeviceFilter, DeviceGrouping, DeviceRole, DeviceTracker};
+use crate::events::{
+    Band, DiscoveryMode, EventKind, EventSettings, NoiseMode, PacketEvent, sound_for,
+    spaced_tone_groups,
+};
+use crate::record::{self, Recorder};
+use crate::sniffer::{self, SnifferController};
 use crate::ui;
 use anyhow::{Context, Result};
 use axum::extract::ws::{Message, WebSocket};
 use axum::{
     Json, Router,
-    extract::{Query, State, WebSocketUpgrade},
-    http::StatusCode,
-    response::{Html, IntoResponse},
+    body::Body,
+    extract::{
+        ConnectInfo, DefaultBodyLimit, FromRequest, Path, Query, Request, State, WebSocketUpgrade,
+        rejection::JsonRejection,
+    },
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
     routing::{get, post},
 };
 use serde::{Deserialize, Serialize};
-use serde_json;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::net::TcpListener;
 use tokio::process::Command;
 use tokio::sync::{RwLock, broadcast};
 use tokio::time;
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::request_id::{
+    MakeRequestUuid, PropagateRequestIdLayer, RequestId, SetRequestIdLayer,
+};
+use tower_http::timeout::TimeoutLayer;
 use tower_http::trace::TraceLayer;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 
 #[derive(Clone)]
 pub struct AppState {
@@ -30,89 +48,460 @@ pub struct AppState {
     pub audio_enabled: Arc<AtomicBool>,
     pub web_sound_enabled: Arc<AtomicBool>,
     pub volume_by_signal: Arc<AtomicBool>,
+    /// A continuous tone following overall activity replaces per-event
+    /// sounds on the audio output.
+    pub activity_drone: Arc<AtomicBool>,
     pub packet_tx: broadcast::Sender<PacketEvent>,
     pub channel: ChannelController,
     pub channels_24: Arc<RwLock<Vec<ChannelInfo>>>,
     pub channels_5: Arc<RwLock<Vec<ChannelInfo>>>,
+    pub channels_6: Arc<RwLock<Vec<ChannelInfo>>>,
     pub event_settings: Arc<RwLock<EventSettings>>,
     pub device_tracker: Arc<DeviceTracker>,
+    pub sniffer: SnifferController,
+    pub recorder: Option<Recorder>,
+    /// Sound toggles saved by `/api/stop-capture`, restored on start.
+    pub paused_sound: Arc<std::sync::Mutex<Option<(bool, bool)>>>,
+    pub meter: Arc<OutputMeter>,
+    pub audio: AudioHandle,
+    /// Set by `/api/reset-all`; the audio task rebuilds its rate limiter,
+    /// windows and detectors before its next event.
+    pub reset_requested: Arc<AtomicBool>,
 }
 
 #[derive(Clone)]
 pub struct ChannelController {
-    interface: Arc<String>,
+    interface: Arc<RwLock<String>>,
     current: Arc<RwLock<Option<u16>>>,
+    /// `current` mirrored for per-frame checks that can't await; 0 while
+    /// the channel is unknown.
+    tuned: Arc<AtomicU16>,
+    /// When `iw` was last asked, successfully or not, so live reads can be
+    /// rate-limited.
+    last_refresh: Arc<RwLock<Option<Instant>>>,
+    /// Announces each channel `set_channel` switches to.
+    changes: broadcast::Sender<u16>,
 }
 
+/// Live channel reads within this long of the last one reuse its result.
+const CHANNEL_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
 impl ChannelController {
     pub fn new(interface: String) -> Self {
         Self {
-            interface: Arc::new(interface),
+            interface: Arc::new(RwLock::new(interface)),
             current: Arc::new(RwLock::new(None)),
+            tuned: Arc::new(AtomicU16::new(0)),
+            last_refresh: Arc::new(RwLock::new(None)),
+            changes: broadcast::channel(8).0,
         }
     }
 
+    pub fn subscribe_changes(&self) -> broadcast::Receiver<u16> {
+        self.changes.subscribe()
+    }
+
+    pub async fn interface(&self) -> String {
+        self.interface.read().await.clone()
+    }
+
+    /// Points channel reads/writes at a different interface. The cached
+    /// channel is dropped since it belonged to the old radio.
+    pub async fn set_interface(&self, interface: String) {
+        *self.interface.write().await = interface;
+        self.store(&mut *self.current.write().await, None);
+    }
+
+    /// Re-reads the channel from `iw`. A failed read or unparsable output
+    /// keeps the last known channel rather than clearing it, so a transient
+    /// `iw` hiccup doesn't show as "unknown"; with nothing known yet a read
+    /// error is returned.
     pub async fn refresh_current(&self) -> Result<Option<u16>> {
-        let detected = current_channel(&self.interface).await?;
+        let interface = self.interface().await;
+        // Stamped up front so a failing `iw` is retried at the same pace.
+        *self.last_refresh.write().await = Some(Instant::now());
+        let read = current_channel(&interface).await;
         let mut guard = self.current.write().await;
-        *guard = detected;
-        Ok(*guard)
+        let channel = keep_last_channel(*guard, read, &interface)?;
+        self.store(&mut guard, channel);
+        Ok(channel)
+    }
+
+    /// Re-reads the channel unless it was read within
+    /// `CHANNEL_REFRESH_INTERVAL`, catching driver-initiated (e.g. DFS)
+    /// switches without running `iw` on every poll.
+    pub async fn refresh_if_stale(&self) -> Result<Option<u16>> {
+        let fresh = self
+            .last_refresh
+            .read()
+            .await
+            .is_some_and(|at| at.elapsed() < CHANNEL_REFRESH_INTERVAL);
+        if fresh {
+            return Ok(self.current().await);
+        }
+        self.refresh_current().await
     }
 
     pub async fn current(&self) -> Option<u16> {
         *self.current.read().await
     }
 
+    /// The cached channel, without awaiting the lock.
+    pub fn tuned(&self) -> Option<u16> {
+        match self.tuned.load(Ordering::Relaxed) {
+            0 => None,
+            channel => Some(channel),
+        }
+    }
+
+    fn store(&self, current: &mut Option<u16>, channel: Option<u16>) {
+        *current = channel;
+        self.tuned.store(channel.unwrap_or(0), Ordering::Relaxed);
+    }
+
     pub async fn set_channel(&self, channel: u16) -> Result<u16> {
-        apply_channel(&self.interface, channel).await?;
-        let mut guard = self.current.write().await;
-        *guard = Some(channel);
+        self.set_channel_with(channel, apply_channel).await
+    }
+
+    /// `set_channel`, retuning the radio with `apply` instead of `iw`.
+    async fn set_channel_with(
+        &self,
+        channel: u16,
+        apply: impl AsyncFnOnce(&str, u16) -> Result<()>,
+    ) -> Result<u16> {
+        let interface = self.interface().await;
+        apply(&interface, channel).await?;
+        self.switched_to(channel).await;
         Ok(channel)
     }
+
+    /// Caches a channel the radio was just tuned to, announcing it to
+    /// `subscribe_changes` listeners if it differs from the last one.
+    async fn switched_to(&self, channel: u16) {
+        let mut guard = self.current.write().await;
+        let previous = *guard;
+        self.store(&mut guard, Some(channel));
+        if previous != Some(channel) {
+            let _ = self.changes.send(channel);
+        }
+    }
 }
 
 pub async fn serve(state: AppState) -> Result<()> {
-    {
-        let iface = state.config.monitor_interface.clone();
-        let channels_24 = Arc::clone(&state.channels_24);
-        let channels_5 = Arc::clone(&state.channels_5);
-        tokio::spawn(async move {
-            match detect_supported_channels(&iface).await {
-                Ok((c24, c5)) => {
-                    *channels_24.write().await = c24;
-                    *channels_5.write().await = c5;
-                }
-                Err(err) => {
-                    tracing::warn!("Unable to detect supported channels: {err:?}");
-                }
-            }
-        });
-    }
+    spawn_channel_detection(&state, state.config.monitor_interface.clone());
+
+    let addr: SocketAddr = state.config.http_bind.parse()?;
+    let listener = TcpListener::bind(&addr).await?;
+    tracing::info!("UI listening on http://{addr}");
+
+    axum::serve(
+        listener,
+        router(state).into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(graceful_shutdown())
+    .await?;
+
+    Ok(())
+}
 
-    let router = Router::new()
+fn router(state: AppState) -> Router {
+    let mut pages = Router::new()
         .route("/", get(index))
         .route("/api/settings", get(settings))
-        .route("/api/channel", post(set_channel))
+        .route("/api/channel", get(live_channel).post(set_channel))
+        .route("/api/channel/preset", post(set_channel_preset))
         .route("/api/sound", post(update_sound))
+        .route("/api/audio-pause", post(audio_pause))
         .route("/api/events", get(events_settings).post(update_events))
+        .route("/api/events/test", post(test_events))
+        .route("/api/palette.wav", get(palette_wav))
+        .route("/api/interfaces", get(interfaces))
+        .route("/api/interface", post(set_interface))
         .route("/api/devices", get(devices))
+        .route("/api/device/:mac", get(device_detail))
+        .route("/api/networks", get(networks))
+        .route("/api/frames/recent", get(recent_frames))
         .route("/api/device-filters", post(update_device_filters))
+        .route("/api/device-alias", post(device_alias))
         .route("/api/device-reset", post(reset_device_counts))
+        .route("/api/reset-all", post(reset_all))
+        .route("/api/stop-capture", post(stop_capture))
+        .route("/api/start-capture", post(start_capture))
+        .route("/api/locate", post(locate))
+        .route("/api/focus-ssid", post(focus_ssid))
+        .route("/api/sessions", get(sessions))
         .route("/api/shutdown", post(shutdown))
+        .route("/api/openapi.json", get(openapi_json))
+        .route("/metrics", get(metrics))
+        .layer(middleware::from_fn(pretty_json));
+    // WebSocket upgrades carry no body worth compressing, so they stay
+    // outside the layer.
+    if state.config.http_compression {
+        pages = pages.layer(CompressionLayer::new());
+    }
+    let router = pages
         .route("/ws/packets", get(ws_packets))
         .route("/ws/devices", get(ws_devices))
-        .layer(TraceLayer::new_for_http())
+        .route("/ws/meter", get(ws_meter))
+        .layer(DefaultBodyLimit::max(state.config.http_body_limit))
+        .layer(TimeoutLayer::new(Duration::from_secs(
+            state.config.http_timeout_secs,
+        )))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
         .with_state(state.clone());
+    match cors_layer(&state.config.allowed_origins) {
+        Some(cors) => router.layer(cors),
+        None => router,
+    }
+}
 
-    let addr: SocketAddr = state.config.http_bind.parse()?;
-    let listener = TcpListener::bind(&addr).await?;
-    tracing::info!("UI listening on http://{addr}");
+/// Tags each request's span with its `x-request-id`, either the client's or
+/// a generated UUID, which is echoed back on the response.
+fn request_span(request: &axum::extract::Request) -> tracing::Span {
+    let id = request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+        .unwrap_or("-");
+    tracing::info_span!(
+        "request",
+        id = %id,
+        method = %request.method(),
+        uri = %request.uri(),
+    )
+}
 
-    axum::serve(listener, router)
-        .with_graceful_shutdown(graceful_shutdown())
-        .await?;
+/// `?pretty=1` on any JSON endpoint re-serializes the response indented, for
+/// reading the API from a terminal. Other responses pass through untouched.
+async fn pretty_json(request: Request, next: Next) -> Response {
+    let pretty = request.uri().query().is_some_and(wants_pretty);
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !pretty || !is_json {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            return ApiError::internal(format!("Failed to read response body: {err}"))
+                .into_response();
+        }
+    };
+    let indented = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .and_then(|value| serde_json::to_vec_pretty(&value));
+    match indented {
+        Ok(indented) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(indented))
+        }
+        Err(_) => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
 
-    Ok(())
+fn wants_pretty(query: &str) -> bool {
+    query
+        .split('&')
+        .any(|pair| matches!(pair, "pretty" | "pretty=1" | "pretty=true"))
+}
+
+fn request_id(headers: &HeaderMap) -> String {
+    headers
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("-")
+        .to_string()
+}
+
+/// No layer means no CORS headers, so browsers keep the same-origin default.
+fn cors_layer(allowed: &[String]) -> Option<CorsLayer> {
+    if allowed.is_empty() {
+        return None;
+    }
+    let origin = if allowed.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        let origins = allowed
+            .iter()
+            .filter_map(|o| match HeaderValue::from_str(o) {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    tracing::warn!("Ignoring invalid allowed origin {o:?}");
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        AllowOrigin::list(origins)
+    };
+    Some(
+        CorsLayer::new()
+            .allow_origin(origin)
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers([header::CONTENT_TYPE]),
+    )
+}
+
+/// WebSocket upgrades aren't covered by CORS, so check Origin by hand.
+/// Requests without an Origin header come from non-browser clients and pass.
+fn ws_origin_allowed(headers: &HeaderMap, allowed: &[String]) -> bool {
+    let Some(origin) = headers.get(header::ORIGIN).and_then(|v| v.to_str().ok()) else {
+        return true;
+    };
+    let origin = origin.trim_end_matches('/');
+    if allowed.iter().any(|o| o == "*" || o == origin) {
+        return true;
+    }
+    let authority = origin
+        .strip_prefix("http://")
+        .or_else(|| origin.strip_prefix("https://"))
+        .unwrap_or(origin);
+    headers
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|host| host.eq_ignore_ascii_case(authority))
+}
+
+fn spawn_channel_detection(state: &AppState, iface: String) {
+    let channels_24 = Arc::clone(&state.channels_24);
+    let channels_5 = Arc::clone(&state.channels_5);
+    let channels_6 = Arc::clone(&state.channels_6);
+    tokio::spawn(async move {
+        match detect_supported_channels(&iface).await {
+            Ok(SupportedChannels { ghz24, ghz5, ghz6 }) => {
+                *channels_24.write().await = ghz24;
+                *channels_5.write().await = ghz5;
+                *channels_6.write().await = ghz6;
+            }
+            Err(err) => {
+                tracing::warn!("Unable to detect supported channels: {err:?}");
+            }
+        }
+    });
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "radioscope"),
+    paths(
+        settings,
+        live_channel,
+        set_channel,
+        set_channel_preset,
+        update_sound,
+        events_settings,
+        update_events,
+        test_events,
+        palette_wav,
+        audio_pause,
+        interfaces,
+        set_interface,
+        devices,
+        device_detail,
+        networks,
+        recent_frames,
+        update_device_filters,
+        device_alias,
+        reset_device_counts,
+        reset_all,
+        stop_capture,
+        start_capture,
+        locate,
+        focus_ssid,
+        sessions,
+        shutdown,
+        metrics,
+        ws_packets,
+        ws_devices,
+        ws_meter,
+    ),
+    components(schemas(DeviceSocketControl))
+)]
+struct ApiDoc;
+
+async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Capture-quality counters in the Prometheus text format.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses((status = 200, content_type = "text/plain", body = String))
+)]
+async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let body = format!(
+        "# HELP radioscope_estimated_missed_frames_total Frames the capture missed, estimated from 802.11 sequence number gaps.\n\
+         # TYPE radioscope_estimated_missed_frames_total counter\n\
+         radioscope_estimated_missed_frames_total {}\n",
+        state.device_tracker.missed_frames_total()
+    );
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+/// JSON error envelope returned by every handler:
+/// `{"error": "<message>", "code": "<status reason>"}`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiError {
+    #[serde(skip)]
+    status: StatusCode,
+    error: String,
+    /// Snake-case form of the HTTP status, e.g. `bad_request`.
+    code: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        let code = status
+            .canonical_reason()
+            .unwrap_or("error")
+            .to_ascii_lowercase()
+            .replace(' ', "_");
+        Self {
+            status,
+            error: message.into(),
+            code,
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self)).into_response()
+    }
+}
+
+/// `Json` whose rejections (malformed or mistyped bodies) use the same
+/// envelope as handler errors.
+struct ApiJson<T>(T);
+
+#[axum::async_trait]
+impl<S, T> FromRequest<S> for ApiJson<T>
+where
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, ApiError> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| ApiError::new(rejection.status(), rejection.body_text()))?;
+        Ok(Self(value))
+    }
 }
 
 async fn graceful_shutdown() {
@@ -121,283 +510,1469 @@ async fn graceful_shutdown() {
 }
 
 async fn index(State(state): State<AppState>) -> impl IntoResponse {
-    Html(ui::render_html(&state.config.monitor_interface))
+    Html(ui::render_html(
+        &state.channel.interface().await,
+        &theme_from_config(&state.config),
+    ))
 }
 
-#[derive(Serialize, Clone)]
+/// A channel the radio supports. Channel numbers repeat across bands, so
+/// `band` and `frequency_mhz` identify it.
+#[derive(Serialize, Clone, ToSchema)]
 pub struct ChannelInfo {
     channel: u16,
     enabled: bool,
+    frequency_mhz: u16,
+    band: Band,
 }
 
-#[derive(Serialize)]
+impl ChannelInfo {
+    fn new(band: Band, channel: u16, enabled: bool) -> Self {
+        Self {
+            channel,
+            enabled,
+            frequency_mhz: band.frequency_mhz(channel),
+            band,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 struct EventToggle {
     id: EventKind,
     label: &'static str,
     enabled: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct SettingsResponse {
     monitor_interface: String,
     channel: Option<u16>,
     audio_jack: bool,
     web_ui_sound: bool,
     volume_by_signal: bool,
+    activity_drone: bool,
     available_channels_24ghz: Vec<ChannelInfo>,
     available_channels_5ghz: Vec<ChannelInfo>,
+    available_channels_6ghz: Vec<ChannelInfo>,
+    channel_presets: Vec<ChannelPreset>,
     packet_events: Vec<EventToggle>,
     mode: NoiseMode,
     data_tick_n: u32,
+    capturing: bool,
+    reconnect: ReconnectSettings,
+    theme: ThemeSettings,
+    audio_stream: AudioStreamStatus,
+    /// Whole seconds since the capture delivered a packet; null while
+    /// stopped.
+    seconds_since_last_frame: Option<u64>,
 }
 
-async fn settings(
-    State(state): State<AppState>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+/// One `CHANNEL_PRESETS` entry. `supported` is true until the radio's
+/// channel list is known.
+#[derive(Serialize, ToSchema)]
+struct ChannelPreset {
+    index: usize,
+    channel: u16,
+    supported: bool,
+}
+
+/// Health of the audio output; `rebuilds` counts recoveries after the
+/// device disappeared.
+#[derive(Serialize, ToSchema)]
+struct AudioStreamStatus {
+    running: bool,
+    rebuilds: u32,
+    last_error: Option<String>,
+}
+
+/// Effective UI colours: the configured overrides or the built-in defaults.
+#[derive(Serialize, ToSchema)]
+struct ThemeSettings {
+    accent: String,
+    background: String,
+}
+
+fn theme_from_config(config: &AppConfig) -> ui::Theme {
+    ui::Theme {
+        accent: config.theme_accent.clone(),
+        background: config.theme_background.clone(),
+    }
+}
+
+/// WebSocket reconnect delays for the browser client. Each failed attempt
+/// doubles the delay up to `max_ms`.
+#[derive(Serialize, ToSchema)]
+struct ReconnectSettings {
+    packets_ms: u64,
+    devices_ms: u64,
+    max_ms: u64,
+}
+
+#[utoipa::path(get, path = "/api/settings", responses((status = 200, body = SettingsResponse)))]
+async fn settings(State(state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
     let channel = state.channel.current().await;
     let channels_24 = state.channels_24.read().await.clone();
     let channels_5 = state.channels_5.read().await.clone();
+    let channels_6 = state.channels_6.read().await.clone();
     let event_settings = state.event_settings.read().await.clone();
     let toggles = all_event_toggles(&event_settings);
+    let channel_presets = state
+        .config
+        .channel_presets
+        .iter()
+        .enumerate()
+        .map(|(index, &channel)| ChannelPreset {
+            index,
+            channel,
+            supported: channel_supported(&channels_24, &channels_5, channel).unwrap_or(true),
+        })
+        .collect();
     Ok(Json(SettingsResponse {
-        monitor_interface: state.config.monitor_interface.clone(),
+        monitor_interface: state.channel.interface().await,
         channel,
         audio_jack: state.audio_enabled.load(Ordering::Relaxed),
         web_ui_sound: state.web_sound_enabled.load(Ordering::Relaxed),
         volume_by_signal: state.volume_by_signal.load(Ordering::Relaxed),
+        activity_drone: state.activity_drone.load(Ordering::Relaxed),
         available_channels_24ghz: channels_24,
         available_channels_5ghz: channels_5,
+        available_channels_6ghz: channels_6,
+        channel_presets,
         packet_events: toggles,
         mode: event_settings.mode.clone(),
-        data_tick_n: data_tick_for(&event_settings.mode),
+        data_tick_n: event_settings.data_tick_n(),
+        capturing: state.sniffer.is_running(),
+        reconnect: ReconnectSettings {
+            packets_ms: state.config.ws_packets_reconnect_ms,
+            devices_ms: state.config.ws_devices_reconnect_ms,
+            max_ms: state.config.ws_reconnect_max_ms,
+        },
+        theme: ThemeSettings {
+            accent: state
+                .config
+                .theme_accent
+                .clone()
+                .unwrap_or_else(|| ui::DEFAULT_ACCENT.to_string()),
+            background: state
+                .config
+                .theme_background
+                .clone()
+                .unwrap_or_else(|| ui::DEFAULT_BACKGROUND.to_string()),
+        },
+        audio_stream: {
+            let health = state.audio.stream_health();
+            AudioStreamStatus {
+                running: health.is_running(),
+                rebuilds: health.rebuilds(),
+                last_error: health.last_error(),
+            }
+        },
+        seconds_since_last_frame: state.sniffer.since_last_frame().map(|age| age.as_secs()),
     }))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct DevicesQuery {
     window_seconds: Option<u64>,
     window_minutes: Option<u64>,
+    /// Hide devices seen fewer times than this (defaults to `MIN_FRAMES`).
+    min_frames: Option<u64>,
+    filter: Option<DeviceFilter>,
+    /// `ssid` clusters devices by network instead of by role.
+    group_by: Option<DeviceGrouping>,
 }
 
-#[derive(Serialize)]
+/// Per-request visibility rules applied on top of the time window.
+#[derive(Clone, Copy)]
+struct DeviceListing {
+    min_frames: u64,
+    filter: DeviceFilter,
+    group_by: DeviceGrouping,
+}
+
+impl DeviceListing {
+    fn from_query(params: &DevicesQuery, config: &AppConfig) -> Self {
+        Self {
+            min_frames: params.min_frames.unwrap_or(config.min_frames),
+            filter: params.filter.unwrap_or_default(),
+            group_by: params.group_by.unwrap_or_default(),
+        }
+    }
+
+    fn snapshot(&self, tracker: &DeviceTracker, window: u64) -> Vec<devices::DeviceView> {
+        let list = tracker.snapshot(Duration::from_secs(window), self.min_frames);
+        self.group_by.apply(self.filter.apply(list))
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 struct DevicesResponse {
     window_seconds: u64,
     devices: Vec<devices::DeviceView>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/devices",
+    params(DevicesQuery),
+    responses((status = 200, body = DevicesResponse))
+)]
 async fn devices(
     State(state): State<AppState>,
     Query(params): Query<DevicesQuery>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ApiError> {
     let window = window_from_query(&params);
-    let snapshot = state.device_tracker.snapshot(Duration::from_secs(window));
+    let snapshot =
+        DeviceListing::from_query(&params, &state.config).snapshot(&state.device_tracker, window);
     Ok(Json(DevicesResponse {
         window_seconds: window,
         devices: snapshot,
     }))
 }
 
-fn window_from_query(params: &DevicesQuery) -> u64 {
-    let base_seconds = if let Some(min) = params.window_minutes {
-        min.saturating_mul(60)
-    } else if let Some(sec) = params.window_seconds {
-        sec
-    } else {
-        600
-    };
-    base_seconds.clamp(60, 7200)
+#[utoipa::path(
+    get,
+    path = "/api/device/{mac}",
+    params(("mac" = String, Path, description = "Device MAC, any common separator")),
+    responses(
+        (status = 200, body = devices::DeviceDetail),
+        (status = 400, body = ApiError),
+        (status = 404, body = ApiError)
+    )
+)]
+async fn device_detail(
+    State(state): State<AppState>,
+    Path(mac): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mac = devices::parse_mac(&mac)
+        .ok_or_else(|| ApiError::bad_request(format!("Invalid MAC {mac:?}")))?;
+    state
+        .device_tracker
+        .detail(mac)
+        .map(Json)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "Device not tracked"))
 }
 
-#[derive(Deserialize)]
-struct ChannelRequest {
-    channel: u16,
+#[derive(Serialize, ToSchema)]
+struct RecentFramesResponse {
+    capacity: usize,
+    /// Oldest first.
+    frames: Vec<sniffer::RecentFrame>,
 }
 
-#[derive(Serialize)]
-struct ChannelResponse {
-    channel: u16,
+/// The last frames that didn't classify, as hex with a parsed header
+/// summary, for debugging driver quirks without tcpdump.
+#[utoipa::path(
+    get,
+    path = "/api/frames/recent",
+    responses(
+        (status = 200, body = RecentFramesResponse),
+        (status = 404, description = "DEBUG_RECENT_FRAMES is not set", body = ApiError)
+    )
+)]
+async fn recent_frames(State(state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
+    let recent = state.sniffer.recent_frames().ok_or_else(|| {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            "Frame capture for debugging is off; set DEBUG_RECENT_FRAMES",
+        )
+    })?;
+    Ok(Json(RecentFramesResponse {
+        capacity: recent.capacity(),
+        frames: recent.snapshot(),
+    }))
 }
 
-async fn set_channel(
-    State(state): State<AppState>,
-    Json(body): Json<ChannelRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let channel = state
-        .channel
-        .set_channel(body.channel)
-        .await
-        .map_err(|err| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to set channel: {err}"),
-            )
-        })?;
-
-    tracing::info!("Monitor interface set to channel {channel}");
-    Ok(Json(ChannelResponse { channel }))
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct NetworksQuery {
+    /// `csv` for a spreadsheet-friendly export; JSON otherwise.
+    format: Option<String>,
 }
 
-#[derive(Deserialize)]
-struct UpdateSoundRequest {
-    audio_jack: Option<bool>,
-    web_ui: Option<bool>,
-    volume_by_signal: Option<bool>,
+#[derive(Serialize, ToSchema)]
+struct NetworksResponse {
+    networks: Vec<devices::NetworkView>,
 }
 
-#[derive(Serialize)]
-struct SoundResponse {
-    audio_jack: bool,
-    web_ui_sound: bool,
-    volume_by_signal: bool,
+/// AP inventory: one row per BSSID the tracker has seen beaconing.
+#[utoipa::path(
+    get,
+    path = "/api/networks",
+    params(NetworksQuery),
+    responses(
+        (status = 200, description = "JSON, or CSV with `?format=csv`", content(
+            (NetworksResponse = "application/json"),
+            (String = "text/csv")
+        ))
+    )
+)]
+async fn networks(State(state): State<AppState>, Query(params): Query<NetworksQuery>) -> Response {
+    let networks = state.device_tracker.networks();
+    if params.format.as_deref() == Some("csv") {
+        return (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"networks.csv\"",
+                ),
+            ],
+            networks_csv(&networks),
+        )
+            .into_response();
+    }
+    Json(NetworksResponse { networks }).into_response()
 }
 
-async fn update_sound(
-    State(state): State<AppState>,
-    Json(body): Json<UpdateSoundRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    if let Some(audio_jack) = body.audio_jack {
-        state.audio_enabled.store(audio_jack, Ordering::Relaxed);
+fn networks_csv(networks: &[devices::NetworkView]) -> String {
+    fn opt<T: ToString>(value: Option<T>) -> String {
+        value.map(|v| v.to_string()).unwrap_or_default()
     }
-    if let Some(web_ui) = body.web_ui {
-        state.web_sound_enabled.store(web_ui, Ordering::Relaxed);
-    }
-    if let Some(v) = body.volume_by_signal {
-        state.volume_by_signal.store(v, Ordering::Relaxed);
+    let mut out = String::from(
+        "bssid,ssid,channel,security,channel_width,oui,locally_administered,\
+         rssi_min_dbm,rssi_max_dbm,first_seen_ms,last_seen_ms,frames\n",
+    );
+    for net in networks {
+        let row = [
+            net.bssid.clone(),
+            csv_field(net.ssid.as_deref().unwrap_or("")),
+            opt(net.channel),
+            opt(net.security.map(|s| s.label())),
+            opt(net.channel_width),
+            net.oui.clone(),
+            net.locally_administered.to_string(),
+            opt(net.rssi_min_dbm),
+            opt(net.rssi_max_dbm),
+            net.first_seen_ms.to_string(),
+            net.last_seen_ms.to_string(),
+            net.frames.to_string(),
+        ];
+        out.push_str(&row.join(","));
+        out.push('\n');
     }
-
-    Ok(Json(SoundResponse {
-        audio_jack: state.audio_enabled.load(Ordering::Relaxed),
-        web_ui_sound: state.web_sound_enabled.load(Ordering::Relaxed),
-        volume_by_signal: state.volume_by_signal.load(Ordering::Relaxed),
-    }))
+    out
 }
 
-#[derive(Serialize)]
-struct EventsResponse {
-    mode: NoiseMode,
-    data_tick_n: u32,
-    events: Vec<EventToggle>,
+/// Quotes a field when it holds a delimiter, quote or line break. Text a
+/// spreadsheet would run as a formula gets a leading `'`, since SSIDs are
+/// whatever a nearby transmitter chose.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{value}")
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
 }
 
-async fn events_settings(
-    State(state): State<AppState>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let settings = state.event_settings.read().await.clone();
-    Ok(Json(build_events_response(&settings)))
+const DEVICE_WINDOW_DEFAULT_SECS: u64 = 600;
+/// Bounds for the device window; the UI's 1-120 minute input matches.
+const DEVICE_WINDOW_MIN_SECS: u64 = 60;
+const DEVICE_WINDOW_MAX_SECS: u64 = 7200;
+
+/// Seconds take precedence over minutes so a client sending its exact value
+/// gets that value echoed back; minutes remain for older clients.
+fn window_from_query(params: &DevicesQuery) -> u64 {
+    let base_seconds = match (params.window_seconds, params.window_minutes) {
+        (Some(secs), _) => secs,
+        (None, Some(min)) => min.saturating_mul(60),
+        (None, None) => DEVICE_WINDOW_DEFAULT_SECS,
+    };
+    base_seconds.clamp(DEVICE_WINDOW_MIN_SECS, DEVICE_WINDOW_MAX_SECS)
 }
 
-#[derive(Deserialize)]
-struct UpdateEventsRequest {
-    mode: Option<NoiseMode>,
-    events: Option<Vec<EventToggleInput>>,
+#[derive(Serialize, ToSchema)]
+struct InterfaceInfo {
+    name: String,
+    description: Option<String>,
+    mode: Option<String>,
+    monitor: bool,
+    active: bool,
 }
 
-#[derive(Deserialize)]
-struct EventToggleInput {
-    id: EventKind,
-    enabled: bool,
+#[derive(Serialize, ToSchema)]
+struct InterfacesResponse {
+    interfaces: Vec<InterfaceInfo>,
 }
 
-async fn update_events(
-    State(state): State<AppState>,
-    Json(body): Json<UpdateEventsRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let updated = {
-        let mut settings = state.event_settings.write().await;
-        if let Some(mode) = body.mode {
-            settings.mode = mode;
-        }
-        if let Some(events) = body.events {
-            for evt in events {
-                settings.enabled.insert(evt.id, evt.enabled);
+#[utoipa::path(
+    get,
+    path = "/api/interfaces",
+    responses(
+        (status = 200, body = InterfacesResponse),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn interfaces(State(state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
+    let list = list_capture_devices().await?;
+    let active_interface = state.channel.interface().await;
+
+    let mut interfaces = Vec::with_capacity(list.len());
+    for device in list {
+        // Interfaces iw doesn't know about (loopback, usb, or no iw at all)
+        // are listed with an unknown mode.
+        let mode = match interface_mode(&device.name).await {
+            Ok(mode) => mode,
+            Err(err) => {
+                tracing::debug!("No wireless mode for {}: {err:?}", device.name);
+                None
             }
+        };
+        interfaces.push(InterfaceInfo::new(
+            device.name,
+            device.desc,
+            mode,
+            &active_interface,
+        ));
+    }
+    Ok(Json(InterfacesResponse { interfaces }))
+}
+
+impl InterfaceInfo {
+    fn new(
+        name: String,
+        description: Option<String>,
+        mode: Option<String>,
+        monitor_interface: &str,
+    ) -> Self {
+        Self {
+            monitor: mode.as_deref() == Some("monitor"),
+            active: name == monitor_interface,
+            name,
+            description,
+            mode,
         }
-        settings.clone()
-    };
-    Ok(Json(build_events_response(&updated)))
+    }
 }
 
-#[derive(Deserialize)]
-struct DeviceFilterRequest {
-    devices: Vec<DeviceToggleInput>,
+#[derive(Serialize, ToSchema)]
+struct CaptureResponse {
+    capturing: bool,
+    audio_jack: bool,
+    web_ui_sound: bool,
 }
 
-#[derive(Deserialize)]
-struct DeviceToggleInput {
-    mac: String,
-    allowed: bool,
+impl CaptureResponse {
+    fn from_state(state: &AppState) -> Self {
+        Self {
+            capturing: state.sniffer.is_running(),
+            audio_jack: state.audio_enabled.load(Ordering::Relaxed),
+            web_ui_sound: state.web_sound_enabled.load(Ordering::Relaxed),
+        }
+    }
 }
 
-#[derive(Serialize)]
-struct DeviceFilterResponse {
-    updated: usize,
+/// Panic stop: mutes all audio and stops the capture thread, leaving the
+/// server up. The sound toggles are remembered for `/api/start-capture`.
+#[utoipa::path(post, path = "/api/stop-capture", responses((status = 200, body = CaptureResponse)))]
+async fn stop_capture(State(state): State<AppState>) -> Json<CaptureResponse> {
+    let audio = state.audio_enabled.swap(false, Ordering::Relaxed);
+    let web = state.web_sound_enabled.swap(false, Ordering::Relaxed);
+    {
+        let mut paused = state.paused_sound.lock().expect("paused sound poisoned");
+        if paused.is_none() {
+            *paused = Some((audio, web));
+        }
+    }
+    state.sniffer.stop().await;
+    tracing::info!("Capture stopped via API");
+    Json(CaptureResponse::from_state(&state))
 }
 
-async fn update_device_filters(
-    State(state): State<AppState>,
-    Json(body): Json<DeviceFilterRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let mut parsed = Vec::new();
-    for item in body.devices {
-        let mac = devices::parse_mac(&item.mac).ok_or_else(|| {
-            (
-                StatusCode::BAD_REQUEST,
-                format!("Invalid MAC address: {}", item.mac),
-            )
-        })?;
-        parsed.push((mac, item.allowed));
+#[utoipa::path(post, path = "/api/start-capture", responses((status = 200, body = CaptureResponse)))]
+async fn start_capture(State(state): State<AppState>) -> Json<CaptureResponse> {
+    if !state.sniffer.is_running() {
+        state.sniffer.start(state.channel.interface().await);
+        tracing::info!("Capture started via API");
     }
+    let paused = state
+        .paused_sound
+        .lock()
+        .expect("paused sound poisoned")
+        .take();
+    if let Some((audio, web)) = paused {
+        state.audio_enabled.store(audio, Ordering::Relaxed);
+        state.web_sound_enabled.store(web, Ordering::Relaxed);
+    }
+    Json(CaptureResponse::from_state(&state))
+}
 
-    state.device_tracker.set_many(&parsed);
-    Ok(Json(DeviceFilterResponse {
-        updated: parsed.len(),
-    }))
+#[derive(Deserialize, ToSchema)]
+struct LocateRequest {
+    mac: Option<String>,
 }
 
-#[derive(Serialize)]
-struct DeviceResetResponse {
-    reset: bool,
+#[derive(Serialize, ToSchema)]
+struct LocateResponse {
+    target: Option<String>,
 }
 
-async fn reset_device_counts(
+/// Starts locator mode for `mac`; a null or empty `mac` stops it.
+#[utoipa::path(
+    post,
+    path = "/api/locate",
+    request_body = LocateRequest,
+    responses((status = 200, body = LocateResponse), (status = 400, body = ApiError))
+)]
+async fn locate(
     State(state): State<AppState>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    state.device_tracker.reset_counts();
-    Ok(Json(DeviceResetResponse { reset: true }))
+    ApiJson(body): ApiJson<LocateRequest>,
+) -> Result<Json<LocateResponse>, ApiError> {
+    let target = match body.mac.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(mac) => Some(
+            devices::parse_mac(mac)
+                .ok_or_else(|| ApiError::bad_request(format!("Invalid MAC address: {mac}")))?,
+        ),
+    };
+    state.event_settings.write().await.locate_target = target;
+    match target {
+        Some(mac) => tracing::info!("Locating {}", devices::format_mac(&mac)),
+        None => tracing::info!("Locator stopped"),
+    }
+    Ok(Json(LocateResponse {
+        target: target.as_ref().map(devices::format_mac),
+    }))
 }
 
-#[derive(Deserialize)]
-struct ShutdownRequest {
-    confirm: bool,
+#[derive(Deserialize, ToSchema)]
+struct DeviceAliasRequest {
+    mac: String,
+    /// Null or empty clears the alias.
+    name: Option<String>,
 }
 
-async fn shutdown(
-    State(_state): State<AppState>,
-    Json(body): Json<ShutdownRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    if !body.confirm {
-        return Err((StatusCode::BAD_REQUEST, "Confirmation required".into()));
-    }
+#[derive(Serialize, ToSchema)]
+struct DeviceAliasResponse {
+    mac: String,
+    name: Option<String>,
+    /// Whether the alias was written to `DEVICE_ALIASES`.
+    persisted: bool,
+}
 
-    tokio::task::spawn_blocking(run_shutdown)
+/// Names a MAC for the device listings, saving it when `DEVICE_ALIASES` is set.
+#[utoipa::path(
+    post,
+    path = "/api/device-alias",
+    request_body = DeviceAliasRequest,
+    responses(
+        (status = 200, body = DeviceAliasResponse),
+        (status = 400, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn device_alias(
+    State(state): State<AppState>,
+    ApiJson(body): ApiJson<DeviceAliasRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mac = devices::parse_mac(&body.mac)
+        .ok_or_else(|| ApiError::bad_request(format!("Invalid MAC {:?}", body.mac)))?;
+    let name = body
+        .name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+    if let Some(name) = &name {
+        if name.chars().count() > devices::MAX_ALIAS_LEN {
+            return Err(ApiError::bad_request(format!(
+                "Alias is longer than {} characters",
+                devices::MAX_ALIAS_LEN
+            )));
+        }
+        if name.chars().any(char::is_control) {
+            return Err(ApiError::bad_request("Alias contains control characters"));
+        }
+    }
+    if !state.device_tracker.set_alias(mac, name.clone()) {
+        return Err(ApiError::bad_request(format!(
+            "At most {} devices can have an alias",
+            devices::MAX_ALIASES
+        )));
+    }
+    let persisted = match state.config.device_aliases.clone() {
+        Some(path) => {
+            let tracker = state.device_tracker.clone();
+            tokio::task::spawn_blocking(move || tracker.save_aliases(std::path::Path::new(&path)))
+                .await
+                .map_err(|err| ApiError::internal(err.to_string()))?
+                .map_err(|err| ApiError::internal(format!("Alias not saved: {err:#}")))?;
+            true
+        }
+        None => false,
+    };
+    Ok(Json(DeviceAliasResponse {
+        mac: devices::format_mac(&mac),
+        name,
+        persisted,
+    }))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct FocusSsidRequest {
+    ssid: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct FocusSsidResponse {
+    ssid: Option<String>,
+    /// BSSIDs advertising the SSID so far; more join as they are heard.
+    bssids: Vec<String>,
+}
+
+/// Allows only `ssid`'s access points and their clients; a null or empty
+/// `ssid` returns to normal filtering.
+#[utoipa::path(
+    post,
+    path = "/api/focus-ssid",
+    request_body = FocusSsidRequest,
+    responses((status = 200, body = FocusSsidResponse))
+)]
+async fn focus_ssid(
+    State(state): State<AppState>,
+    ApiJson(body): ApiJson<FocusSsidRequest>,
+) -> Json<FocusSsidResponse> {
+    let ssid = body.ssid.filter(|name| !name.is_empty());
+    state.device_tracker.set_focus_ssid(ssid.clone());
+    let bssids = match &ssid {
+        Some(name) => {
+            let bssids = state.device_tracker.bssids_for_ssid(name);
+            tracing::info!("Focusing on {name:?} ({} BSSIDs so far)", bssids.len());
+            bssids.iter().map(devices::format_mac).collect()
+        }
+        None => {
+            tracing::info!("SSID focus cleared");
+            Vec::new()
+        }
+    };
+    Json(FocusSsidResponse { ssid, bssids })
+}
+
+#[derive(Serialize, ToSchema)]
+struct SessionsResponse {
+    recording: bool,
+    sessions: Vec<record::SessionRange>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/sessions",
+    responses((status = 200, body = SessionsResponse), (status = 500, body = ApiError))
+)]
+async fn sessions(State(state): State<AppState>) -> Result<Json<SessionsResponse>, ApiError> {
+    let Some(recorder) = state.recorder.as_ref() else {
+        return Ok(Json(SessionsResponse {
+            recording: false,
+            sessions: Vec::new(),
+        }));
+    };
+    let path = recorder.path().to_path_buf();
+    let sessions = tokio::task::spawn_blocking(move || record::list_sessions(&path))
         .await
-        .map_err(|err| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Task join error: {err}"),
-            )
-        })?
-        .map_err(|err| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Shutdown failed: {err}"),
-            )
-        })?;
+        .map_err(|err| ApiError::internal(format!("Task join error: {err}")))?
+        .map_err(|err| ApiError::internal(format!("Failed to list sessions: {err:?}")))?;
+    Ok(Json(SessionsResponse {
+        recording: true,
+        sessions,
+    }))
+}
+
+async fn list_capture_devices() -> Result<Vec<pcap::Device>, ApiError> {
+    tokio::task::spawn_blocking(pcap::Device::list)
+        .await
+        .map_err(|err| ApiError::internal(format!("Task join error: {err}")))?
+        .map_err(|err| ApiError::internal(format!("Failed to list capture interfaces: {err}")))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct InterfaceRequest {
+    interface: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct InterfaceResponse {
+    interface: String,
+    channel: Option<u16>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/interface",
+    request_body = InterfaceRequest,
+    responses(
+        (status = 200, body = InterfaceResponse),
+        (status = 400, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn set_interface(
+    State(state): State<AppState>,
+    ApiJson(body): ApiJson<InterfaceRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let interface = body.interface.trim().to_string();
+    let known = list_capture_devices()
+        .await?
+        .iter()
+        .any(|device| device.name == interface);
+    if !known {
+        return Err(ApiError::bad_request(format!(
+            "Unknown capture interface: {interface}"
+        )));
+    }
+
+    state.sniffer.stop().await;
+    state.channel.set_interface(interface.clone()).await;
+    state.sniffer.start(interface.clone());
+    let channel = match state.channel.refresh_current().await {
+        Ok(channel) => channel,
+        Err(err) => {
+            tracing::warn!("Unable to read channel for {interface}: {err:?}");
+            None
+        }
+    };
+    spawn_channel_detection(&state, interface.clone());
+
+    tracing::info!("Monitor interface switched to {interface}");
+    Ok(Json(InterfaceResponse { interface, channel }))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct ChannelRequest {
+    channel: u16,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ChannelResponse {
+    channel: u16,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/channel",
+    request_body = ChannelRequest,
+    responses((status = 200, body = ChannelResponse), (status = 500, body = ApiError))
+)]
+async fn set_channel(
+    State(state): State<AppState>,
+    ApiJson(body): ApiJson<ChannelRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let channel = state
+        .channel
+        .set_channel(body.channel)
+        .await
+        .map_err(|err| ApiError::internal(format!("Failed to set channel: {err}")))?;
+
+    tracing::info!("Monitor interface set to channel {channel}");
+    Ok(Json(ChannelResponse { channel }))
+}
+
+/// Whether `iw set channel` can tune to `channel`, judged from the detected
+/// 2.4 and 5 GHz lists (6 GHz numbers overlap and need a frequency to set).
+/// `None` until detection has filled them.
+fn channel_supported(
+    channels_24: &[ChannelInfo],
+    channels_5: &[ChannelInfo],
+    channel: u16,
+) -> Option<bool> {
+    if channels_24.is_empty() && channels_5.is_empty() {
+        return None;
+    }
+    Some(
+        channels_24
+            .iter()
+            .chain(channels_5)
+            .any(|info| info.channel == channel && info.enabled),
+    )
+}
+
+#[derive(Deserialize, ToSchema)]
+struct ChannelPresetRequest {
+    /// Position in `CHANNEL_PRESETS`, from 0.
+    index: usize,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/channel/preset",
+    request_body = ChannelPresetRequest,
+    responses(
+        (status = 200, body = ChannelResponse),
+        (status = 400, description = "The radio does not support the preset's channel", body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn set_channel_preset(
+    State(state): State<AppState>,
+    ApiJson(body): ApiJson<ChannelPresetRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let channel = tune_preset(&state, body.index, apply_channel).await?;
+    tracing::info!(
+        "Monitor interface set to preset {} (channel {channel})",
+        body.index
+    );
+    Ok(Json(ChannelResponse { channel }))
+}
+
+/// Tunes to the channel preset `index` names, retuning the radio with `apply`.
+async fn tune_preset(
+    state: &AppState,
+    index: usize,
+    apply: impl AsyncFnOnce(&str, u16) -> Result<()>,
+) -> Result<u16, ApiError> {
+    let channel = preset_channel(state, index).await?;
+    state
+        .channel
+        .set_channel_with(channel, apply)
+        .await
+        .map_err(|err| ApiError::internal(format!("Failed to set channel: {err}")))
+}
+
+/// The channel preset `index` names, if the radio can tune to it.
+async fn preset_channel(state: &AppState, index: usize) -> Result<u16, ApiError> {
+    let channel = *state.config.channel_presets.get(index).ok_or_else(|| {
+        ApiError::new(StatusCode::NOT_FOUND, format!("No channel preset {index}"))
+    })?;
+    let supported = channel_supported(
+        &state.channels_24.read().await,
+        &state.channels_5.read().await,
+        channel,
+    );
+    if supported == Some(false) {
+        return Err(ApiError::bad_request(format!(
+            "Channel {channel} is not supported by the monitor interface"
+        )));
+    }
+    Ok(channel)
+}
+
+#[derive(Serialize, ToSchema)]
+struct LiveChannelResponse {
+    channel: Option<u16>,
+}
+
+/// Reads the channel from the radio rather than the cache, so changes the
+/// driver made on its own show up.
+#[utoipa::path(
+    get,
+    path = "/api/channel",
+    responses((status = 200, body = LiveChannelResponse), (status = 500, body = ApiError))
+)]
+async fn live_channel(State(state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
+    let channel = state
+        .channel
+        .refresh_if_stale()
+        .await
+        .map_err(|err| ApiError::internal(format!("Failed to read channel: {err}")))?;
+    Ok(Json(LiveChannelResponse { channel }))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct UpdateSoundRequest {
+    audio_jack: Option<bool>,
+    web_ui: Option<bool>,
+    volume_by_signal: Option<bool>,
+    /// Replace per-event sounds with a drone whose pitch and timbre follow
+    /// the overall event rate.
+    activity_drone: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct SoundResponse {
+    audio_jack: bool,
+    web_ui_sound: bool,
+    volume_by_signal: bool,
+    activity_drone: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sound",
+    request_body = UpdateSoundRequest,
+    responses((status = 200, body = SoundResponse))
+)]
+async fn update_sound(
+    State(state): State<AppState>,
+    ApiJson(body): ApiJson<UpdateSoundRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if let Some(audio_jack) = body.audio_jack {
+        state.audio_enabled.store(audio_jack, Ordering::Relaxed);
+    }
+    if let Some(web_ui) = body.web_ui {
+        state.web_sound_enabled.store(web_ui, Ordering::Relaxed);
+    }
+    if let Some(v) = body.volume_by_signal {
+        state.volume_by_signal.store(v, Ordering::Relaxed);
+    }
+    if let Some(drone) = body.activity_drone {
+        state.activity_drone.store(drone, Ordering::Relaxed);
+    }
+
+    Ok(Json(SoundResponse {
+        audio_jack: state.audio_enabled.load(Ordering::Relaxed),
+        web_ui_sound: state.web_sound_enabled.load(Ordering::Relaxed),
+        volume_by_signal: state.volume_by_signal.load(Ordering::Relaxed),
+        activity_drone: state.activity_drone.load(Ordering::Relaxed),
+    }))
+}
+
+#[derive(Serialize, ToSchema)]
+struct EventsResponse {
+    mode: NoiseMode,
+    data_tick_n: u32,
+    data_tick_override: Option<u32>,
+    band_filter: Option<Band>,
+    min_rssi_dbm: Option<i8>,
+    data_tick_pitch: bool,
+    suppress_group_data: bool,
+    trend_target: Option<String>,
+    capture_unclassified: bool,
+    ack_hiss_modes: Vec<NoiseMode>,
+    mute_ssids: Vec<String>,
+    burst_on_suppress: bool,
+    merge_assoc_family: bool,
+    on_channel_only: bool,
+    high_priority: Vec<EventKind>,
+    retries_only: bool,
+    new_device_cue: bool,
+    discovery: DiscoveryMode,
+    events: Vec<EventToggle>,
+}
+
+#[utoipa::path(get, path = "/api/events", responses((status = 200, body = EventsResponse)))]
+async fn events_settings(State(state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
+    let settings = state.event_settings.read().await.clone();
+    Ok(Json(build_events_response(
+        &settings,
+        state.sniffer.discovery_mode(),
+    )))
+}
+
+#[derive(Serialize, ToSchema)]
+struct TestEventsResponse {
+    /// Kinds auditioned, in playback order.
+    kinds: Vec<EventKind>,
+    duration_ms: u64,
+}
+
+/// Spacing between auditioned sounds in `/api/events/test`.
+const TEST_EVENT_SPACING: Duration = Duration::from_millis(300);
+
+/// Plays the sound of every enabled event kind once, in toggle order, on
+/// the server's own audio output.
+#[utoipa::path(
+    post,
+    path = "/api/events/test",
+    responses(
+        (status = 200, body = TestEventsResponse),
+        (status = 409, description = "Audio output is switched off", body = ApiError)
+    )
+)]
+async fn test_events(State(state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
+    if !state.audio_enabled.load(Ordering::Relaxed) {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "Audio output is switched off",
+        ));
+    }
+    let settings = state.event_settings.read().await.clone();
+    let kinds: Vec<EventKind> = all_event_toggles(&settings)
+        .into_iter()
+        .filter(|toggle| toggle.enabled)
+        .map(|toggle| toggle.id)
+        .collect();
+    let sounds: Vec<_> = kinds
+        .iter()
+        .map(|kind| sound_for(&PacketEvent::synthetic(kind.clone())))
+        .collect();
+    let duration = state.audio.play_sequence(&sounds, TEST_EVENT_SPACING);
+    Ok(Json(TestEventsResponse {
+        kinds,
+        duration_ms: duration.as_millis() as u64,
+    }))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PaletteWavQuery {
+    /// A sound name such as `deauth-zap`, or an event kind such as `deauth`
+    /// for the sound that kind plays.
+    sound: String,
+}
+
+/// Downloads one synthesized sound exactly as the palette holds it.
+#[utoipa::path(
+    get,
+    path = "/api/palette.wav",
+    params(PaletteWavQuery),
+    responses(
+        (status = 200, content_type = "audio/wav", body = Vec<u8>),
+        (status = 400, body = ApiError)
+    )
+)]
+async fn palette_wav(
+    State(state): State<AppState>,
+    Query(params): Query<PaletteWavQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let id = SoundId::parse(&params.sound)
+        .or_else(|| {
+            serde_json::from_value::<EventKind>(serde_json::Value::String(params.sound.clone()))
+                .ok()
+                .map(|kind| crate::sound_for(&PacketEvent::synthetic(kind)))
+        })
+        .ok_or_else(|| ApiError::bad_request(format!("Unknown sound {:?}", params.sound)))?;
+    let wav = state
+        .audio
+        .sound_wav(id)
+        .map_err(|err| ApiError::internal(format!("{err:#}")))?;
+    let disposition = format!("attachment; filename=\"{}.wav\"", id.name());
+    Ok((
+        [
+            (header::CONTENT_TYPE, "audio/wav".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        wav,
+    ))
+}
+
+/// Longest pause `/api/audio-pause` accepts.
+const MAX_AUDIO_PAUSE: Duration = Duration::from_secs(300);
+
+#[derive(Deserialize, ToSchema)]
+struct AudioPauseRequest {
+    /// How long to stay silent; 0 resumes immediately.
+    seconds: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+struct AudioPauseResponse {
+    remaining_ms: u64,
+}
+
+/// Mutes the audio output for a while, e.g. while a confirmation dialog is
+/// open, then resumes on its own. The `audio_jack` setting is untouched.
+#[utoipa::path(
+    post,
+    path = "/api/audio-pause",
+    request_body = AudioPauseRequest,
+    responses((status = 200, body = AudioPauseResponse), (status = 400, body = ApiError))
+)]
+async fn audio_pause(
+    State(state): State<AppState>,
+    ApiJson(body): ApiJson<AudioPauseRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let duration = Duration::from_secs(body.seconds);
+    if duration > MAX_AUDIO_PAUSE {
+        return Err(ApiError::bad_request(format!(
+            "Pause is limited to {} seconds",
+            MAX_AUDIO_PAUSE.as_secs()
+        )));
+    }
+    state.audio.pause_for(duration);
+    Ok(Json(AudioPauseResponse {
+        remaining_ms: state.audio.pause_remaining().as_millis() as u64,
+    }))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct UpdateEventsRequest {
+    mode: Option<NoiseMode>,
+    events: Option<Vec<EventToggleInput>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<Band>)]
+    band_filter: Option<Option<Band>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<i8>)]
+    min_rssi_dbm: Option<Option<i8>>,
+    data_tick_pitch: Option<bool>,
+    suppress_group_data: Option<bool>,
+    capture_unclassified: Option<bool>,
+    /// Modes in which ACKs become a background hiss; `[]` turns it off.
+    ack_hiss_modes: Option<Vec<NoiseMode>>,
+    /// SSIDs whose frames are not played; `[]` clears the list.
+    mute_ssids: Option<Vec<String>>,
+    /// Announce runs of rate-limited repeats with a burst sound.
+    burst_on_suppress: Option<bool>,
+    /// `false` pitches reassociation apart from association.
+    merge_assoc_family: Option<bool>,
+    /// Only play frames from the channel the radio is tuned to.
+    on_channel_only: Option<bool>,
+    /// Kinds exempt from the per-window caps; `[]` makes every kind share
+    /// them.
+    high_priority: Option<Vec<EventKind>>,
+    /// Play only a quiet retry indicator, once per retransmitted frame.
+    retries_only: Option<bool>,
+    /// Ping for the first frame of every device not seen before.
+    new_device_cue: Option<bool>,
+    /// Restarts a running capture so the BPF filter follows the mode.
+    discovery: Option<DiscoveryMode>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<u32>, minimum = 1)]
+    data_tick_n: Option<Option<u32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    trend_target: Option<Option<String>>,
+}
+
+/// Distinguishes an absent field (`None`) from an explicit `null`
+/// (`Some(None)`) so optional settings can be cleared.
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize, ToSchema)]
+struct EventToggleInput {
+    id: EventKind,
+    enabled: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/events",
+    request_body = UpdateEventsRequest,
+    responses((status = 200, body = EventsResponse), (status = 400, body = ApiError))
+)]
+async fn update_events(
+    State(state): State<AppState>,
+    ApiJson(body): ApiJson<UpdateEventsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if let Some(Some(0)) = body.data_tick_n {
+        return Err(ApiError::bad_request("data_tick_n must be at least 1"));
+    }
+    // Captured frames only carry a channel number, which can't be told
+    // apart from 2.4/5 GHz ones, so a 6 GHz filter could never match.
+    if let Some(Some(Band::Ghz6)) = body.band_filter {
+        return Err(ApiError::bad_request(
+            "band_filter supports 2.4ghz and 5ghz only",
+        ));
+    }
+    let trend_target = match body.trend_target.as_ref() {
+        Some(Some(mac)) => {
+            Some(Some(devices::parse_mac(mac).ok_or_else(|| {
+                ApiError::bad_request(format!("Invalid MAC address: {mac}"))
+            })?))
+        }
+        Some(None) => Some(None),
+        None => None,
+    };
+    // The spaced tones only depend on which kinds are enabled.
+    let respace = state
+        .config
+        .audio_spaced_tones
+        .filter(|_| body.events.is_some());
+    let updated = {
+        let mut settings = state.event_settings.write().await;
+        if let Some(mode) = body.mode {
+            settings.mode = mode;
+        }
+        if let Some(events) = body.events {
+            for evt in events {
+                settings.enabled.insert(evt.id, evt.enabled);
+            }
+        }
+        if let Some(band) = body.band_filter {
+            settings.band_filter = band;
+        }
+        if let Some(min_dbm) = body.min_rssi_dbm {
+            settings.min_rssi_dbm = min_dbm;
+        }
+        if let Some(pitch) = body.data_tick_pitch {
+            settings.data_tick_pitch = pitch;
+        }
+        if let Some(suppress) = body.suppress_group_data {
+            settings.suppress_group_data = suppress;
+        }
+        if let Some(capture) = body.capture_unclassified {
+            settings.capture_unclassified = capture;
+            state.sniffer.set_capture_unclassified(capture);
+        }
+        if let Some(modes) = body.ack_hiss_modes {
+            settings.ack_hiss_modes = modes;
+        }
+        if let Some(burst) = body.burst_on_suppress {
+            settings.burst_on_suppress = burst;
+        }
+        if let Some(merge) = body.merge_assoc_family {
+            settings.merge_assoc_family = merge;
+        }
+        if let Some(on_channel) = body.on_channel_only {
+            settings.on_channel_only = on_channel;
+        }
+        if let Some(kinds) = body.high_priority {
+            settings.high_priority = kinds;
+        }
+        if let Some(retries_only) = body.retries_only {
+            settings.retries_only = retries_only;
+            state.sniffer.set_retries_only(retries_only);
+        }
+        if let Some(new_device_cue) = body.new_device_cue {
+            settings.new_device_cue = new_device_cue;
+        }
+        if let Some(ssids) = body.mute_ssids {
+            settings.mute_ssids = ssids.into_iter().filter(|ssid| !ssid.is_empty()).collect();
+        }
+        if let Some(data_tick_n) = body.data_tick_n {
+            settings.data_tick_n = data_tick_n;
+        }
+        if let Some(target) = trend_target {
+            settings.trend_target = target;
+        }
+        settings.clone()
+    };
+    if let Some(range) = respace {
+        state
+            .audio
+            .set_spaced_tones(&spaced_tone_groups(&updated), range);
+    }
+    if let Some(mode) = body.discovery
+        && state.sniffer.set_discovery_mode(mode)
+    {
+        tracing::info!("Discovery mode set to {mode:?}");
+        if state.sniffer.is_running() {
+            state.sniffer.start(state.channel.interface().await);
+        }
+    }
+    Ok(Json(build_events_response(
+        &updated,
+        state.sniffer.discovery_mode(),
+    )))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct DeviceFilterRequest {
+    devices: Vec<DeviceToggleInput>,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct DeviceToggleInput {
+    mac: String,
+    allowed: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+struct DeviceFilterResponse {
+    updated: usize,
+}
 
-    tracing::info!("Shutdown requested via UI");
+const MAX_DEVICE_FILTERS: usize = 1024;
+
+#[utoipa::path(
+    post,
+    path = "/api/device-filters",
+    request_body = DeviceFilterRequest,
+    responses(
+        (status = 200, body = DeviceFilterResponse),
+        (status = 400, body = ApiError),
+        (status = 413, body = ApiError)
+    )
+)]
+async fn update_device_filters(
+    State(state): State<AppState>,
+    ApiJson(body): ApiJson<DeviceFilterRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if body.devices.len() > MAX_DEVICE_FILTERS {
+        return Err(ApiError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!(
+                "Too many devices: {} (max {MAX_DEVICE_FILTERS})",
+                body.devices.len()
+            ),
+        ));
+    }
+    let mut parsed = Vec::with_capacity(body.devices.len());
+    for item in body.devices {
+        let mac = devices::parse_mac(&item.mac)
+            .ok_or_else(|| ApiError::bad_request(format!("Invalid MAC address: {}", item.mac)))?;
+        parsed.push((mac, item.allowed));
+    }
+
+    state.device_tracker.set_many(&parsed);
+    Ok(Json(DeviceFilterResponse {
+        updated: parsed.len(),
+    }))
+}
+
+#[derive(Serialize, ToSchema)]
+struct DeviceResetResponse {
+    reset: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/device-reset",
+    responses((status = 200, body = DeviceResetResponse))
+)]
+async fn reset_device_counts(State(state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
+    state.device_tracker.reset_counts();
+    Ok(Json(DeviceResetResponse { reset: true }))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct ResetAllRequest {
+    confirm: bool,
+    /// Keep blocked devices and the SSID focus.
+    #[serde(default)]
+    keep_filters: bool,
+}
+
+/// Clears tracked devices, SSID/channel caches and the audio task's rate
+/// state, for starting a fresh survey.
+#[utoipa::path(
+    post,
+    path = "/api/reset-all",
+    request_body = ResetAllRequest,
+    responses(
+        (status = 200, body = DeviceResetResponse),
+        (status = 400, body = ApiError)
+    )
+)]
+async fn reset_all(
+    State(state): State<AppState>,
+    ApiJson(body): ApiJson<ResetAllRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if !body.confirm {
+        return Err(ApiError::bad_request("Confirmation required"));
+    }
+    state.device_tracker.reset_all(body.keep_filters);
+    state.reset_requested.store(true, Ordering::Relaxed);
+    tracing::info!(
+        "All tracked state reset via API (filters {})",
+        if body.keep_filters { "kept" } else { "cleared" }
+    );
+    Ok(Json(DeviceResetResponse { reset: true }))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct ShutdownRequest {
+    confirm: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/shutdown",
+    request_body = ShutdownRequest,
+    responses(
+        (status = 202, description = "Poweroff started"),
+        (status = 400, body = ApiError),
+        (status = 403, description = "Caller is not loopback or in POWER_ALLOWED_CIDRS", body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn shutdown(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    ApiJson(body): ApiJson<ShutdownRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let audit = PowerAudit::new("shutdown", peer, &headers);
+    if !power_allowed(&state.config, peer) {
+        audit.record(&state.config, "refused").await;
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "Shutdown is only allowed from loopback or POWER_ALLOWED_CIDRS",
+        ));
+    }
+    if !body.confirm {
+        return Err(ApiError::bad_request("Confirmation required"));
+    }
+
+    // Recorded before poweroff starts, or the line may never reach disk.
+    audit.record(&state.config, "accepted").await;
+    let result = tokio::task::spawn_blocking(run_shutdown)
+        .await
+        .map_err(|err| ApiError::internal(format!("Task join error: {err}")))?;
+    if let Err(err) = result {
+        audit.record(&state.config, "failed").await;
+        return Err(ApiError::internal(format!("Shutdown failed: {err}")));
+    }
     Ok(StatusCode::ACCEPTED)
 }
 
+/// Who asked for a power action, logged under the `audit` target and, with
+/// `POWER_AUDIT_LOG`, appended to that file as one JSON object per line.
+struct PowerAudit {
+    action: &'static str,
+    peer: SocketAddr,
+    /// The `x-request-id` the request was traced under.
+    request_id: String,
+}
+
+#[derive(Serialize)]
+struct PowerAuditEntry<'a> {
+    action: &'a str,
+    outcome: &'a str,
+    peer: SocketAddr,
+    request_id: &'a str,
+    unix_ms: u64,
+}
+
+impl PowerAudit {
+    fn new(action: &'static str, peer: SocketAddr, headers: &HeaderMap) -> Self {
+        Self {
+            action,
+            peer,
+            request_id: request_id(headers),
+        }
+    }
+
+    async fn record(&self, config: &AppConfig, outcome: &str) {
+        tracing::warn!(
+            target: "audit",
+            action = self.action,
+            outcome,
+            peer = %self.peer,
+            request_id = %self.request_id,
+            "Power action {} {outcome} for {}",
+            self.action,
+            self.peer
+        );
+        let Some(path) = config.power_audit_log.as_deref() else {
+            return;
+        };
+        let entry = PowerAuditEntry {
+            action: self.action,
+            outcome,
+            peer: self.peer,
+            request_id: &self.request_id,
+            unix_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+        };
+        let Ok(mut line) = serde_json::to_string(&entry) else {
+            return;
+        };
+        line.push('\n');
+        let file = path.to_string();
+        let written = tokio::task::spawn_blocking(move || {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(file)?
+                .write_all(line.as_bytes())
+        })
+        .await;
+        if let Ok(Err(err)) = written {
+            tracing::error!("Unable to append to power audit log {path}: {err}");
+        }
+    }
+}
+
+/// Loopback always passes; other peers must fall in a configured network.
+fn power_allowed(config: &AppConfig, peer: SocketAddr) -> bool {
+    let ip = peer.ip().to_canonical();
+    ip.is_loopback() || config.power_allowed_cidrs.iter().any(|c| c.contains(ip))
+}
+
 fn run_shutdown() -> Result<()> {
     let status = std::process::Command::new("systemctl")
         .arg("poweroff")
@@ -411,77 +1986,347 @@ fn run_shutdown() -> Result<()> {
     }
 }
 
+/// Pushes `DevicesResponse` snapshots; accepts `DeviceSocketControl` messages.
+#[utoipa::path(
+    get,
+    path = "/ws/devices",
+    params(DevicesQuery),
+    responses(
+        (status = 101, description = "WebSocket upgrade", body = DevicesResponse),
+        (status = 403, body = ApiError)
+    )
+)]
 async fn ws_devices(
     State(state): State<AppState>,
     Query(params): Query<DevicesQuery>,
+    headers: HeaderMap,
     ws: WebSocketUpgrade,
-) -> impl IntoResponse {
+) -> Response {
+    if !ws_origin_allowed(&headers, &state.config.allowed_origins) {
+        return ApiError::new(StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+    }
     let window = window_from_query(&params);
-    ws.on_upgrade(move |socket| handle_ws_devices(socket, state, window))
+    let listing = DeviceListing::from_query(&params, &state.config);
+    let id = request_id(&headers);
+    ws.on_upgrade(move |socket| async move {
+        tracing::info!("Device socket {id} opened");
+        handle_ws_devices(socket, state, window, listing).await;
+        tracing::info!("Device socket {id} closed");
+    })
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PacketsQuery {
+    format: Option<String>,
+    /// Comma-separated event kinds to send, e.g. `deauth,eapol`; all when
+    /// absent.
+    kinds: Option<String>,
+}
+
+/// Streams `WsEvent` JSON, or 3-byte binary frames with `?format=bin`.
+/// `?kinds=` limits the stream to the listed kinds.
+#[utoipa::path(
+    get,
+    path = "/ws/packets",
+    params(PacketsQuery),
+    responses(
+        (status = 101, description = "WebSocket upgrade", body = WsEvent),
+        (status = 400, description = "`kinds` names an unknown event kind", body = ApiError),
+        (status = 403, body = ApiError)
+    )
+)]
+async fn ws_packets(
+    State(state): State<AppState>,
+    Query(params): Query<PacketsQuery>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    if !ws_origin_allowed(&headers, &state.config.allowed_origins) {
+        return ApiError::new(StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+    }
+    let binary = params.format.as_deref() == Some("bin");
+    let kinds = match packet_kinds(params.kinds.as_deref()) {
+        Ok(kinds) => kinds,
+        Err(err) => return err.into_response(),
+    };
+    let id = request_id(&headers);
+    ws.on_upgrade(move |socket| async move {
+        tracing::info!("Packet socket {id} opened");
+        handle_ws(socket, state, binary, kinds).await;
+        tracing::info!("Packet socket {id} closed");
+    })
 }
 
-async fn ws_packets(State(state): State<AppState>, ws: WebSocketUpgrade) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_ws(socket, state))
+/// Parses `?kinds=`; `None` when absent or empty, which sends every kind.
+fn packet_kinds(list: Option<&str>) -> Result<Option<HashSet<EventKind>>, ApiError> {
+    let Some(list) = list.map(str::trim).filter(|list| !list.is_empty()) else {
+        return Ok(None);
+    };
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            serde_json::from_value(serde_json::Value::String(name.to_string()))
+                .map_err(|_| ApiError::bad_request(format!("Unknown event kind {name:?}")))
+        })
+        .collect::<Result<HashSet<_>, _>>()
+        .map(Some)
 }
 
-async fn handle_ws(mut socket: WebSocket, state: AppState) {
+async fn handle_ws(
+    mut socket: WebSocket,
+    state: AppState,
+    binary: bool,
+    kinds: Option<HashSet<EventKind>>,
+) {
     let mut rx = state.packet_tx.subscribe();
     while let Ok(evt) = rx.recv().await {
         if !state.web_sound_enabled.load(Ordering::Relaxed) {
             continue;
         }
-        let payload = match serde_json::to_string(&WsEvent {
-            kind: evt.kind,
-            retry: evt.retry,
-            amplitude: evt.amplitude,
-        }) {
-            Ok(s) => s,
-            Err(_) => continue,
+        let Some(message) = packet_message(&evt, binary, kinds.as_ref()) else {
+            continue;
         };
-        if socket.send(Message::Text(payload)).await.is_err() {
+        if socket.send(message).await.is_err() {
             break;
         }
     }
 }
 
-async fn handle_ws_devices(mut socket: WebSocket, state: AppState, window: u64) {
-    let mut interval = time::interval(Duration::from_secs(10));
-    if send_devices_snapshot(&mut socket, &state, window)
-        .await
-        .is_err()
-    {
-        return;
+/// `evt` as a packet socket message, or `None` if `kinds` filters it out.
+fn packet_message(
+    evt: &PacketEvent,
+    binary: bool,
+    kinds: Option<&HashSet<EventKind>>,
+) -> Option<Message> {
+    if kinds.is_some_and(|kinds| !kinds.contains(&evt.kind)) {
+        return None;
     }
+    if binary {
+        Some(Message::Binary(encode_ws_event(evt).to_vec()))
+    } else {
+        serde_json::to_string(&WsEvent::from(evt))
+            .ok()
+            .map(Message::Text)
+    }
+}
+
+const WS_FLAG_RETRY: u8 = 0x01;
+
+/// Compact `?format=bin` encoding: kind code, flags, amplitude quantized to
+/// 0..=255. Kind codes follow the `EventKind` declaration order, which the
+/// browser client mirrors.
+fn encode_ws_event(evt: &PacketEvent) -> [u8; 3] {
+    let kind = match evt.kind {
+        EventKind::Beacon => 0,
+        EventKind::ProbeReq => 1,
+        EventKind::ProbeResp => 2,
+        EventKind::Assoc => 3,
+        EventKind::Deauth => 4,
+        EventKind::Eapol => 5,
+        EventKind::Rts => 6,
+        EventKind::Cts => 7,
+        EventKind::Ack => 8,
+        EventKind::DataTick => 9,
+        EventKind::BeaconFlood => 10,
+        EventKind::Other => 11,
+        EventKind::ProbeRespFlood => 12,
+        EventKind::NullData => 13,
+    };
+    let flags = if evt.retry { WS_FLAG_RETRY } else { 0 };
+    let amplitude = (evt.amplitude.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [kind, flags, amplitude]
+}
+
+#[derive(Serialize, ToSchema)]
+struct MeterLevel {
+    /// RMS of the last output buffer, 0.0 to 1.0.
+    rms: f32,
+    peak: f32,
+}
+
+/// Pushes the audio jack's output level ten times a second.
+#[utoipa::path(
+    get,
+    path = "/ws/meter",
+    responses(
+        (status = 101, description = "WebSocket upgrade", body = MeterLevel),
+        (status = 403, body = ApiError)
+    )
+)]
+async fn ws_meter(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    if !ws_origin_allowed(&headers, &state.config.allowed_origins) {
+        return ApiError::new(StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+    }
+    ws.on_upgrade(move |socket| handle_ws_meter(socket, state.meter))
+}
+
+async fn handle_ws_meter(mut socket: WebSocket, meter: Arc<OutputMeter>) {
+    let mut interval = time::interval(Duration::from_millis(100));
     loop {
-        interval.tick().await;
-        if send_devices_snapshot(&mut socket, &state, window)
-            .await
-            .is_err()
-        {
-            break;
+        tokio::select! {
+            _ = interval.tick() => {
+                let level = MeterLevel {
+                    rms: meter.rms(),
+                    peak: meter.peak(),
+                };
+                let Ok(payload) = serde_json::to_string(&level) else {
+                    continue;
+                };
+                if socket.send(Message::Text(payload)).await.is_err() {
+                    break;
+                }
+            }
+            msg = socket.recv() => {
+                if !matches!(msg, Some(Ok(_))) {
+                    break;
+                }
+            }
         }
     }
 }
 
-async fn send_devices_snapshot(
-    socket: &mut WebSocket,
-    state: &AppState,
+#[derive(Deserialize, ToSchema)]
+struct DeviceSocketControl {
+    freeze: Option<bool>,
+    window_seconds: Option<u64>,
+    window_minutes: Option<u64>,
+}
+
+/// Text-frame view of the device socket, so the push loop can be driven
+/// without a live connection.
+trait DeviceSocket {
+    async fn send_text(&mut self, text: String) -> Result<(), ()>;
+    /// Next text frame from the client, or `None` once it disconnects.
+    async fn recv_text(&mut self) -> Option<String>;
+}
+
+impl DeviceSocket for WebSocket {
+    async fn send_text(&mut self, text: String) -> Result<(), ()> {
+        self.send(Message::Text(text)).await.map_err(|_| ())
+    }
+
+    async fn recv_text(&mut self) -> Option<String> {
+        loop {
+            match self.recv().await {
+                Some(Ok(Message::Text(text))) => return Some(text),
+                Some(Ok(_)) => continue,
+                _ => return None,
+            }
+        }
+    }
+}
+
+async fn handle_ws_devices(
+    mut socket: WebSocket,
+    state: AppState,
     window: u64,
-) -> Result<(), ()> {
-    let snapshot = state.device_tracker.snapshot(Duration::from_secs(window));
-    let payload = serde_json::to_string(&DevicesResponse {
+    listing: DeviceListing,
+) {
+    push_devices(&mut socket, window, |window| {
+        devices_snapshot(&state, window, listing)
+    })
+    .await;
+}
+
+async fn push_devices(
+    socket: &mut impl DeviceSocket,
+    mut window: u64,
+    snapshot: impl Fn(u64) -> Option<String>,
+) {
+    let mut interval = time::interval(Duration::from_secs(10));
+    // While frozen the tracker keeps running; only the pushes pause.
+    let mut frozen = false;
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                if frozen {
+                    continue;
+                }
+                let Some(payload) = snapshot(window) else {
+                    break;
+                };
+                if socket.send_text(payload).await.is_err() {
+                    break;
+                }
+            }
+            text = socket.recv_text() => {
+                let Some(text) = text else {
+                    break;
+                };
+                let Ok(control) = serde_json::from_str::<DeviceSocketControl>(&text) else {
+                    continue;
+                };
+                let mut changed = false;
+                if let Some(freeze) = control.freeze {
+                    changed = frozen && !freeze;
+                    frozen = freeze;
+                }
+                if control.window_seconds.is_some() || control.window_minutes.is_some() {
+                    window = window_from_query(&DevicesQuery {
+                        window_seconds: control.window_seconds,
+                        window_minutes: control.window_minutes,
+                        min_frames: None,
+                        filter: None,
+                        group_by: None,
+                    });
+                    changed = true;
+                }
+                if !changed || frozen {
+                    continue;
+                }
+                let Some(payload) = snapshot(window) else {
+                    break;
+                };
+                if socket.send_text(payload).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn devices_snapshot(state: &AppState, window: u64, listing: DeviceListing) -> Option<String> {
+    let snapshot = listing.snapshot(&state.device_tracker, window);
+    serde_json::to_string(&DevicesResponse {
         window_seconds: window,
         devices: snapshot,
     })
-    .map_err(|_| ())?;
-    socket.send(Message::Text(payload)).await.map_err(|_| ())
+    .ok()
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct WsEvent {
     kind: EventKind,
     retry: bool,
     amplitude: f32,
+    channel: Option<u16>,
+    signal_dbm: Option<i8>,
+    seq: Option<u16>,
+    role: DeviceRole,
+    /// Raw frame control, only on `other` events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fc: Option<u16>,
+}
+
+impl From<&PacketEvent> for WsEvent {
+    fn from(evt: &PacketEvent) -> Self {
+        Self {
+            kind: evt.kind.clone(),
+            retry: evt.retry,
+            amplitude: evt.amplitude,
+            channel: evt.channel,
+            signal_dbm: evt.signal_dbm,
+            seq: evt.seq,
+            role: evt.role,
+            fc: (evt.kind == EventKind::Other).then_some(evt.fc),
+        }
+    }
 }
 
 async fn current_channel(interface: &str) -> Result<Option<u16>> {
@@ -496,18 +2341,66 @@ async fn current_channel(interface: &str) -> Result<Option<u16>> {
         anyhow::bail!("iw dev {interface} info failed: {stderr}");
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    for line in stdout.lines() {
-        let line = line.trim_start();
-        if let Some(rest) = line.strip_prefix("channel ") {
-            if let Some(token) = rest.split_whitespace().next() {
-                if let Ok(channel) = token.parse::<u16>() {
-                    return Ok(Some(channel));
-                }
-            }
+    Ok(iw_channel(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The channel to cache after an `iw` read: a channel that was read
+/// replaces `known`, anything else keeps it. The read error only surfaces
+/// while nothing is known.
+fn keep_last_channel(
+    known: Option<u16>,
+    read: Result<Option<u16>>,
+    interface: &str,
+) -> Result<Option<u16>> {
+    match (read, known) {
+        (Ok(Some(channel)), _) => Ok(Some(channel)),
+        (Ok(None), Some(known)) => {
+            tracing::warn!("No channel in `iw dev {interface} info`; keeping {known}");
+            Ok(Some(known))
         }
+        (Ok(None), None) => Ok(None),
+        (Err(err), Some(known)) => {
+            tracing::warn!("Keeping channel {known} on {interface}: {err:#}");
+            Ok(Some(known))
+        }
+        (Err(err), None) => Err(err),
+    }
+}
+
+/// The `channel` line of `iw dev <if> info`, e.g. `channel 36 (5180 MHz)`.
+fn iw_channel(info: &str) -> Option<u16> {
+    info.lines().find_map(|line| {
+        line.trim_start()
+            .strip_prefix("channel ")?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()
+    })
+}
+
+async fn interface_mode(interface: &str) -> Result<Option<String>> {
+    let output = Command::new("iw")
+        .args(["dev", interface, "info"])
+        .output()
+        .await
+        .with_context(|| format!("Failed to read mode for {interface}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("iw dev {interface} info failed: {stderr}");
     }
-    Ok(None)
+
+    Ok(iw_interface_type(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The `type` line of `iw dev <if> info`, e.g. `monitor` or `managed`.
+fn iw_interface_type(info: &str) -> Option<String> {
+    info.lines().find_map(|line| {
+        line.trim_start()
+            .strip_prefix("type ")
+            .map(|mode| mode.trim().to_string())
+    })
 }
 
 async fn apply_channel(interface: &str, channel: u16) -> Result<()> {
@@ -526,9 +2419,13 @@ async fn apply_channel(interface: &str, channel: u16) -> Result<()> {
     }
 }
 
-async fn detect_supported_channels(
-    interface: &str,
-) -> Result<(Vec<ChannelInfo>, Vec<ChannelInfo>)> {
+struct SupportedChannels {
+    ghz24: Vec<ChannelInfo>,
+    ghz5: Vec<ChannelInfo>,
+    ghz6: Vec<ChannelInfo>,
+}
+
+async fn detect_supported_channels(interface: &str) -> Result<SupportedChannels> {
     let phy = detect_phy(interface).await?;
     let output = Command::new("iw")
         .args(["phy", &phy, "info"])
@@ -541,11 +2438,16 @@ async fn detect_supported_channels(
         anyhow::bail!("iw phy info failed: {stderr}");
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut channels_24: BTreeMap<u16, bool> = BTreeMap::new();
-    let mut channels_5: BTreeMap<u16, bool> = BTreeMap::new();
+    Ok(parse_supported_channels(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
 
-    for line in stdout.lines() {
+/// Splits the frequency list of `iw phy <phy> info` into bands.
+fn parse_supported_channels(info: &str) -> SupportedChannels {
+    let mut channels: BTreeMap<u16, ChannelInfo> = BTreeMap::new();
+
+    for line in info.lines() {
         let line = line.trim_start();
         if !line.starts_with('*') {
             continue;
@@ -565,51 +2467,44 @@ async fn detect_supported_channels(
                 .and_then(|end| line[start + 1..start + 1 + end].trim().parse::<u16>().ok())
         });
 
-        if let (Some(freq), Some(ch)) = (freq_mhz, channel_num) {
-            if freq < 3000.0 {
-                channels_24.insert(ch, !is_disabled);
-            } else if freq < 6000.0 {
-                channels_5.insert(ch, !is_disabled);
-            }
+        // Keyed by frequency: the same channel number recurs per band.
+        if let (Some(freq), Some(ch)) = (freq_mhz, channel_num)
+            && let Some(band) = Band::for_frequency(freq as u16)
+        {
+            channels.insert(
+                freq as u16,
+                ChannelInfo {
+                    channel: ch,
+                    enabled: !is_disabled,
+                    frequency_mhz: freq as u16,
+                    band,
+                },
+            );
         }
     }
 
-    let c24 = if channels_24.is_empty() {
-        (1..=14)
-            .map(|ch| ChannelInfo {
-                channel: ch,
-                enabled: false,
-            })
-            .collect()
-    } else {
-        channels_24
-            .into_iter()
-            .map(|(ch, enabled)| ChannelInfo {
-                channel: ch,
-                enabled,
-            })
-            .collect()
-    };
-    let c5 = if channels_5.is_empty() {
-        vec![]
-    } else {
-        channels_5
-            .into_iter()
-            .map(|(ch, enabled)| ChannelInfo {
-                channel: ch,
-                enabled,
-            })
+    let in_band = |band: Band| -> Vec<ChannelInfo> {
+        channels
+            .values()
+            .filter(|info| info.band == band)
+            .cloned()
             .collect()
     };
-    Ok((c24, c5))
+    let mut ghz24 = in_band(Band::Ghz24);
+    if ghz24.is_empty() {
+        ghz24 = (1..=14)
+            .map(|ch| ChannelInfo::new(Band::Ghz24, ch, false))
+            .collect();
+    }
+    SupportedChannels {
+        ghz24,
+        ghz5: in_band(Band::Ghz5),
+        ghz6: in_band(Band::Ghz6),
+    }
 }
 
 fn all_event_toggles(settings: &EventSettings) -> Vec<EventToggle> {
-    use EventKind::*;
-    let order = vec![
-        Beacon, ProbeReq, ProbeResp, Assoc, Deauth, Eapol, Rts, Cts, Ack, DataTick,
-    ];
-    order
+    EventKind::ALL
         .into_iter()
         .map(|id| EventToggle {
             label: event_label(&id),
@@ -631,20 +2526,33 @@ fn event_label(kind: &EventKind) -> &'static str {
         EventKind::Cts => "CTS",
         EventKind::Ack => "ACK / Block ACK",
         EventKind::DataTick => "Data tick",
+        EventKind::BeaconFlood => "Beacon flood alert",
+        EventKind::Other => "Unclassified frames",
+        EventKind::ProbeRespFlood => "Probe-response flood alert",
+        EventKind::NullData => "Null / QoS-Null (keepalive)",
     }
 }
 
-fn data_tick_for(mode: &NoiseMode) -> u32 {
-    match mode {
-        NoiseMode::Crowded => 100,
-        NoiseMode::Sparse => 10,
-    }
-}
-
-fn build_events_response(settings: &EventSettings) -> EventsResponse {
+fn build_events_response(settings: &EventSettings, discovery: DiscoveryMode) -> EventsResponse {
     EventsResponse {
         mode: settings.mode.clone(),
-        data_tick_n: data_tick_for(&settings.mode),
+        data_tick_n: settings.data_tick_n(),
+        data_tick_override: settings.data_tick_n,
+        band_filter: settings.band_filter,
+        min_rssi_dbm: settings.min_rssi_dbm,
+        data_tick_pitch: settings.data_tick_pitch,
+        suppress_group_data: settings.suppress_group_data,
+        trend_target: settings.trend_target.as_ref().map(devices::format_mac),
+        capture_unclassified: settings.capture_unclassified,
+        ack_hiss_modes: settings.ack_hiss_modes.clone(),
+        mute_ssids: settings.mute_ssids.clone(),
+        burst_on_suppress: settings.burst_on_suppress,
+        merge_assoc_family: settings.merge_assoc_family,
+        on_channel_only: settings.on_channel_only,
+        high_priority: settings.high_priority.clone(),
+        retries_only: settings.retries_only,
+        new_device_cue: settings.new_device_cue,
+        discovery,
         events: all_event_toggles(settings),
     }
 }
@@ -671,3 +2579,1060 @@ async fn detect_phy(interface: &str) -> Result<String> {
 
     anyhow::bail!("Could not find wiphy for interface {interface}")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::IpCidr;
+    use crate::devices::{DeviceRole, Observation};
+    use crate::events::RateKey;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::sync::mpsc;
+    use tower::ServiceExt;
+
+    #[test]
+    fn interfaces_are_annotated_with_mode_and_activity() {
+        let info = "Interface wlan1mon\n\tifindex 5\n\ttype monitor\n\tchannel 6 (2437 MHz)\n";
+        let mode = iw_interface_type(info);
+        assert_eq!(mode.as_deref(), Some("monitor"));
+        let active = InterfaceInfo::new("wlan1mon".into(), None, mode, "wlan1mon");
+        assert!(active.monitor && active.active);
+
+        let managed = InterfaceInfo::new(
+            "wlan0".into(),
+            Some("Built-in".into()),
+            iw_interface_type("\ttype managed\n"),
+            "wlan1mon",
+        );
+        assert_eq!(managed.mode.as_deref(), Some("managed"));
+        assert!(!managed.monitor && !managed.active);
+
+        let wired = InterfaceInfo::new("eth0".into(), None, None, "wlan1mon");
+        assert!(wired.mode.is_none() && !wired.monitor);
+    }
+
+    /// Device socket backed by channels: the test plays the client.
+    struct FakeSocket {
+        incoming: mpsc::UnboundedReceiver<String>,
+        outgoing: mpsc::UnboundedSender<String>,
+    }
+
+    impl DeviceSocket for FakeSocket {
+        async fn send_text(&mut self, text: String) -> Result<(), ()> {
+            self.outgoing.send(text).map_err(|_| ())
+        }
+
+        async fn recv_text(&mut self) -> Option<String> {
+            self.incoming.recv().await
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn frozen_device_socket_pauses_snapshots_until_unfrozen() {
+        let (client_tx, incoming) = mpsc::unbounded_channel();
+        let (outgoing, mut client_rx) = mpsc::unbounded_channel();
+        let pushes = Arc::new(AtomicUsize::new(0));
+        let counter = pushes.clone();
+        tokio::spawn(async move {
+            let mut socket = FakeSocket { incoming, outgoing };
+            push_devices(&mut socket, 60, |_| {
+                Some(counter.fetch_add(1, Ordering::Relaxed).to_string())
+            })
+            .await;
+        });
+
+        assert_eq!(client_rx.recv().await.as_deref(), Some("0"));
+        client_tx.send(r#"{"freeze":true}"#.into()).unwrap();
+        time::sleep(Duration::from_secs(35)).await;
+        assert!(client_rx.try_recv().is_err());
+
+        client_tx.send(r#"{"freeze":false}"#.into()).unwrap();
+        assert_eq!(client_rx.recv().await.as_deref(), Some("1"));
+        time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(client_rx.recv().await.as_deref(), Some("2"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn window_change_applies_mid_stream() {
+        let (client_tx, incoming) = mpsc::unbounded_channel();
+        let (outgoing, mut client_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut socket = FakeSocket { incoming, outgoing };
+            push_devices(&mut socket, 600, |window| Some(window.to_string())).await;
+        });
+
+        assert_eq!(client_rx.recv().await.as_deref(), Some("600"));
+        client_tx.send(r#"{"window_minutes":5}"#.into()).unwrap();
+        assert_eq!(client_rx.recv().await.as_deref(), Some("300"));
+        client_tx.send(r#"{"window_minutes":1000}"#.into()).unwrap();
+        assert_eq!(client_rx.recv().await.as_deref(), Some("7200"));
+        time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(client_rx.recv().await.as_deref(), Some("7200"));
+    }
+
+    fn test_state(config: AppConfig) -> AppState {
+        let (sniffer_tx, _) = mpsc::unbounded_channel();
+        let device_tracker = Arc::new(DeviceTracker::new(0, None, 0));
+        AppState {
+            audio: AudioHandle::new(8000, false, &config),
+            config: Arc::new(config),
+            audio_enabled: Arc::new(AtomicBool::new(true)),
+            web_sound_enabled: Arc::new(AtomicBool::new(true)),
+            volume_by_signal: Arc::new(AtomicBool::new(false)),
+            activity_drone: Arc::new(AtomicBool::new(false)),
+            packet_tx: broadcast::channel(16).0,
+            channel: ChannelController::new("wlan1mon".into()),
+            channels_24: Arc::new(RwLock::new(Vec::new())),
+            channels_5: Arc::new(RwLock::new(Vec::new())),
+            channels_6: Arc::new(RwLock::new(Vec::new())),
+            event_settings: Arc::new(RwLock::new(EventSettings::default())),
+            sniffer: SnifferController::new(
+                sniffer_tx,
+                device_tracker.clone(),
+                DiscoveryMode::Full,
+                0,
+                Duration::from_secs(1),
+                None,
+            )
+            .silent(),
+            device_tracker,
+            recorder: None,
+            paused_sound: Arc::new(std::sync::Mutex::new(None)),
+            meter: Arc::new(crate::audio::OutputMeter::default()),
+            reset_requested: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    async fn json_body(response: impl IntoResponse) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_response().into_body(), usize::MAX)
+            .await
+            .expect("body reads");
+        serde_json::from_slice(&body).expect("body is JSON")
+    }
+
+    #[tokio::test]
+    async fn settings_include_reconnect_config() {
+        let mut config = AppConfig::for_tests();
+        config.ws_packets_reconnect_ms = 4000;
+        config.ws_devices_reconnect_ms = 5000;
+        config.ws_reconnect_max_ms = 60_000;
+        let Ok(response) = settings(State(test_state(config))).await else {
+            panic!("settings failed");
+        };
+        let body = json_body(response).await;
+        assert_eq!(
+            body["reconnect"],
+            serde_json::json!({"packets_ms": 4000, "devices_ms": 5000, "max_ms": 60000})
+        );
+    }
+
+    fn packet_event(kind: EventKind) -> PacketEvent {
+        PacketEvent {
+            kind,
+            rate_key: RateKey::None,
+            retry: false,
+            new_device: false,
+            amplitude: 1.0,
+            src: None,
+            receiver: None,
+            bssid: None,
+            channel: None,
+            signal_dbm: None,
+            eapol_msg: None,
+            group_addressed: false,
+            seq: None,
+            fc: 0,
+            role: DeviceRole::Unknown,
+        }
+    }
+
+    #[test]
+    fn packet_sockets_send_only_the_requested_kinds() {
+        assert_eq!(packet_kinds(None).unwrap(), None);
+        assert_eq!(packet_kinds(Some(" ")).unwrap(), None);
+        for list in ["deauth,bogus", "bogus"] {
+            let Err(err) = packet_kinds(Some(list)) else {
+                panic!("{list:?} was accepted");
+            };
+            assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        }
+
+        let kinds = packet_kinds(Some("deauth, eapol")).unwrap();
+        let sent: Vec<EventKind> = [
+            EventKind::Beacon,
+            EventKind::Deauth,
+            EventKind::Ack,
+            EventKind::Eapol,
+        ]
+        .into_iter()
+        .filter(|kind| packet_message(&packet_event(kind.clone()), false, kinds.as_ref()).is_some())
+        .collect();
+        assert_eq!(sent, vec![EventKind::Deauth, EventKind::Eapol]);
+        assert!(packet_message(&packet_event(EventKind::Beacon), true, None).is_some());
+    }
+
+    #[test]
+    fn binary_ws_events_round_trip_through_the_client_table() {
+        // The browser decodes with this table; see BIN_KINDS in ui.rs.
+        let client_kinds = [
+            "beacon",
+            "probe-req",
+            "probe-resp",
+            "assoc",
+            "deauth",
+            "eapol",
+            "rts",
+            "cts",
+            "ack",
+            "data-tick",
+            "beacon-flood",
+            "other",
+            "probe-resp-flood",
+            "null-data",
+        ];
+        for toggle in all_event_toggles(&EventSettings::default()) {
+            let kind = toggle.id;
+            let mut evt = packet_event(kind.clone());
+            evt.retry = true;
+            evt.amplitude = 0.5;
+            let [code, flags, amplitude] = encode_ws_event(&evt);
+            assert_eq!(
+                serde_json::json!(client_kinds[code as usize]),
+                serde_json::to_value(kind).unwrap()
+            );
+            assert_eq!(flags & WS_FLAG_RETRY, WS_FLAG_RETRY);
+            assert!((amplitude as f32 / 255.0 - 0.5).abs() < 1.0 / 255.0);
+        }
+
+        let mut evt = packet_event(EventKind::Deauth);
+        evt.amplitude = 2.0;
+        assert_eq!(encode_ws_event(&evt), [4, 0, 255]);
+        evt.amplitude = -1.0;
+        assert_eq!(encode_ws_event(&evt), [4, 0, 0]);
+    }
+
+    fn post_json(uri: &str, body: String) -> axum::http::Request<axum::body::Body> {
+        axum::http::Request::post(uri)
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(body))
+            .unwrap()
+    }
+
+    fn device_filters_body(count: usize) -> String {
+        let devices: Vec<_> = (0..count)
+            .map(|i| {
+                let mac = format!("02:00:00:00:{:02x}:{:02x}", i / 256, i % 256);
+                serde_json::json!({ "mac": mac, "allowed": false })
+            })
+            .collect();
+        serde_json::json!({ "devices": devices }).to_string()
+    }
+
+    #[tokio::test]
+    async fn oversized_bodies_are_rejected() {
+        let mut config = AppConfig::for_tests();
+        config.http_body_limit = 1024;
+        let response = router(test_state(config))
+            .oneshot(post_json("/api/device-filters", device_filters_body(100)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn device_filter_lists_are_capped() {
+        let mut config = AppConfig::for_tests();
+        config.http_body_limit = 1 << 20;
+        let app = router(test_state(config));
+        let response = app
+            .clone()
+            .oneshot(post_json(
+                "/api/device-filters",
+                device_filters_body(MAX_DEVICE_FILTERS + 1),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let response = app
+            .oneshot(post_json("/api/device-filters", device_filters_body(2)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    fn preflight(origin: &str) -> axum::http::Request<axum::body::Body> {
+        axum::http::Request::options("/api/events")
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+            .body(axum::body::Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn preflight_admits_only_allowed_origins() {
+        let mut config = AppConfig::for_tests();
+        config.allowed_origins = vec!["http://dash.local:3000".into()];
+        let app = router(test_state(config));
+
+        let allowed = app
+            .clone()
+            .oneshot(preflight("http://dash.local:3000"))
+            .await
+            .unwrap();
+        assert_eq!(
+            allowed.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&HeaderValue::from_static("http://dash.local:3000"))
+        );
+
+        let denied = app.oneshot(preflight("http://evil.example")).await.unwrap();
+        assert!(
+            denied
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn ws_origin_check_honours_the_allowlist() {
+        let headers = |origin: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::HOST, HeaderValue::from_static("radioscope:8080"));
+            headers.insert(header::ORIGIN, HeaderValue::from_str(origin).unwrap());
+            headers
+        };
+        let allowed = vec!["http://dash.local:3000".to_string()];
+        assert!(ws_origin_allowed(&headers("http://radioscope:8080"), &[]));
+        assert!(ws_origin_allowed(
+            &headers("http://dash.local:3000"),
+            &allowed
+        ));
+        assert!(!ws_origin_allowed(
+            &headers("http://evil.example"),
+            &allowed
+        ));
+        assert!(ws_origin_allowed(&HeaderMap::new(), &allowed));
+    }
+
+    #[tokio::test]
+    async fn data_tick_override_is_reported_and_applied() {
+        let state = test_state(AppConfig::for_tests());
+        let app = router(state.clone());
+
+        let response = app
+            .clone()
+            .oneshot(post_json("/api/events", r#"{"data_tick_n":50}"#.into()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json_body(response).await;
+        assert_eq!(body["data_tick_n"], 50);
+        assert_eq!(body["data_tick_override"], 50);
+        assert_eq!(state.event_settings.read().await.data_tick_n(), 50);
+
+        let response = app
+            .clone()
+            .oneshot(post_json("/api/events", r#"{"data_tick_n":0}"#.into()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .oneshot(post_json("/api/events", r#"{"data_tick_n":null}"#.into()))
+            .await
+            .unwrap();
+        let body = json_body(response).await;
+        assert_eq!(body["data_tick_n"], 100);
+        assert!(body["data_tick_override"].is_null());
+    }
+
+    #[tokio::test]
+    async fn openapi_doc_lists_the_api_routes() {
+        let response = router(test_state(AppConfig::for_tests()))
+            .oneshot(
+                axum::http::Request::get("/api/openapi.json")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let doc = json_body(response).await;
+        for path in [
+            "/api/settings",
+            "/api/channel",
+            "/api/sound",
+            "/api/events",
+            "/api/interfaces",
+            "/api/interface",
+            "/api/devices",
+            "/api/device-filters",
+            "/api/device-reset",
+            "/api/locate",
+            "/api/sessions",
+            "/api/shutdown",
+            "/ws/packets",
+            "/ws/devices",
+            "/ws/meter",
+        ] {
+            assert!(doc["paths"].get(path).is_some(), "{path} missing");
+        }
+        assert!(
+            doc["components"]["schemas"]
+                .get("SettingsResponse")
+                .is_some()
+        );
+        assert!(
+            doc["components"]["schemas"]
+                .get("DevicesResponse")
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn responses_carry_a_request_id() {
+        let app = router(test_state(AppConfig::for_tests()));
+        let get_settings = || axum::http::Request::get("/api/settings");
+
+        let response = app
+            .clone()
+            .oneshot(get_settings().body(axum::body::Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let generated = response.headers().get("x-request-id").expect("id header");
+        assert!(!generated.is_empty());
+
+        let response = app
+            .oneshot(
+                get_settings()
+                    .header("x-request-id", "client-42")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers().get("x-request-id"),
+            Some(&HeaderValue::from_static("client-42"))
+        );
+    }
+
+    #[tokio::test]
+    async fn capture_toggles_off_and_on() {
+        let state = test_state(AppConfig::for_tests());
+        let app = router(state.clone());
+        let post = |uri: &str| {
+            axum::http::Request::post(uri)
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        let body = json_body(
+            app.clone()
+                .oneshot(post("/api/stop-capture"))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(
+            body,
+            serde_json::json!({"capturing": false, "audio_jack": false, "web_ui_sound": false})
+        );
+        assert!(!state.sniffer.is_running());
+
+        let body = json_body(
+            app.clone()
+                .oneshot(post("/api/start-capture"))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(
+            body,
+            serde_json::json!({"capturing": true, "audio_jack": true, "web_ui_sound": true})
+        );
+        assert!(state.sniffer.is_running());
+
+        app.oneshot(post("/api/stop-capture")).await.unwrap();
+        assert!(!state.sniffer.is_running());
+    }
+
+    #[test]
+    fn verbose_ws_events_carry_the_role() {
+        // addr2 == BSSID, so the classifier tags the beacon as the AP's.
+        let evt =
+            sniffer::tests::classify(&sniffer::tests::beacon(2437, &[0, 3, b'l', b'a', b'b']));
+        let Some(Message::Text(json)) = packet_message(&evt, false, None) else {
+            panic!("beacon was not sent as JSON");
+        };
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["role"], "ap");
+    }
+
+    #[tokio::test]
+    async fn custom_accent_is_injected_into_the_page() {
+        let mut config = AppConfig::for_tests();
+        config.theme_accent = Some("#12ab34".to_string());
+        let response = router(test_state(config))
+            .oneshot(
+                axum::http::Request::get("/")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("--accent: #12ab34;"));
+    }
+
+    #[tokio::test]
+    async fn bad_channel_requests_get_a_json_error() {
+        let response = router(test_state(AppConfig::for_tests()))
+            .oneshot(post_json(
+                "/api/channel",
+                r#"{"channel":"six"}"#.to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = json_body(response).await;
+        assert_eq!(body["code"], "unprocessable_entity");
+        assert!(body["error"].as_str().is_some_and(|e| !e.is_empty()));
+    }
+
+    fn devices_query(window_seconds: Option<u64>, window_minutes: Option<u64>) -> DevicesQuery {
+        DevicesQuery {
+            window_seconds,
+            window_minutes,
+            min_frames: None,
+            filter: None,
+            group_by: None,
+        }
+    }
+
+    #[test]
+    fn window_from_query_prefers_seconds_and_clamps() {
+        assert_eq!(window_from_query(&devices_query(None, None)), 600);
+        assert_eq!(window_from_query(&devices_query(Some(900), Some(1))), 900);
+        assert_eq!(window_from_query(&devices_query(Some(90), None)), 90);
+        assert_eq!(window_from_query(&devices_query(None, Some(5))), 300);
+        assert_eq!(window_from_query(&devices_query(Some(10), None)), 60);
+        assert_eq!(
+            window_from_query(&devices_query(None, Some(u64::MAX))),
+            7200
+        );
+    }
+
+    #[test]
+    fn failed_channel_reads_keep_the_last_known_channel() {
+        let failed = || Err(anyhow::anyhow!("iw exited with status 1"));
+        assert_eq!(
+            keep_last_channel(Some(6), failed(), "wlan1mon").unwrap(),
+            Some(6)
+        );
+        assert_eq!(
+            keep_last_channel(Some(6), Ok(None), "wlan1mon").unwrap(),
+            Some(6)
+        );
+        assert_eq!(
+            keep_last_channel(Some(6), Ok(Some(11)), "wlan1mon").unwrap(),
+            Some(11)
+        );
+        // With nothing to fall back on, the error surfaces.
+        assert!(keep_last_channel(None, failed(), "wlan1mon").is_err());
+        assert_eq!(keep_last_channel(None, Ok(None), "wlan1mon").unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn channel_presets_are_looked_up_and_checked() {
+        let mut config = AppConfig::for_tests();
+        config.channel_presets = vec![1, 6, 165];
+        let state = test_state(config);
+        *state.channels_24.write().await = (1..=11)
+            .map(|channel| ChannelInfo::new(Band::Ghz24, channel, true))
+            .collect();
+        *state.channels_5.write().await = vec![ChannelInfo::new(Band::Ghz5, 165, false)];
+
+        assert_eq!(preset_channel(&state, 1).await.unwrap(), 6);
+        let preset = |index: usize| {
+            set_channel_preset(
+                State(state.clone()),
+                ApiJson(ChannelPresetRequest { index }),
+            )
+        };
+        let Err(err) = preset(3).await else {
+            panic!("out-of-range preset was accepted");
+        };
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+        let Err(err) = preset(2).await else {
+            panic!("disabled channel was accepted");
+        };
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        assert_eq!(state.channel.tuned(), None);
+
+        // Selecting a preset retunes the radio to it.
+        let mut applied = None;
+        let tuned = tune_preset(&state, 1, async |_interface: &str, channel| {
+            applied = Some(channel);
+            Ok(())
+        })
+        .await;
+        assert_eq!(tuned.ok(), Some(6));
+        assert_eq!(applied, Some(6));
+        assert_eq!(state.channel.tuned(), Some(6));
+    }
+
+    #[tokio::test]
+    async fn live_channel_reflects_a_changed_channel() {
+        assert_eq!(
+            iw_channel(
+                "Interface wlan1mon\n\ttype monitor\n\tchannel 52 (5260 MHz), width: 20 MHz\n"
+            ),
+            Some(52)
+        );
+        assert_eq!(iw_channel("\ttype monitor\n"), None);
+
+        let state = test_state(AppConfig::for_tests());
+        *state.channel.current.write().await = Some(6);
+        *state.channel.last_refresh.write().await = Some(Instant::now());
+        let Ok(response) = live_channel(State(state.clone())).await else {
+            panic!("live channel failed");
+        };
+        assert_eq!(json_body(response).await["channel"], 6);
+
+        // A DFS move picked up by the last `iw` read shows up on the next GET.
+        *state.channel.current.write().await = Some(52);
+        let Ok(response) = live_channel(State(state)).await else {
+            panic!("live channel failed");
+        };
+        assert_eq!(json_body(response).await["channel"], 52);
+    }
+
+    #[test]
+    fn power_allowed_admits_loopback_and_configured_networks() {
+        let mut config = AppConfig::for_tests();
+        config.power_allowed_cidrs.clear();
+        let peer = |addr: &str| addr.parse::<SocketAddr>().unwrap();
+        assert!(power_allowed(&config, peer("127.0.0.1:5000")));
+        assert!(power_allowed(&config, peer("[::1]:5000")));
+        assert!(power_allowed(&config, peer("[::ffff:127.0.0.1]:5000")));
+        assert!(!power_allowed(&config, peer("192.168.1.20:5000")));
+
+        config.power_allowed_cidrs = vec![IpCidr::parse("192.168.1.0/24").unwrap()];
+        assert!(power_allowed(&config, peer("192.168.1.20:5000")));
+        assert!(!power_allowed(&config, peer("10.0.0.1:5000")));
+    }
+
+    #[tokio::test]
+    async fn remote_shutdown_is_forbidden() {
+        let mut config = AppConfig::for_tests();
+        config.power_allowed_cidrs.clear();
+        let state = test_state(config);
+        // Unconfirmed, so a loopback caller stops at the 400 instead of powering off.
+        let request = |addr: &str| {
+            shutdown(
+                State(state.clone()),
+                ConnectInfo(addr.parse().unwrap()),
+                HeaderMap::new(),
+                ApiJson(ShutdownRequest { confirm: false }),
+            )
+        };
+        let Err(err) = request("192.168.1.20:5000").await else {
+            panic!("remote shutdown was accepted");
+        };
+        assert_eq!(err.status, StatusCode::FORBIDDEN);
+        let Err(err) = request("127.0.0.1:5000").await else {
+            panic!("unconfirmed shutdown was accepted");
+        };
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("home"), "home");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("=HYPERLINK(1)"), "'=HYPERLINK(1)");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("-2+3"), "'-2+3");
+        assert_eq!(csv_field("+1,2"), "\"'+1,2\"");
+        assert_eq!(csv_field("a=b"), "a=b");
+    }
+
+    #[tokio::test]
+    async fn mute_ssids_round_trip_through_the_events_api() {
+        let state = test_state(AppConfig::for_tests());
+        let body = serde_json::from_str(r#"{"mute_ssids":["home","","guest"]}"#).unwrap();
+        let Ok(response) = update_events(State(state.clone()), ApiJson(body)).await else {
+            panic!("update failed");
+        };
+        assert_eq!(
+            json_body(response).await["mute_ssids"],
+            serde_json::json!(["home", "guest"])
+        );
+        assert_eq!(
+            state.event_settings.read().await.mute_ssids,
+            ["home", "guest"]
+        );
+    }
+
+    #[tokio::test]
+    async fn merge_assoc_family_round_trips_through_the_events_api() {
+        let state = test_state(AppConfig::for_tests());
+        let Ok(response) = events_settings(State(state.clone())).await else {
+            panic!("settings failed");
+        };
+        assert_eq!(json_body(response).await["merge_assoc_family"], true);
+
+        let body = serde_json::from_str(r#"{"merge_assoc_family":false}"#).unwrap();
+        let Ok(response) = update_events(State(state.clone()), ApiJson(body)).await else {
+            panic!("update failed");
+        };
+        assert_eq!(json_body(response).await["merge_assoc_family"], false);
+        assert!(!state.event_settings.read().await.merge_assoc_family);
+
+        // Fields left out of an update keep their value.
+        let body = serde_json::from_str(r#"{"data_tick_n":5}"#).unwrap();
+        assert!(
+            update_events(State(state.clone()), ApiJson(body))
+                .await
+                .is_ok()
+        );
+        let Ok(response) = events_settings(State(state.clone())).await else {
+            panic!("settings failed");
+        };
+        assert_eq!(json_body(response).await["merge_assoc_family"], false);
+    }
+
+    #[tokio::test]
+    async fn reset_all_empties_the_snapshot() {
+        let state = test_state(AppConfig::for_tests());
+        let blocked = [0x02, 0, 0, 0, 0, 0x01];
+        state.device_tracker.observe(Observation {
+            mac: [0x02, 0, 0, 0, 0, 0x02],
+            bssid: Some(blocked),
+            role: DeviceRole::Client,
+            rssi_dbm: Some(-50),
+            channel: Some(6),
+            ..Observation::default()
+        });
+        state.device_tracker.set_many(&[(blocked, false)]);
+        assert!(
+            !state
+                .device_tracker
+                .snapshot(Duration::from_secs(60), 0)
+                .is_empty()
+        );
+
+        let request = |confirm, keep_filters| {
+            reset_all(
+                State(state.clone()),
+                ApiJson(ResetAllRequest {
+                    confirm,
+                    keep_filters,
+                }),
+            )
+        };
+        assert!(request(false, true).await.is_err());
+        assert!(request(true, true).await.is_ok());
+        assert!(
+            state
+                .device_tracker
+                .snapshot(Duration::from_secs(60), 0)
+                .is_empty()
+        );
+        assert!(state.reset_requested.load(Ordering::Relaxed));
+        assert!(!state.device_tracker.allows(None, Some(blocked)));
+
+        assert!(request(true, false).await.is_ok());
+        assert!(state.device_tracker.allows(None, Some(blocked)));
+    }
+
+    #[test]
+    fn channel_numbers_are_told_apart_by_band_and_frequency() {
+        let info = "\
+            Frequencies:\n\
+            \t\t\t* 2412.0 MHz [1] (20.0 dBm)\n\
+            \t\t\t* 2484.0 MHz [14] (disabled)\n\
+            \t\t\t* 5180.0 MHz [36] (23.0 dBm)\n\
+            \t\t\t* 5955.0 MHz [1] (12.0 dBm)\n\
+            \t\t\t* 5975.0 MHz [5] (12.0 dBm)\n";
+        let channels = parse_supported_channels(info);
+        let find = |list: &[ChannelInfo], channel| {
+            list.iter()
+                .find(|info| info.channel == channel)
+                .map(|info| (info.band, info.frequency_mhz, info.enabled))
+        };
+        assert_eq!(find(&channels.ghz24, 1), Some((Band::Ghz24, 2412, true)));
+        assert_eq!(find(&channels.ghz24, 14), Some((Band::Ghz24, 2484, false)));
+        assert_eq!(find(&channels.ghz5, 36), Some((Band::Ghz5, 5180, true)));
+        assert_eq!(find(&channels.ghz6, 1), Some((Band::Ghz6, 5955, true)));
+        assert_eq!(channels.ghz6.len(), 2);
+        assert_eq!(Band::Ghz6.frequency_mhz(1), 5955);
+        assert_eq!(Band::Ghz24.frequency_mhz(1), 2412);
+    }
+
+    #[tokio::test]
+    async fn a_6ghz_band_filter_is_rejected() {
+        let state = test_state(AppConfig::for_tests());
+        let body = serde_json::from_str(r#"{"band_filter":"6ghz"}"#).unwrap();
+        let Err(err) = update_events(State(state.clone()), ApiJson(body)).await else {
+            panic!("6 GHz filter was accepted");
+        };
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        assert_eq!(state.event_settings.read().await.band_filter, None);
+    }
+
+    #[tokio::test]
+    async fn audio_pauses_are_bounded_and_zero_resumes() {
+        let state = test_state(AppConfig::for_tests());
+        let pause = |seconds: u64| {
+            audio_pause(State(state.clone()), ApiJson(AudioPauseRequest { seconds }))
+        };
+
+        let Ok(response) = pause(300).await else {
+            panic!("five-minute pause was rejected");
+        };
+        let remaining = json_body(response).await["remaining_ms"].as_u64().unwrap();
+        assert!((299_000..=300_000).contains(&remaining), "{remaining} ms");
+
+        let Err(err) = pause(301).await else {
+            panic!("pause past the limit was accepted");
+        };
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        assert!(state.audio.pause_remaining() > Duration::ZERO);
+
+        let Ok(response) = pause(0).await else {
+            panic!("resume was rejected");
+        };
+        assert_eq!(json_body(response).await["remaining_ms"], 0);
+        assert_eq!(state.audio.pause_remaining(), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn event_test_plays_each_enabled_kind_once() {
+        let state = test_state(AppConfig::for_tests());
+        {
+            let mut settings = state.event_settings.write().await;
+            for enabled in settings.enabled.values_mut() {
+                *enabled = false;
+            }
+            for kind in [EventKind::Beacon, EventKind::Deauth, EventKind::Ack] {
+                settings.enabled.insert(kind, true);
+            }
+        }
+        let Ok(response) = test_events(State(state.clone())).await else {
+            panic!("event test failed");
+        };
+        let body = json_body(response).await;
+        assert_eq!(
+            body["kinds"],
+            serde_json::json!(["beacon", "deauth", "ack"])
+        );
+        // Three onsets 300 ms apart, plus the last sound's own length.
+        let duration = body["duration_ms"].as_u64().unwrap();
+        assert!((600..900).contains(&duration), "{duration} ms");
+
+        state.audio_enabled.store(false, Ordering::Relaxed);
+        let Err(err) = test_events(State(state)).await else {
+            panic!("played with audio off");
+        };
+        assert_eq!(err.status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn tuned_channel_mirrors_the_cache_without_awaiting() {
+        let channel = ChannelController::new("radioscope-test-missing0".into());
+        assert_eq!(channel.tuned(), None);
+        channel.store(&mut *channel.current.write().await, Some(6));
+        assert_eq!(channel.tuned(), Some(6));
+        channel
+            .set_interface("radioscope-test-missing1".into())
+            .await;
+        assert_eq!(channel.tuned(), None);
+    }
+
+    #[tokio::test]
+    async fn channel_switches_are_announced_once_per_change() {
+        let channel = ChannelController::new("radioscope-test-missing0".into());
+        let mut changes = channel.subscribe_changes();
+        channel.switched_to(6).await;
+        channel.switched_to(6).await;
+        channel.switched_to(11).await;
+        assert_eq!(changes.try_recv().unwrap(), 6);
+        assert_eq!(changes.try_recv().unwrap(), 11);
+        assert!(changes.try_recv().is_err());
+        assert_eq!(channel.tuned(), Some(11));
+    }
+
+    #[tokio::test]
+    async fn device_listings_are_gzipped_when_accepted() {
+        let fetch = |compression: bool, encoding: Option<&'static str>| async move {
+            let mut config = AppConfig::for_tests();
+            config.http_compression = compression;
+            let state = test_state(config);
+            for last in 0..20 {
+                state.device_tracker.observe(Observation {
+                    mac: [0x02, 0, 0, 0, 0, last],
+                    role: DeviceRole::Client,
+                    rssi_dbm: Some(-60),
+                    channel: Some(6),
+                    ..Observation::default()
+                });
+            }
+            let mut request = axum::http::Request::get("/api/devices");
+            if let Some(encoding) = encoding {
+                request = request.header("accept-encoding", encoding);
+            }
+            let response = router(state)
+                .oneshot(request.body(axum::body::Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            response.headers().get("content-encoding").cloned()
+        };
+
+        assert_eq!(fetch(true, Some("gzip")).await.unwrap(), "gzip");
+        assert_eq!(fetch(true, None).await, None);
+        assert_eq!(fetch(false, Some("gzip")).await, None);
+    }
+
+    #[tokio::test]
+    async fn pretty_query_indents_only_json_responses() {
+        let app = router(test_state(AppConfig::for_tests()));
+        let fetch = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        axum::http::Request::get(uri)
+                            .body(axum::body::Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                String::from_utf8(bytes.to_vec()).unwrap()
+            }
+        };
+
+        let compact = fetch("/api/settings").await;
+        let pretty = fetch("/api/settings?pretty=1").await;
+        assert!(!compact.contains('\n'));
+        assert!(pretty.starts_with("{\n  \""), "{pretty}");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&pretty).unwrap(),
+            serde_json::from_str::<serde_json::Value>(&compact).unwrap()
+        );
+
+        assert_eq!(fetch("/metrics?pretty=1").await, fetch("/metrics").await);
+    }
+
+    #[tokio::test]
+    async fn aliases_show_in_snapshots_and_survive_a_restart() {
+        let path = std::env::temp_dir().join(format!(
+            "radioscope-aliases-test-{}.json.tmp",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let mut config = AppConfig::for_tests();
+        config.device_aliases = Some(path.to_string_lossy().into_owned());
+        let state = test_state(config);
+        let mac = [0x02, 0, 0, 0, 0, 1];
+        state.device_tracker.observe(Observation {
+            mac,
+            role: DeviceRole::Client,
+            ..Observation::default()
+        });
+
+        let body = serde_json::json!({"mac": "02:00:00:00:00:01", "name": " printer "});
+        let Ok(response) = device_alias(
+            State(state.clone()),
+            ApiJson(serde_json::from_value(body).unwrap()),
+        )
+        .await
+        else {
+            panic!("alias rejected");
+        };
+        let body = json_body(response).await;
+        assert_eq!(body["name"], "printer");
+        assert_eq!(body["persisted"], true);
+        let snapshot = state.device_tracker.snapshot(Duration::from_secs(60), 0);
+        assert_eq!(snapshot[0].alias.as_deref(), Some("printer"));
+
+        let restarted = DeviceTracker::new(0, None, 0);
+        let loaded = restarted.load_aliases(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded.unwrap(), 1);
+        restarted.observe(Observation {
+            mac,
+            role: DeviceRole::Client,
+            ..Observation::default()
+        });
+        let snapshot = restarted.snapshot(Duration::from_secs(60), 0);
+        assert_eq!(snapshot[0].alias.as_deref(), Some("printer"));
+    }
+
+    #[tokio::test]
+    async fn alias_count_is_capped() {
+        let state = test_state(AppConfig::for_tests());
+        for n in 0..devices::MAX_ALIASES {
+            let mac = [0x02, 0, 0, 0, (n >> 8) as u8, n as u8];
+            assert!(state.device_tracker.set_alias(mac, Some(format!("d{n}"))));
+        }
+        let alias = |mac: &str| {
+            let body = serde_json::json!({"mac": mac, "name": "one more"});
+            device_alias(
+                State(state.clone()),
+                ApiJson(serde_json::from_value(body).unwrap()),
+            )
+        };
+        let Err(err) = alias("02:00:00:00:ff:ff").await else {
+            panic!("alias past the cap was accepted");
+        };
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        // Renaming a device that already has an alias still works.
+        assert!(alias("02:00:00:00:00:00").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn refused_shutdowns_are_audited_with_the_peer() {
+        let path = std::env::temp_dir().join(format!(
+            "radioscope-audit-test-{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let mut config = AppConfig::for_tests();
+        config.power_allowed_cidrs.clear();
+        config.power_audit_log = Some(path.to_string_lossy().into_owned());
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "audit-7".parse().unwrap());
+
+        let result = shutdown(
+            State(test_state(config)),
+            ConnectInfo("192.168.1.20:5000".parse().unwrap()),
+            headers,
+            ApiJson(ShutdownRequest { confirm: true }),
+        )
+        .await;
+        assert!(result.is_err());
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let entry: serde_json::Value = serde_json::from_str(log.trim()).unwrap();
+        assert_eq!(entry["action"], "shutdown");
+        assert_eq!(entry["outcome"], "refused");
+        assert_eq!(entry["peer"], "192.168.1.20:5000");
+        assert_eq!(entry["request_id"], "audit-7");
+        assert!(entry["unix_ms"].as_u64().is_some_and(|ms| ms > 0));
+    }
+}